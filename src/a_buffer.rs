@@ -0,0 +1,143 @@
+use crate::color::LinearRgb;
+use crate::depth_buffer::DepthBuffer;
+use crate::draw_modes::blend::blend::{blend_pixel, BlendState};
+use crate::hdr_target::HdrTarget;
+
+pub const DEFAULT_MAX_LAYERS: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayerFragment {
+    pub depth: f64,
+    pub color: LinearRgb,
+    pub alpha: f64,
+    pub blend: BlendState,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ABufferStats {
+    pub fragments: usize,
+    // Pixels that were offered more fragments than they have layers for.
+    pub overflowed_pixels: usize,
+    pub dropped_fragments: usize,
+    pub max_layers_used: usize,
+}
+
+// Per-pixel fragment lists for transparency that does not depend on draw order. Each
+// list holds at most `max_layers` fragments; once full, a new fragment replaces the
+// farthest one if it is nearer and is dropped otherwise, so the layers that survive are
+// the ones nearest the viewer.
+#[derive(Clone, Debug)]
+pub struct ABuffer {
+    width: usize,
+    height: usize,
+    max_layers: usize,
+    lists: Vec<Vec<LayerFragment>>,
+    overflowed: Vec<bool>,
+    stats: ABufferStats,
+}
+
+impl ABuffer {
+    pub fn new(width: usize, height: usize, max_layers: usize) -> Self {
+        Self {
+            width,
+            height,
+            max_layers: max_layers.max(1),
+            lists: vec![vec![]; width * height],
+            overflowed: vec![false; width * height],
+            stats: ABufferStats::default(),
+        }
+    }
+
+    pub fn get_size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    pub fn get_max_layers(&self) -> usize {
+        self.max_layers
+    }
+
+    pub fn get_stats(&self) -> ABufferStats {
+        self.stats
+    }
+
+    pub fn get_fragments(&self, x: usize, y: usize) -> Option<&[LayerFragment]> {
+        match x < self.width && y < self.height {
+            true => Some(&self.lists[x + y * self.width]),
+            false => None,
+        }
+    }
+
+    pub fn push(&mut self, x: usize, y: usize, fragment: LayerFragment) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+
+        let at = x + y * self.width;
+        let list = &mut self.lists[at];
+
+        self.stats.fragments += 1;
+
+        if list.len() < self.max_layers {
+            list.push(fragment);
+            self.stats.max_layers_used = self.stats.max_layers_used.max(list.len());
+
+            return;
+        }
+
+        if !self.overflowed[at] {
+            self.overflowed[at] = true;
+            self.stats.overflowed_pixels += 1;
+        }
+
+        self.stats.dropped_fragments += 1;
+
+        // Larger depth is nearer, so the farthest fragment has the smallest depth.
+        let (farthest, stored) = list
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.depth.total_cmp(&b.depth))
+            .map(|(i, f)| (i, f.depth))
+            .unwrap();
+
+        if fragment.depth > stored {
+            list[farthest] = fragment;
+        }
+    }
+
+    // Sorts every list far to near and blends it over the target. Fragments behind the
+    // opaque surface stored in `depth_buffer` are skipped.
+    pub fn resolve(&mut self, depth_buffer: &DepthBuffer, target: &mut HdrTarget) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let list = &mut self.lists[x + y * self.width];
+
+                if list.is_empty() {
+                    continue;
+                }
+
+                list.sort_by(|a, b| a.depth.total_cmp(&b.depth));
+
+                for fragment in list.iter() {
+                    if !depth_buffer.is_visible(x, y, fragment.depth, 0.0) {
+                        continue;
+                    }
+
+                    blend_pixel(
+                        target,
+                        x,
+                        y,
+                        fragment.color,
+                        fragment.alpha,
+                        &fragment.blend,
+                    );
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.lists.iter_mut().for_each(Vec::clear);
+        self.overflowed.fill(false);
+        self.stats = ABufferStats::default();
+    }
+}
@@ -0,0 +1,736 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use log::{debug, info};
+use serde::Deserialize;
+
+use crate::camera::OrbitCamera;
+use crate::context::{
+    frame_depth, render_viewer_frame, save_depth, LightPreset, ViewerMode, VIEWER_BACKGROUND,
+};
+use crate::draw_modes::draw_depth::draw_depth::DepthSettings;
+use crate::draw_modes::draw_primitives::draw_primitives::PrimitiveStyle;
+use crate::image_canvas::Canvas;
+use crate::linear_algebra::{compose_affine, Affine, Matrix3, Point3, Quaternion, Transform};
+use crate::presenter::{PresentError, Presenter};
+use crate::wavefront_parser::WavefronObject;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TurntableTarget {
+    // The model spins in front of a fixed viewer, so lights stay put relative to the screen.
+    Model,
+    // The viewer circles the model, so lights stay put relative to the model.
+    Camera,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Turntable {
+    pub frames: usize,
+    pub fps: f64,
+    pub degrees: f64,
+    pub start_yaw: f64,
+    pub pitch: f64,
+    pub zoom: f64,
+    pub target: TurntableTarget,
+}
+
+impl Default for Turntable {
+    fn default() -> Self {
+        Self {
+            frames: 120,
+            fps: 30.0,
+            degrees: 360.0,
+            start_yaw: 0.0,
+            pitch: 0.0,
+            zoom: 1.0,
+            target: TurntableTarget::Model,
+        }
+    }
+}
+
+impl Turntable {
+    // Frame times come from the frame index alone, never from the wall clock, so the same
+    // settings always produce the same sequence.
+    pub fn get_frame_time(&self, frame: usize) -> f64 {
+        frame as f64 / self.fps
+    }
+
+    // A full revolution ends one step short of the start so the sequence loops cleanly.
+    pub fn get_yaw_at(&self, frame: usize) -> f64 {
+        let step = self.degrees / self.frames.max(1) as f64;
+
+        (self.start_yaw + step * frame as f64).to_radians()
+    }
+
+    pub fn get_camera_at(&self, frame: usize) -> OrbitCamera {
+        OrbitCamera {
+            yaw: self.get_yaw_at(frame),
+            pitch: self.pitch.to_radians(),
+            zoom: self.zoom,
+            pan: (0.0, 0.0),
+        }
+    }
+
+    pub fn get_light_at(&self, light: LightPreset, camera: &OrbitCamera) -> LightPreset {
+        match (self.target, light) {
+            (TurntableTarget::Model, LightPreset::World(name, dir)) => {
+                LightPreset::World(name, camera.view_to_world_direction(dir))
+            }
+            (
+                TurntableTarget::Model,
+                LightPreset::Directional {
+                    direction,
+                    intensity,
+                },
+            ) => LightPreset::Directional {
+                direction: camera.view_to_world_direction(direction),
+                intensity,
+            },
+            _ => light,
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn render_turntable<P: Presenter + ?Sized>(
+    presenter: &mut P,
+    model: &WavefronObject,
+    turntable: &Turntable,
+    mode: ViewerMode,
+    light: LightPreset,
+    primitives: &PrimitiveStyle,
+    depth: &DepthSettings,
+    size: (usize, usize),
+) -> Result<usize, PresentError> {
+    let start = Instant::now();
+    let (w, h) = size;
+
+    let mut image = Canvas::new(w, h, VIEWER_BACKGROUND);
+    let mut rendered = 0;
+    let mut last_frame = None;
+
+    for frame in 0..turntable.frames {
+        if !presenter.is_open() {
+            break;
+        }
+
+        let camera = turntable.get_camera_at(frame);
+        let frame_light = turntable.get_light_at(light, &camera);
+
+        let drawn = render_viewer_frame(
+            model,
+            &camera,
+            mode,
+            frame_light,
+            primitives,
+            depth,
+            &mut image,
+        );
+        presenter.present(&image)?;
+        presenter.poll_events();
+
+        last_frame = Some((camera, drawn));
+
+        debug!(
+            frame = frame,
+            time = turntable.get_frame_time(frame),
+            yaw = camera.yaw.to_degrees();
+            "turntable frame"
+        );
+
+        rendered += 1;
+    }
+
+    save_depth(
+        depth,
+        last_frame
+            .filter(|_| depth.out.is_some())
+            .map(|(camera, drawn)| frame_depth(model, &camera, drawn, size)),
+    )?;
+
+    info!(
+        mode = mode.get_name(),
+        frames = rendered,
+        duration = turntable.get_frame_time(rendered),
+        elapsed:? = start.elapsed();
+        "turntable finished"
+    );
+
+    Ok(rendered)
+}
+
+pub const DEFAULT_ANIMATION_FPS: f64 = 30.0;
+pub const DEFAULT_BEZIER_HANDLES: [f64; 4] = [0.42, 0.0, 0.58, 1.0];
+pub const BEZIER_ITERATIONS: usize = 48;
+
+// The interpolation of a key applies to the segment that starts at it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interpolation {
+    Step,
+    Linear,
+    // CSS-style easing curve through (0, 0), (x1, y1), (x2, y2) and (1, 1).
+    Bezier([f64; 4]),
+}
+
+impl Interpolation {
+    pub fn ease(&self, t: f64) -> f64 {
+        match self {
+            Interpolation::Step => 0.0,
+            Interpolation::Linear => t,
+            Interpolation::Bezier([x1, y1, x2, y2]) => {
+                let bezier = |s: f64, p1: f64, p2: f64| {
+                    let r = 1.0 - s;
+
+                    3.0 * r * r * s * p1 + 3.0 * r * s * s * p2 + s * s * s
+                };
+
+                // x(s) is monotonic while x1 and x2 stay inside [0, 1], so bisection
+                // always finds the curve parameter for this time.
+                let (mut lo, mut hi) = (0.0, 1.0);
+
+                for _ in 0..BEZIER_ITERATIONS {
+                    let mid = (lo + hi) / 2.0;
+
+                    if bezier(mid, *x1, *x2) < t {
+                        lo = mid;
+                    } else {
+                        hi = mid;
+                    }
+                }
+
+                bezier((lo + hi) / 2.0, *y1, *y2)
+            }
+        }
+    }
+}
+
+pub trait Interpolate: Copy {
+    fn interpolate(from: Self, to: Self, t: f64) -> Self;
+}
+
+impl Interpolate for f64 {
+    fn interpolate(from: Self, to: Self, t: f64) -> Self {
+        from + (to - from) * t
+    }
+}
+
+impl Interpolate for Point3<f64> {
+    fn interpolate(from: Self, to: Self, t: f64) -> Self {
+        from + (to - from) * t
+    }
+}
+
+impl Interpolate for Quaternion {
+    fn interpolate(from: Self, to: Self, t: f64) -> Self {
+        from.slerp(&to, t)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Keyframe<T> {
+    pub time: f64,
+    pub value: T,
+    pub interpolation: Interpolation,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Track<T> {
+    keys: Vec<Keyframe<T>>,
+}
+
+impl<T> Default for Track<T> {
+    fn default() -> Self {
+        Self { keys: vec![] }
+    }
+}
+
+impl<T: Interpolate> Track<T> {
+    pub fn new(mut keys: Vec<Keyframe<T>>) -> Self {
+        keys.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        Self { keys }
+    }
+
+    pub fn get_keys(&self) -> &[Keyframe<T>] {
+        &self.keys
+    }
+
+    pub fn get_end_time(&self) -> f64 {
+        self.keys.last().map_or(0.0, |k| k.time)
+    }
+
+    // Holds the first value before the first key and the last value after the last one.
+    pub fn sample(&self, t: f64) -> Option<T> {
+        let first = self.keys.first()?;
+        let next = self.keys.partition_point(|k| k.time <= t);
+
+        if next == 0 {
+            return Some(first.value);
+        }
+
+        if next == self.keys.len() {
+            return Some(self.keys[next - 1].value);
+        }
+
+        let (from, to) = (&self.keys[next - 1], &self.keys[next]);
+        let local = (t - from.time) / (to.time - from.time);
+
+        Some(T::interpolate(
+            from.value,
+            to.value,
+            from.interpolation.ease(local),
+        ))
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TransformTrack {
+    pub position: Track<Point3<f64>>,
+    pub rotation: Track<Quaternion>,
+    pub scale: Track<Point3<f64>>,
+}
+
+impl TransformTrack {
+    pub fn sample(&self, t: f64) -> Transform {
+        let rest = Transform::default();
+
+        Transform {
+            translation: self.position.sample(t).unwrap_or(rest.translation),
+            rotation: self.rotation.sample(t).unwrap_or(rest.rotation),
+            scale: self.scale.sample(t).unwrap_or(rest.scale),
+        }
+    }
+
+    pub fn get_end_time(&self) -> f64 {
+        [
+            self.position.get_end_time(),
+            self.rotation.get_end_time(),
+            self.scale.get_end_time(),
+        ]
+        .into_iter()
+        .fold(0.0, f64::max)
+    }
+}
+
+// Directions are in world space, like the world light presets.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LightTrack {
+    pub direction: Track<Point3<f64>>,
+    pub intensity: Track<f64>,
+}
+
+impl LightTrack {
+    pub fn sample(&self, t: f64) -> LightPreset {
+        LightPreset::Directional {
+            direction: self.direction.sample(t).unwrap_or(Point3(0.0, 0.0, -1.0)),
+            intensity: self.intensity.sample(t).unwrap_or(1.0),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.direction.get_keys().is_empty() && self.intensity.get_keys().is_empty()
+    }
+
+    pub fn get_end_time(&self) -> f64 {
+        f64::max(self.direction.get_end_time(), self.intensity.get_end_time())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AnimationPose {
+    pub model: Transform,
+    pub camera: Transform,
+    pub light: LightPreset,
+}
+
+impl AnimationPose {
+    pub fn get_view_transform(&self) -> Affine {
+        compose_affine(self.camera.get_view_affine(), self.model.get_affine())
+    }
+
+    pub fn get_view_light(&self) -> LightPreset {
+        let to_view = self.camera.rotation.get_rotation_matrix().transpose();
+
+        match self.light {
+            LightPreset::World(name, dir) => LightPreset::World(name, to_view * dir),
+            LightPreset::Directional {
+                direction,
+                intensity,
+            } => LightPreset::Directional {
+                direction: to_view * direction,
+                intensity,
+            },
+            LightPreset::Headlight => LightPreset::Headlight,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyframeAnimation {
+    pub fps: f64,
+    pub duration: f64,
+    pub model: TransformTrack,
+    pub camera: TransformTrack,
+    pub light: LightTrack,
+}
+
+impl KeyframeAnimation {
+    pub fn load(path: PathBuf) -> Result<Self, AnimationError> {
+        let text =
+            std::fs::read_to_string(&path).map_err(|err| AnimationError::Io(path.clone(), err))?;
+
+        Self::from_toml_str(&text)
+    }
+
+    pub fn from_toml_str(text: &str) -> Result<Self, AnimationError> {
+        let description: AnimationDescription =
+            toml::from_str(text).map_err(|err| AnimationError::Parse(err.to_string()))?;
+
+        description.build("")
+    }
+
+    // Both ends are rendered, so a two second clip at 30 fps is 61 frames.
+    pub fn get_frame_count(&self) -> usize {
+        (self.duration * self.fps).round() as usize + 1
+    }
+
+    pub fn get_frame_time(&self, frame: usize) -> f64 {
+        frame as f64 / self.fps
+    }
+
+    pub fn evaluate(&self, t: f64) -> AnimationPose {
+        AnimationPose {
+            model: self.model.sample(t),
+            camera: self.camera.sample(t),
+            light: self.light.sample(t),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum AnimationError {
+    Io(PathBuf, io::Error),
+    Parse(String),
+    Invalid { key: String, message: String },
+}
+
+impl fmt::Display for AnimationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnimationError::Io(path, err) => {
+                write!(f, "could not read {}: {}", path.display(), err)
+            }
+            AnimationError::Parse(msg) => write!(f, "{}", msg),
+            AnimationError::Invalid { key, message } => write!(f, "{}: {}", key, message),
+        }
+    }
+}
+
+fn invalid(key: &str, message: &str) -> AnimationError {
+    AnimationError::Invalid {
+        key: key.to_string(),
+        message: message.to_string(),
+    }
+}
+
+pub fn join_key(prefix: &str, name: &str) -> String {
+    match prefix.is_empty() {
+        true => name.to_string(),
+        false => format!("{}.{}", prefix, name),
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InterpolationName {
+    Step,
+    #[default]
+    Linear,
+    Bezier,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(untagged, expecting = "a uniform scale or an [x, y, z] array")]
+pub enum ScaleValue {
+    Uniform(f64),
+    Axes([f64; 3]),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyDescription<T> {
+    pub time: f64,
+    pub value: T,
+    #[serde(default)]
+    pub interpolation: InterpolationName,
+    pub bezier: Option<[f64; 4]>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RotationKeyDescription {
+    pub time: f64,
+    // Yaw, pitch and roll in degrees.
+    pub euler: Option<[f64; 3]>,
+    // w, x, y, z
+    pub quaternion: Option<[f64; 4]>,
+    #[serde(default)]
+    pub interpolation: InterpolationName,
+    pub bezier: Option<[f64; 4]>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TransformTrackDescription {
+    #[serde(default)]
+    pub position: Vec<KeyDescription<[f64; 3]>>,
+    #[serde(default)]
+    pub rotation: Vec<RotationKeyDescription>,
+    #[serde(default)]
+    pub scale: Vec<KeyDescription<ScaleValue>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LightTrackDescription {
+    #[serde(default)]
+    pub direction: Vec<KeyDescription<[f64; 3]>>,
+    #[serde(default)]
+    pub intensity: Vec<KeyDescription<f64>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AnimationDescription {
+    pub fps: Option<f64>,
+    pub duration: Option<f64>,
+    #[serde(default)]
+    pub model: TransformTrackDescription,
+    #[serde(default)]
+    pub camera: TransformTrackDescription,
+    #[serde(default)]
+    pub light: LightTrackDescription,
+}
+
+fn build_interpolation(
+    key: &str,
+    name: InterpolationName,
+    bezier: Option<[f64; 4]>,
+) -> Result<Interpolation, AnimationError> {
+    match (name, bezier) {
+        (InterpolationName::Step, None) => Ok(Interpolation::Step),
+        (InterpolationName::Linear, None) => Ok(Interpolation::Linear),
+        (InterpolationName::Bezier, handles) => {
+            let handles = handles.unwrap_or(DEFAULT_BEZIER_HANDLES);
+
+            match (0.0..=1.0).contains(&handles[0]) && (0.0..=1.0).contains(&handles[2]) {
+                true => Ok(Interpolation::Bezier(handles)),
+                false => Err(invalid(
+                    &join_key(key, "bezier"),
+                    "the x handles must lie in [0, 1]",
+                )),
+            }
+        }
+        (_, Some(_)) => Err(invalid(
+            &join_key(key, "bezier"),
+            "only allowed with interpolation = \"bezier\"",
+        )),
+    }
+}
+
+fn build_track<D, T: Interpolate>(
+    key: &str,
+    keys: Vec<D>,
+    mut build_key: impl FnMut(&str, D) -> Result<Keyframe<T>, AnimationError>,
+) -> Result<Track<T>, AnimationError> {
+    let mut built = Vec::with_capacity(keys.len());
+
+    for (i, description) in keys.into_iter().enumerate() {
+        let key = format!("{}[{}]", key, i);
+        let keyframe = build_key(&key, description)?;
+
+        if !keyframe.time.is_finite() || keyframe.time < 0.0 {
+            return Err(invalid(
+                &join_key(&key, "time"),
+                "must be a non-negative number of seconds",
+            ));
+        }
+
+        built.push(keyframe);
+    }
+
+    Ok(Track::new(built))
+}
+
+fn build_key<V, T>(
+    key: &str,
+    description: KeyDescription<V>,
+    convert: impl Fn(V) -> Result<T, &'static str>,
+) -> Result<Keyframe<T>, AnimationError> {
+    Ok(Keyframe {
+        time: description.time,
+        value: convert(description.value).map_err(|msg| invalid(&join_key(key, "value"), msg))?,
+        interpolation: build_interpolation(key, description.interpolation, description.bezier)?,
+    })
+}
+
+impl TransformTrackDescription {
+    pub fn build(self, key: &str) -> Result<TransformTrack, AnimationError> {
+        let point = |[x, y, z]: [f64; 3]| Ok(Point3(x, y, z));
+
+        Ok(TransformTrack {
+            position: build_track(&join_key(key, "position"), self.position, |k, d| {
+                build_key(k, d, point)
+            })?,
+            rotation: build_track(&join_key(key, "rotation"), self.rotation, |k, d| {
+                let value = match (d.euler, d.quaternion) {
+                    (Some([yaw, pitch, roll]), None) => {
+                        Quaternion::from_euler_degrees(yaw, pitch, roll)
+                    }
+                    (None, Some([w, x, y, z])) if w != 0.0 || x != 0.0 || y != 0.0 || z != 0.0 => {
+                        Quaternion::new(w, x, y, z)
+                    }
+                    (None, Some(_)) => {
+                        return Err(invalid(&join_key(k, "quaternion"), "must not be all zero"))
+                    }
+                    _ => return Err(invalid(k, "needs exactly one of `euler` or `quaternion`")),
+                };
+
+                Ok(Keyframe {
+                    time: d.time,
+                    value,
+                    interpolation: build_interpolation(k, d.interpolation, d.bezier)?,
+                })
+            })?,
+            scale: build_track(&join_key(key, "scale"), self.scale, |k, d| {
+                build_key(k, d, |value| match value {
+                    ScaleValue::Uniform(s) => Ok(Point3(s, s, s)),
+                    ScaleValue::Axes([x, y, z]) => Ok(Point3(x, y, z)),
+                })
+            })?,
+        })
+    }
+}
+
+impl LightTrackDescription {
+    pub fn build(self, key: &str) -> Result<LightTrack, AnimationError> {
+        Ok(LightTrack {
+            direction: build_track(&join_key(key, "direction"), self.direction, |k, d| {
+                build_key(k, d, |[x, y, z]| match x != 0.0 || y != 0.0 || z != 0.0 {
+                    true => Ok(Point3(x, y, z)),
+                    false => Err("a light direction must not be zero"),
+                })
+            })?,
+            intensity: build_track(&join_key(key, "intensity"), self.intensity, |k, d| {
+                build_key(k, d, Ok)
+            })?,
+        })
+    }
+}
+
+impl AnimationDescription {
+    pub fn build(self, key: &str) -> Result<KeyframeAnimation, AnimationError> {
+        let fps = self.fps.unwrap_or(DEFAULT_ANIMATION_FPS);
+
+        if !fps.is_finite() || fps <= 0.0 {
+            return Err(invalid(&join_key(key, "fps"), "must be a positive number"));
+        }
+
+        let model = self.model.build(&join_key(key, "model"))?;
+        let camera = self.camera.build(&join_key(key, "camera"))?;
+        let light = self.light.build(&join_key(key, "light"))?;
+
+        let duration = match self.duration {
+            Some(d) if d.is_finite() && d >= 0.0 => d,
+            Some(_) => {
+                return Err(invalid(
+                    &join_key(key, "duration"),
+                    "must be a non-negative number of seconds",
+                ))
+            }
+            None => [
+                model.get_end_time(),
+                camera.get_end_time(),
+                light.get_end_time(),
+            ]
+            .into_iter()
+            .fold(0.0, f64::max),
+        };
+
+        Ok(KeyframeAnimation {
+            fps,
+            duration,
+            model,
+            camera,
+            light,
+        })
+    }
+}
+
+// Every frame bakes the model and camera transforms into a copy of the mesh, so the draw
+// modes keep working in the identity orbit camera's space.
+pub fn render_keyframes<P: Presenter + ?Sized>(
+    presenter: &mut P,
+    model: &WavefronObject,
+    animation: &KeyframeAnimation,
+    mode: ViewerMode,
+    primitives: &PrimitiveStyle,
+    depth: &DepthSettings,
+    size: (usize, usize),
+) -> Result<usize, PresentError> {
+    let start = Instant::now();
+    let (w, h) = size;
+
+    let camera = OrbitCamera::default();
+    let mut image = Canvas::new(w, h, VIEWER_BACKGROUND);
+    let mut rendered = 0;
+    let mut last_frame = None;
+
+    for frame in 0..animation.get_frame_count() {
+        if !presenter.is_open() {
+            break;
+        }
+
+        let time = animation.get_frame_time(frame);
+        let pose = animation.evaluate(time);
+        let (linear, translation) = pose.get_view_transform();
+        let posed = model.transformed(linear, translation);
+
+        let drawn = render_viewer_frame(
+            &posed,
+            &camera,
+            mode,
+            pose.get_view_light(),
+            primitives,
+            depth,
+            &mut image,
+        );
+        presenter.present(&image)?;
+        presenter.poll_events();
+
+        debug!(frame = frame, time = time; "keyframe frame");
+
+        last_frame = Some((posed, drawn));
+
+        rendered += 1;
+    }
+
+    save_depth(
+        depth,
+        last_frame
+            .filter(|_| depth.out.is_some())
+            .map(|(posed, drawn)| frame_depth(&posed, &camera, drawn, size)),
+    )?;
+
+    info!(
+        mode = mode.get_name(),
+        frames = rendered,
+        duration = animation.duration,
+        elapsed:? = start.elapsed();
+        "keyframe animation finished"
+    );
+
+    Ok(rendered)
+}
@@ -0,0 +1,55 @@
+use crate::linear_algebra::{Matrix3, Point3};
+
+pub const MIN_ZOOM: f64 = 0.05;
+pub const MAX_ZOOM: f64 = 50.0;
+pub const MAX_PITCH: f64 = std::f64::consts::FRAC_PI_2 - 0.01;
+
+// Orthographic orbit around the model origin. Positions are rotated, scaled and then
+// panned in screen-plane units before `from_world_to_screen` maps them to pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OrbitCamera {
+    pub yaw: f64,
+    pub pitch: f64,
+    pub zoom: f64,
+    pub pan: (f64, f64),
+}
+
+impl Default for OrbitCamera {
+    fn default() -> Self {
+        Self {
+            yaw: 0.0,
+            pitch: 0.0,
+            zoom: 1.0,
+            pan: (0.0, 0.0),
+        }
+    }
+}
+
+impl OrbitCamera {
+    pub fn get_rotation(&self) -> Matrix3 {
+        Matrix3::rotation_x(self.pitch) * Matrix3::rotation_y(self.yaw)
+    }
+
+    pub fn transform_point(&self, p: Point3<f64>) -> Point3<f64> {
+        let Point3(x, y, z) = self.get_rotation() * p * self.zoom;
+
+        Point3(x + self.pan.0, y + self.pan.1, z)
+    }
+
+    pub fn view_to_world_direction(&self, view_dir: Point3<f64>) -> Point3<f64> {
+        self.get_rotation().transpose() * view_dir
+    }
+
+    pub fn orbit(&mut self, d_yaw: f64, d_pitch: f64) {
+        self.yaw = (self.yaw + d_yaw) % std::f64::consts::TAU;
+        self.pitch = (self.pitch + d_pitch).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    pub fn pan_by(&mut self, dx: f64, dy: f64) {
+        self.pan = (self.pan.0 + dx, self.pan.1 + dy);
+    }
+
+    pub fn zoom_by(&mut self, factor: f64) {
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+}
@@ -0,0 +1,729 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::animation::{Turntable, TurntableTarget};
+use crate::context::{LightPreset, ViewerMode};
+use crate::draw_modes::draw_depth::draw_depth::{DepthRange, DepthScale, DepthSettings};
+use crate::draw_modes::draw_primitives::draw_primitives::PrimitiveStyle;
+use crate::hdr_target::ToneMapping;
+use crate::model_loader::LoadOptions;
+use crate::post_process::PostDescription;
+use crate::presenter::{
+    FileSequencePresenter, Presenter, RawFramePresenter, TerminalPresenter,
+    DEFAULT_TERMINAL_COLUMNS,
+};
+use crate::texture_sampler::{SamplerSettings, TextureFilter};
+use crate::write_image::ImageFormat;
+
+pub const DEFAULT_SIZE: (usize, usize) = (800, 800);
+
+pub const USAGE: &str = "usage:
+  naqshsmith-renderer [display options]
+  naqshsmith-renderer view <model> [options]
+  naqshsmith-renderer turntable <model> [options]
+  naqshsmith-renderer animate <model> <keyframes.toml> [options]
+  naqshsmith-renderer scene <scene.toml> [options]
+  naqshsmith-renderer export <model> <out.obj> [model options]
+
+models can be Wavefront .obj, glTF 2.0 .gltf/.glb, STL or PLY files. export writes any
+of them as .obj, with a sibling .mtl when the model has materials. Without a command the
+textured z-buffer demo runs.
+
+display options:
+  --size <W>x<H>            framebuffer size (default 800x800, or the scene's canvas)
+  --backend <name>          window, terminal, files or raw (default window, files otherwise)
+  --columns <n>             terminal width in characters (default 80)
+  --out <dir>               output directory for the files backend
+  --format <tga|ppm>        image format for the files backend (default tga)
+  --frames <n>              stop after n frames (turntable length, default 120;
+                            a headless view renders one)
+  --line-width <px>         width of OBJ lines and curves (default 1, or the scene's)
+  --point-size <px>         diameter of OBJ points (default 3, or the scene's)
+  --dither                  dither shaded scenes when quantizing to 8 bits (or the scene's)
+  --tone-mapping <name>     clamp, reinhard or aces for shaded scenes (or the scene's)
+  --exposure <stops>        exposure of shaded scenes before tone mapping (or the scene's)
+  --depth-scale <name>      linear or log gray ramp of the depth mode (or the scene's)
+  --depth-range <n>,<f>     depths mapped to white and black (default the covered range)
+  --depth-out <file.pfm>    save the last frame's depth as a float map (or the scene's)
+  --post <effect[:k=v,...]> post-process scenes, repeatable and run in order, replacing the
+                            scene's [[post]] list; none clears it. effects: box-blur,
+                            gaussian-blur (radius), sharpen (amount, radius), fxaa,
+                            vignette (strength, radius), lut (path, amount),
+                            dof (focus, range, radius)
+  --filter <name>           nearest, bilinear, trilinear or anisotropic texture filtering
+                            (default trilinear, or the scene's)
+  --lod-bias <levels>       added to the mip level, positive is blurrier (or the scene's)
+  --max-anisotropy <n>      most taps of the anisotropic filter (default 8, or the scene's)
+
+model options:
+  --weld <distance>         merge STL vertices closer than this (default 1e-6, 0 for exact)
+  --tessellation <n>        samples per direction for OBJ curves and surfaces (default 16)
+
+turntable and animate options:
+  --mode <name>             flat, overlay, hidden-line, depth, normals, uvs, barycentric, overdraw,
+                            colors
+
+turntable options:
+  --light <name>            headlight, front, top or left (default front)
+  --rotate <model|camera>   spin the model under fixed lights or orbit the camera (default model)
+  --degrees <d>             total rotation (default 360)
+  --fps <f>                 frame rate used for frame timestamps (default 30)
+  --pitch <d>               camera elevation in degrees (default 0)
+  --zoom <z>                camera zoom (default 1)
+
+the raw backend writes packed RGB24 frames, top row first, to stdout.";
+
+#[derive(Debug, PartialEq)]
+pub enum CliError {
+    MissingArgument(&'static str),
+    MissingValue(String),
+    InvalidValue(String, String),
+    UnknownArgument(String),
+    BackendUnavailable(String),
+    BackendFailed(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::MissingArgument(name) => write!(f, "missing argument <{}>", name),
+            CliError::MissingValue(flag) => write!(f, "{} expects a value", flag),
+            CliError::InvalidValue(flag, value) => {
+                write!(f, "invalid value '{}' for {}", value, flag)
+            }
+            CliError::UnknownArgument(arg) => write!(f, "unknown argument '{}'", arg),
+            CliError::BackendUnavailable(name) => {
+                write!(f, "the {} backend is not compiled into this build", name)
+            }
+            CliError::BackendFailed(msg) => write!(f, "could not open display: {}", msg),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Backend {
+    Window,
+    Terminal {
+        columns: usize,
+    },
+    Files {
+        directory: PathBuf,
+        format: ImageFormat,
+    },
+    Raw,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DisplayOptions {
+    pub size: (usize, usize),
+    pub backend: Backend,
+    pub max_frames: Option<usize>,
+    // None leaves the default, or the scene's setting, in place.
+    pub line_width: Option<f64>,
+    pub point_size: Option<f64>,
+    pub dither: bool,
+    pub tone_mapping: Option<ToneMapping>,
+    pub exposure: Option<f64>,
+    pub post: Option<Vec<PostDescription>>,
+    pub depth_scale: Option<DepthScale>,
+    pub depth_range: Option<DepthRange>,
+    pub depth_out: Option<PathBuf>,
+    pub filter: Option<TextureFilter>,
+    pub lod_bias: Option<f64>,
+    pub max_anisotropy: Option<usize>,
+}
+
+impl Default for DisplayOptions {
+    fn default() -> Self {
+        Self {
+            size: DEFAULT_SIZE,
+            backend: Backend::Window,
+            max_frames: None,
+            line_width: None,
+            point_size: None,
+            dither: false,
+            tone_mapping: None,
+            exposure: None,
+            post: None,
+            depth_scale: None,
+            depth_range: None,
+            depth_out: None,
+            filter: None,
+            lod_bias: None,
+            max_anisotropy: None,
+        }
+    }
+}
+
+impl DisplayOptions {
+    pub fn get_primitive_style(&self) -> PrimitiveStyle {
+        let default = PrimitiveStyle::default();
+
+        PrimitiveStyle {
+            line_width: self.line_width.unwrap_or(default.line_width),
+            point_size: self.point_size.unwrap_or(default.point_size),
+            ..default
+        }
+    }
+
+    pub fn get_depth_settings(&self) -> DepthSettings {
+        DepthSettings {
+            scale: self.depth_scale.unwrap_or_default(),
+            range: self.depth_range,
+            out: self.depth_out.clone(),
+        }
+    }
+
+    // Applied over `base`, which is the default or the scene's sampler.
+    pub fn get_sampler_settings(&self, base: SamplerSettings) -> SamplerSettings {
+        SamplerSettings {
+            filter: self.filter.unwrap_or(base.filter),
+            lod_bias: self.lod_bias.unwrap_or(base.lod_bias),
+            max_anisotropy: self.max_anisotropy.unwrap_or(base.max_anisotropy),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Demo {
+        display: DisplayOptions,
+    },
+    View {
+        model_path: PathBuf,
+        load_options: LoadOptions,
+        display: DisplayOptions,
+    },
+    Turntable {
+        model_path: PathBuf,
+        load_options: LoadOptions,
+        display: DisplayOptions,
+        turntable: Turntable,
+        mode: ViewerMode,
+        light: LightPreset,
+    },
+    Animate {
+        model_path: PathBuf,
+        load_options: LoadOptions,
+        animation_path: PathBuf,
+        display: DisplayOptions,
+        mode: ViewerMode,
+    },
+    Scene {
+        scene_path: PathBuf,
+        display: DisplayOptions,
+        size_override: bool,
+    },
+    Export {
+        model_path: PathBuf,
+        load_options: LoadOptions,
+        out_path: PathBuf,
+    },
+}
+
+pub struct ArgCursor<'a> {
+    args: &'a [String],
+    position: usize,
+}
+
+impl<'a> ArgCursor<'a> {
+    pub fn new(args: &'a [String]) -> Self {
+        Self { args, position: 0 }
+    }
+
+    pub fn next_arg(&mut self) -> Option<&'a str> {
+        let arg = self.args.get(self.position)?;
+        self.position += 1;
+
+        Some(arg.as_str())
+    }
+
+    pub fn value_for(&mut self, flag: &str) -> Result<&'a str, CliError> {
+        self.next_arg()
+            .ok_or_else(|| CliError::MissingValue(flag.to_string()))
+    }
+
+    pub fn parsed_value_for<T: FromStr>(&mut self, flag: &str) -> Result<T, CliError> {
+        let value = self.value_for(flag)?;
+
+        value
+            .parse::<T>()
+            .map_err(|_| CliError::InvalidValue(flag.to_string(), value.to_string()))
+    }
+
+    // NaN and the infinities are rejected along with whatever fails `valid`.
+    pub fn finite_value_for(
+        &mut self,
+        flag: &str,
+        valid: impl Fn(f64) -> bool,
+    ) -> Result<f64, CliError> {
+        let value = self.value_for(flag)?;
+
+        value
+            .parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite() && valid(*v))
+            .ok_or_else(|| CliError::InvalidValue(flag.to_string(), value.to_string()))
+    }
+}
+
+pub fn parse_size(flag: &str, value: &str) -> Result<(usize, usize), CliError> {
+    let invalid = || CliError::InvalidValue(flag.to_string(), value.to_string());
+
+    let (w, h) = value.split_once('x').ok_or_else(invalid)?;
+    let (w, h) = (
+        w.parse::<usize>().map_err(|_| invalid())?,
+        h.parse::<usize>().map_err(|_| invalid())?,
+    );
+
+    match w > 0 && h > 0 {
+        true => Ok((w, h)),
+        false => Err(invalid()),
+    }
+}
+
+// Returns Ok(false) when `flag` is not a display option so callers can try their own.
+pub fn parse_display_flag(
+    flag: &str,
+    cursor: &mut ArgCursor,
+    display: &mut DisplayOptions,
+) -> Result<bool, CliError> {
+    match flag {
+        "--size" => display.size = parse_size(flag, cursor.value_for(flag)?)?,
+        "--frames" => display.max_frames = Some(cursor.parsed_value_for(flag)?),
+        "--dither" => display.dither = true,
+        "--tone-mapping" => {
+            let name = cursor.value_for(flag)?;
+
+            display.tone_mapping = Some(
+                ToneMapping::from_name(name)
+                    .ok_or_else(|| CliError::InvalidValue(flag.to_string(), name.to_string()))?,
+            );
+        }
+        "--exposure" => {
+            let value = cursor.value_for(flag)?;
+            let stops = value
+                .parse::<f64>()
+                .ok()
+                .filter(|s| s.is_finite())
+                .ok_or_else(|| CliError::InvalidValue(flag.to_string(), value.to_string()))?;
+
+            display.exposure = Some(stops);
+        }
+        "--depth-scale" => {
+            let name = cursor.value_for(flag)?;
+
+            display.depth_scale = Some(
+                DepthScale::from_name(name)
+                    .ok_or_else(|| CliError::InvalidValue(flag.to_string(), name.to_string()))?,
+            );
+        }
+        "--depth-range" => {
+            let value = cursor.value_for(flag)?;
+            let invalid = || CliError::InvalidValue(flag.to_string(), value.to_string());
+
+            let (near, far) = value.split_once(',').ok_or_else(invalid)?;
+            let (near, far) = (
+                near.parse::<f64>().map_err(|_| invalid())?,
+                far.parse::<f64>().map_err(|_| invalid())?,
+            );
+
+            if !near.is_finite() || !far.is_finite() || near == far {
+                return Err(invalid());
+            }
+
+            display.depth_range = Some(DepthRange { near, far });
+        }
+        "--depth-out" => display.depth_out = Some(PathBuf::from(cursor.value_for(flag)?)),
+        "--filter" => {
+            let name = cursor.value_for(flag)?;
+
+            display.filter = Some(
+                TextureFilter::from_name(name)
+                    .ok_or_else(|| CliError::InvalidValue(flag.to_string(), name.to_string()))?,
+            );
+        }
+        "--lod-bias" => {
+            let value = cursor.value_for(flag)?;
+            let levels = value
+                .parse::<f64>()
+                .ok()
+                .filter(|l| l.is_finite())
+                .ok_or_else(|| CliError::InvalidValue(flag.to_string(), value.to_string()))?;
+
+            display.lod_bias = Some(levels);
+        }
+        "--max-anisotropy" => {
+            let value = cursor.value_for(flag)?;
+            let taps = value
+                .parse::<usize>()
+                .ok()
+                .filter(|t| *t > 0)
+                .ok_or_else(|| CliError::InvalidValue(flag.to_string(), value.to_string()))?;
+
+            display.max_anisotropy = Some(taps);
+        }
+        "--post" => {
+            let spec = cursor.value_for(flag)?;
+            let post = display.post.get_or_insert_with(Vec::new);
+
+            match spec {
+                "none" => post.clear(),
+                _ => post.push(
+                    PostDescription::parse_spec(spec)
+                        .map_err(|_| CliError::InvalidValue(flag.to_string(), spec.to_string()))?,
+                ),
+            }
+        }
+        "--line-width" | "--point-size" => {
+            let value = cursor.value_for(flag)?;
+            let pixels = value
+                .parse::<f64>()
+                .ok()
+                .filter(|p| p.is_finite() && *p >= 1.0)
+                .ok_or_else(|| CliError::InvalidValue(flag.to_string(), value.to_string()))?;
+
+            match flag {
+                "--line-width" => display.line_width = Some(pixels),
+                _ => display.point_size = Some(pixels),
+            }
+        }
+        "--backend" => {
+            let name = cursor.value_for(flag)?;
+
+            display.backend = match name {
+                "window" => Backend::Window,
+                "terminal" => Backend::Terminal {
+                    columns: DEFAULT_TERMINAL_COLUMNS,
+                },
+                "files" => Backend::Files {
+                    directory: PathBuf::from("frames"),
+                    format: ImageFormat::Tga,
+                },
+                "raw" => Backend::Raw,
+                _ => return Err(CliError::InvalidValue(flag.to_string(), name.to_string())),
+            };
+        }
+        "--columns" => {
+            let value: usize = cursor.parsed_value_for(flag)?;
+
+            display.backend = Backend::Terminal { columns: value };
+        }
+        "--out" => {
+            let directory = PathBuf::from(cursor.value_for(flag)?);
+
+            display.backend = match &display.backend {
+                Backend::Files { format, .. } => Backend::Files {
+                    directory,
+                    format: *format,
+                },
+                _ => Backend::Files {
+                    directory,
+                    format: ImageFormat::Tga,
+                },
+            };
+        }
+        "--format" => {
+            let name = cursor.value_for(flag)?;
+            let format = match name {
+                "tga" => ImageFormat::Tga,
+                "ppm" => ImageFormat::Ppm,
+                _ => return Err(CliError::InvalidValue(flag.to_string(), name.to_string())),
+            };
+
+            display.backend = match &display.backend {
+                Backend::Files { directory, .. } => Backend::Files {
+                    directory: directory.clone(),
+                    format,
+                },
+                _ => Backend::Files {
+                    directory: PathBuf::from("frames"),
+                    format,
+                },
+            };
+        }
+        _ => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+// Same contract as `parse_display_flag`, for options that change how the model is read.
+pub fn parse_load_flag(
+    flag: &str,
+    cursor: &mut ArgCursor,
+    load_options: &mut LoadOptions,
+) -> Result<bool, CliError> {
+    match flag {
+        "--weld" => {
+            let value = cursor.value_for(flag)?;
+
+            load_options.weld_tolerance = value
+                .parse::<f64>()
+                .ok()
+                .filter(|t| *t >= 0.0)
+                .ok_or_else(|| CliError::InvalidValue(flag.to_string(), value.to_string()))?;
+        }
+        "--tessellation" => {
+            let value = cursor.value_for(flag)?;
+
+            load_options.tessellation = value
+                .parse::<usize>()
+                .ok()
+                .filter(|n| *n > 0)
+                .ok_or_else(|| CliError::InvalidValue(flag.to_string(), value.to_string()))?;
+        }
+        _ => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+pub fn parse_mode(flag: &str, cursor: &mut ArgCursor) -> Result<ViewerMode, CliError> {
+    let name = cursor.value_for(flag)?;
+
+    ViewerMode::from_name(name)
+        .ok_or_else(|| CliError::InvalidValue(flag.to_string(), name.to_string()))
+}
+
+pub fn sequence_display_options() -> DisplayOptions {
+    DisplayOptions {
+        backend: Backend::Files {
+            directory: PathBuf::from("frames"),
+            format: ImageFormat::Tga,
+        },
+        ..DisplayOptions::default()
+    }
+}
+
+pub fn parse_args(args: &[String]) -> Result<Command, CliError> {
+    let mut cursor = ArgCursor::new(args);
+
+    match cursor.next_arg() {
+        None => Ok(Command::Demo {
+            display: DisplayOptions::default(),
+        }),
+        Some(first) if first.starts_with("--") => {
+            let mut display = DisplayOptions::default();
+            let mut next = Some(first);
+
+            while let Some(flag) = next {
+                if !parse_display_flag(flag, &mut cursor, &mut display)? {
+                    return Err(CliError::UnknownArgument(flag.to_string()));
+                }
+
+                next = cursor.next_arg();
+            }
+
+            Ok(Command::Demo { display })
+        }
+        Some("view") => {
+            let model_path = PathBuf::from(
+                cursor
+                    .next_arg()
+                    .ok_or(CliError::MissingArgument("model"))?,
+            );
+            let mut load_options = LoadOptions::default();
+            let mut display = DisplayOptions::default();
+
+            while let Some(flag) = cursor.next_arg() {
+                if !parse_display_flag(flag, &mut cursor, &mut display)?
+                    && !parse_load_flag(flag, &mut cursor, &mut load_options)?
+                {
+                    return Err(CliError::UnknownArgument(flag.to_string()));
+                }
+            }
+
+            Ok(Command::View {
+                model_path,
+                load_options,
+                display,
+            })
+        }
+        Some("turntable") => {
+            let model_path = PathBuf::from(
+                cursor
+                    .next_arg()
+                    .ok_or(CliError::MissingArgument("model"))?,
+            );
+            let mut load_options = LoadOptions::default();
+            let mut display = sequence_display_options();
+            let mut turntable = Turntable::default();
+            let mut mode = ViewerMode::Flat;
+            let mut light = LightPreset::from_name("front").unwrap();
+
+            while let Some(flag) = cursor.next_arg() {
+                if parse_display_flag(flag, &mut cursor, &mut display)?
+                    || parse_load_flag(flag, &mut cursor, &mut load_options)?
+                {
+                    continue;
+                }
+
+                match flag {
+                    "--mode" => mode = parse_mode(flag, &mut cursor)?,
+                    "--light" => {
+                        let name = cursor.value_for(flag)?;
+
+                        light = LightPreset::from_name(name).ok_or_else(|| {
+                            CliError::InvalidValue(flag.to_string(), name.to_string())
+                        })?;
+                    }
+                    "--rotate" => {
+                        turntable.target = match cursor.value_for(flag)? {
+                            "model" => TurntableTarget::Model,
+                            "camera" => TurntableTarget::Camera,
+                            other => {
+                                return Err(CliError::InvalidValue(
+                                    flag.to_string(),
+                                    other.to_string(),
+                                ))
+                            }
+                        }
+                    }
+                    "--degrees" => turntable.degrees = cursor.finite_value_for(flag, |_| true)?,
+                    "--fps" => turntable.fps = cursor.finite_value_for(flag, |f| f > 0.0)?,
+                    "--pitch" => turntable.pitch = cursor.finite_value_for(flag, |_| true)?,
+                    "--zoom" => turntable.zoom = cursor.finite_value_for(flag, |z| z > 0.0)?,
+                    _ => return Err(CliError::UnknownArgument(flag.to_string())),
+                }
+            }
+
+            if let Some(frames) = display.max_frames {
+                turntable.frames = frames;
+            }
+
+            Ok(Command::Turntable {
+                model_path,
+                load_options,
+                display,
+                turntable,
+                mode,
+                light,
+            })
+        }
+        Some("animate") => {
+            let model_path = PathBuf::from(
+                cursor
+                    .next_arg()
+                    .ok_or(CliError::MissingArgument("model"))?,
+            );
+            let animation_path = PathBuf::from(
+                cursor
+                    .next_arg()
+                    .ok_or(CliError::MissingArgument("keyframes.toml"))?,
+            );
+            let mut load_options = LoadOptions::default();
+            let mut display = sequence_display_options();
+            let mut mode = ViewerMode::Flat;
+
+            while let Some(flag) = cursor.next_arg() {
+                if parse_display_flag(flag, &mut cursor, &mut display)?
+                    || parse_load_flag(flag, &mut cursor, &mut load_options)?
+                {
+                    continue;
+                }
+
+                match flag {
+                    "--mode" => mode = parse_mode(flag, &mut cursor)?,
+                    _ => return Err(CliError::UnknownArgument(flag.to_string())),
+                }
+            }
+
+            Ok(Command::Animate {
+                model_path,
+                load_options,
+                animation_path,
+                display,
+                mode,
+            })
+        }
+        Some("scene") => {
+            let scene_path = PathBuf::from(
+                cursor
+                    .next_arg()
+                    .ok_or(CliError::MissingArgument("scene.toml"))?,
+            );
+            let mut display = sequence_display_options();
+            let mut size_override = false;
+
+            while let Some(flag) = cursor.next_arg() {
+                size_override |= flag == "--size";
+
+                if !parse_display_flag(flag, &mut cursor, &mut display)? {
+                    return Err(CliError::UnknownArgument(flag.to_string()));
+                }
+            }
+
+            Ok(Command::Scene {
+                scene_path,
+                display,
+                size_override,
+            })
+        }
+        Some("export") => {
+            let model_path = PathBuf::from(
+                cursor
+                    .next_arg()
+                    .ok_or(CliError::MissingArgument("model"))?,
+            );
+            let out_path = PathBuf::from(
+                cursor
+                    .next_arg()
+                    .ok_or(CliError::MissingArgument("out.obj"))?,
+            );
+            let mut load_options = LoadOptions::default();
+
+            while let Some(flag) = cursor.next_arg() {
+                if !parse_load_flag(flag, &mut cursor, &mut load_options)? {
+                    return Err(CliError::UnknownArgument(flag.to_string()));
+                }
+            }
+
+            Ok(Command::Export {
+                model_path,
+                load_options,
+                out_path,
+            })
+        }
+        Some(other) => Err(CliError::UnknownArgument(other.to_string())),
+    }
+}
+
+pub fn make_presenter(
+    display: &DisplayOptions,
+    title: &str,
+) -> Result<Box<dyn Presenter>, CliError> {
+    let (w, h) = display.size;
+
+    match &display.backend {
+        Backend::Window => make_window_presenter(title, w, h),
+        Backend::Terminal { columns } => Ok(Box::new(TerminalPresenter::stdout(*columns))),
+        Backend::Files { directory, format } => Ok(Box::new(FileSequencePresenter::new(
+            directory.clone(),
+            *format,
+            display.max_frames,
+        ))),
+        Backend::Raw => Ok(Box::new(RawFramePresenter::stdout(display.max_frames))),
+    }
+}
+
+#[cfg(feature = "sdl")]
+fn make_window_presenter(
+    title: &str,
+    width: usize,
+    height: usize,
+) -> Result<Box<dyn Presenter>, CliError> {
+    crate::presenter::SdlPresenter::new(title, width, height)
+        .map(|p| Box::new(p) as Box<dyn Presenter>)
+        .map_err(|err| CliError::BackendFailed(err.to_string()))
+}
+
+#[cfg(not(feature = "sdl"))]
+fn make_window_presenter(
+    _title: &str,
+    _width: usize,
+    _height: usize,
+) -> Result<Box<dyn Presenter>, CliError> {
+    Err(CliError::BackendUnavailable(String::from("window")))
+}
@@ -0,0 +1,64 @@
+use std::sync::OnceLock;
+
+use crate::image_canvas::Rgb;
+
+// Channels between zero and one in linear light; values above one are allowed until
+// they are encoded.
+pub type LinearRgb = [f64; 3];
+
+// 4x4 Bayer matrix, thresholds in sixteenths.
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+fn decode_table() -> &'static [f64; 256] {
+    static TABLE: OnceLock<[f64; 256]> = OnceLock::new();
+
+    TABLE.get_or_init(|| std::array::from_fn(|i| srgb_curve_to_linear(i as f64 / 255.0)))
+}
+
+pub fn srgb_curve_to_linear(c: f64) -> f64 {
+    match c <= 0.04045 {
+        true => c / 12.92,
+        false => ((c + 0.055) / 1.055).powf(2.4),
+    }
+}
+
+pub fn linear_to_srgb(c: f64) -> f64 {
+    let c = c.clamp(0.0, 1.0);
+
+    match c <= 0.0031308 {
+        true => c * 12.92,
+        false => 1.055 * c.powf(1.0 / 2.4) - 0.055,
+    }
+}
+
+pub fn srgb_to_linear(c: u8) -> f64 {
+    decode_table()[c as usize]
+}
+
+pub fn decode_srgb(rgb: Rgb) -> LinearRgb {
+    rgb.map(srgb_to_linear)
+}
+
+pub fn encode_srgb(linear: LinearRgb) -> Rgb {
+    linear.map(|c| (linear_to_srgb(c) * 255.0).round() as u8)
+}
+
+// Like `encode_srgb`, but with `dither` the rounding threshold follows an ordered pattern
+// keyed on the pixel, which breaks smooth gradients' bands into fine noise.
+pub fn encode_srgb_at(linear: LinearRgb, x: usize, y: usize, dither: bool) -> Rgb {
+    if !dither {
+        return encode_srgb(linear);
+    }
+
+    let offset = (BAYER[y % 4][x % 4] as f64 + 0.5) / 16.0 - 0.5;
+
+    linear.map(|c| {
+        (linear_to_srgb(c) * 255.0 + offset)
+            .round()
+            .clamp(0.0, 255.0) as u8
+    })
+}
+
+pub fn encode_gray(intensity: f64) -> u8 {
+    (linear_to_srgb(intensity) * 255.0).round() as u8
+}
@@ -1,101 +1,361 @@
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
-use sdl2::pixels::Color;
-use sdl2::rect::Point;
-use std::borrow::BorrowMut;
+use std::io;
 use std::ops::Deref;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use log::info;
+
+use crate::camera::OrbitCamera;
+use crate::depth_buffer::DepthBuffer;
+use crate::draw_modes::draw_debug::draw_debug::{draw_debug_view, DebugView};
+use crate::draw_modes::draw_depth::draw_depth::{draw_depth_view, DepthSettings};
+use crate::draw_modes::draw_hidden_line::draw_hidden_line::{
+    draw_hidden_line_view, shade_flat_with_depth, WireframeStyle, DEFAULT_DEPTH_BIAS,
+};
+use crate::draw_modes::draw_primitives::draw_primitives::{
+    draw_primitives, has_primitives, PrimitiveStyle,
+};
 use crate::image_canvas::Canvas;
+use crate::linear_algebra::Point3;
+use crate::presenter::{PresentError, Presenter, PresenterEvent};
+use crate::wavefront_parser::WavefronObject;
 
-pub fn display_image_on_screen(image: Canvas) {
-    let sdl_context = sdl2::init().unwrap();
-    let video_subsystem = sdl_context.video().unwrap();
+pub const STATIC_FRAME_INTERVAL: Duration = Duration::from_millis(1000 / 60);
 
-    let (w, h) = image.get_size();
+// Interactive backends keep showing the image until they are closed, the others get
+// exactly one frame.
+pub fn display_image_on_screen<P: Presenter + ?Sized>(
+    presenter: &mut P,
+    image: &Canvas,
+) -> Result<(), PresentError> {
+    loop {
+        presenter.present(image)?;
+        presenter.poll_events();
 
-    let window = video_subsystem
-        .window("rust-sdl2 demo", w as u32, h as u32)
-        .position_centered()
-        .build()
-        .unwrap();
-
-    let mut canvas = window.into_canvas().build().unwrap();
-
-    canvas.set_draw_color(Color::RGB(0, 255, 255));
-    canvas.clear();
-    canvas.present();
-    let image_canvas = image.get_map();
-    let mut event_pump = sdl_context.event_pump().unwrap();
-    let mut i = 0;
-    'running: loop {
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit { .. }
-                | Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
-                    ..
-                } => break 'running,
-                _ => {}
-            }
+        if !presenter.is_interactive() || !presenter.is_open() {
+            return Ok(());
         }
 
-        for (i, x_vec) in image_canvas.iter().enumerate() {
-            for (j, c) in x_vec.into_iter().enumerate() {
-                canvas.set_draw_color(Color::RGB(*c, *c, *c));
-                canvas.draw_point(Point::new(w as i32 - j as i32, h as i32 - i as i32));
-            }
+        std::thread::sleep(STATIC_FRAME_INTERVAL);
+    }
+}
+
+pub fn display_threaded_image_on_screen<P: Presenter + ?Sized>(
+    presenter: &mut P,
+    image_arc_mutex: Arc<Mutex<Canvas>>,
+) -> Result<(), PresentError> {
+    let image = image_arc_mutex.deref().lock().unwrap();
+
+    display_image_on_screen(presenter, &image)
+}
+
+pub const VIEWER_BACKGROUND: u8 = 20;
+pub const ORBIT_RADIANS_PER_PIXEL: f64 = 0.01;
+pub const ZOOM_PER_WHEEL_STEP: f64 = 1.1;
+pub const FPS_REFRESH: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ViewerMode {
+    Flat,
+    Overlay,
+    HiddenLine,
+    Depth,
+    Normals,
+    Uvs,
+    Barycentric,
+    Overdraw,
+    VertexColors,
+}
+
+impl ViewerMode {
+    pub const ALL: [ViewerMode; 9] = [
+        ViewerMode::Flat,
+        ViewerMode::Overlay,
+        ViewerMode::HiddenLine,
+        ViewerMode::Depth,
+        ViewerMode::Normals,
+        ViewerMode::Uvs,
+        ViewerMode::Barycentric,
+        ViewerMode::Overdraw,
+        ViewerMode::VertexColors,
+    ];
+
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|m| *m == self).unwrap();
+
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            ViewerMode::Flat => "flat",
+            ViewerMode::Overlay => "overlay",
+            ViewerMode::HiddenLine => "hidden-line",
+            ViewerMode::Depth => "depth",
+            ViewerMode::Normals => "normals",
+            ViewerMode::Uvs => "uvs",
+            ViewerMode::Barycentric => "barycentric",
+            ViewerMode::Overdraw => "overdraw",
+            ViewerMode::VertexColors => "colors",
         }
+    }
 
-        canvas.present();
-        ::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|m| m.get_name() == name)
+    }
+
+    pub fn from_key(key: char) -> Option<Self> {
+        let index = key.to_digit(10)? as usize;
+
+        match index {
+            1..=9 => Some(Self::ALL[index - 1]),
+            _ => None,
+        }
     }
 }
 
-pub fn display_threaded_image_on_screen(image_arc_mutex: Arc<Mutex<Canvas>>) {
-    let mut image = image_arc_mutex.deref().lock().unwrap();
+// Directions are the way the light travels, so (0, 0, -1) shines into the screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LightPreset {
+    Headlight,
+    World(&'static str, Point3<f64>),
+    // The flat shader does not renormalize, so the intensity scales the diffuse term.
+    Directional {
+        direction: Point3<f64>,
+        intensity: f64,
+    },
+}
+
+pub const LIGHT_PRESETS: [LightPreset; 4] = [
+    LightPreset::Headlight,
+    LightPreset::World("front", Point3(0.0, 0.0, -1.0)),
+    LightPreset::World("top", Point3(0.0, -1.0, 0.0)),
+    LightPreset::World("left", Point3(1.0, 0.0, 0.0)),
+];
+
+impl LightPreset {
+    pub fn get_world_direction(&self, camera: &OrbitCamera) -> Point3<f64> {
+        match self {
+            LightPreset::Headlight => camera.view_to_world_direction(Point3(0.0, 0.0, -1.0)),
+            LightPreset::World(_, dir) => dir.normalize(),
+            LightPreset::Directional {
+                direction,
+                intensity,
+            } => direction.normalize() * *intensity,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        LIGHT_PRESETS.iter().copied().find(|l| l.get_name() == name)
+    }
 
-    let sdl_context = sdl2::init().unwrap();
-    let video_subsystem = sdl_context.video().unwrap();
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            LightPreset::Headlight => "headlight",
+            LightPreset::World(name, _) => name,
+            LightPreset::Directional { .. } => "directional",
+        }
+    }
+}
 
+// Returns the depth the frame was drawn with, primitives included, when any of its passes
+// built one.
+pub fn render_viewer_frame(
+    model: &WavefronObject,
+    camera: &OrbitCamera,
+    mode: ViewerMode,
+    light: LightPreset,
+    primitives: &PrimitiveStyle,
+    depth: &DepthSettings,
+    image: &mut Canvas,
+) -> Option<DepthBuffer> {
     let (w, h) = image.get_size();
+    let light_dir = light.get_world_direction(camera);
 
-    let window = video_subsystem
-        .window("rust-sdl2 demo", w as u32, h as u32)
-        .position_centered()
-        .build()
-        .unwrap();
-
-    let mut canvas = window.into_canvas().build().unwrap();
-
-    canvas.set_draw_color(Color::RGB(0, 255, 255));
-    canvas.clear();
-    canvas.present();
-    let image_canvas = image.get_map();
-    let mut event_pump = sdl_context.event_pump().unwrap();
-    let mut i = 0;
-    'running: loop {
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit { .. }
-                | Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
-                    ..
-                } => break 'running,
-                _ => {}
-            }
+    image.clear(VIEWER_BACKGROUND);
+
+    let mut depth_buffer = match mode {
+        ViewerMode::Flat => {
+            let mut depth_buffer = DepthBuffer::new(w, h);
+
+            shade_flat_with_depth(model, camera, light_dir, Some(image), &mut depth_buffer);
+
+            Some(depth_buffer)
+        }
+        ViewerMode::Overlay | ViewerMode::HiddenLine => {
+            let style = match mode {
+                ViewerMode::Overlay => WireframeStyle::Overlay,
+                _ => WireframeStyle::HiddenLine,
+            };
+
+            Some(draw_hidden_line_view(
+                model,
+                camera,
+                light_dir,
+                style,
+                DEFAULT_DEPTH_BIAS,
+                image,
+                255,
+            ))
+        }
+        ViewerMode::Depth => Some(draw_depth_view(
+            model,
+            camera,
+            depth.range,
+            depth.scale,
+            image,
+        )),
+        ViewerMode::Normals
+        | ViewerMode::Uvs
+        | ViewerMode::Barycentric
+        | ViewerMode::Overdraw
+        | ViewerMode::VertexColors => {
+            let view = match mode {
+                ViewerMode::Normals => DebugView::Normals,
+                ViewerMode::Uvs => DebugView::Uvs,
+                ViewerMode::Barycentric => DebugView::Barycentric,
+                ViewerMode::Overdraw => DebugView::Overdraw,
+                _ => DebugView::VertexColors,
+            };
+
+            draw_debug_view(model, camera, view, image);
+
+            None
         }
+    };
+
+    // Lines and points are hidden by the faces, so views without a depth of their own get
+    // a depth-only pass first.
+    if has_primitives(model) {
+        let depth_buffer = depth_buffer.get_or_insert_with(|| depth_pass(model, camera, (w, h)));
 
-        for (i, x_vec) in image_canvas.iter().enumerate() {
-            for (j, c) in x_vec.into_iter().enumerate() {
-                canvas.set_draw_color(Color::RGB(*c, *c, *c));
+        draw_primitives(model, camera, primitives, depth_buffer, image);
+    }
+
+    depth_buffer
+}
 
-                canvas.draw_point(Point::new(w as i32 - j as i32, h as i32 - i as i32));
+fn depth_pass(model: &WavefronObject, camera: &OrbitCamera, size: (usize, usize)) -> DepthBuffer {
+    let mut depth_buffer = DepthBuffer::new(size.0, size.1);
+
+    shade_flat_with_depth(
+        model,
+        camera,
+        Point3(0.0, 0.0, -1.0),
+        None,
+        &mut depth_buffer,
+    );
+
+    depth_buffer
+}
+
+// The depth to save for a frame: the one it was drawn with, or a depth-only pass for the
+// views that kept none, so those only pay for it on the frame that is saved.
+pub fn frame_depth(
+    model: &WavefronObject,
+    camera: &OrbitCamera,
+    drawn: Option<DepthBuffer>,
+    size: (usize, usize),
+) -> DepthBuffer {
+    drawn.unwrap_or_else(|| depth_pass(model, camera, size))
+}
+
+// Writes the last frame's depth where `depth` says to, once a sequence is over.
+pub fn save_depth(depth: &DepthSettings, depth_buffer: Option<DepthBuffer>) -> io::Result<()> {
+    match (&depth.out, depth_buffer) {
+        (Some(path), Some(depth_buffer)) => {
+            depth_buffer.write_pfm(path.clone())?;
+
+            info!(path:% = path.display(); "depth saved");
+
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+// Left drag orbits, right or middle drag pans, the wheel zooms. 1-9 pick a draw mode,
+// M cycles through them, L cycles lights, R resets the camera. Without a window nothing
+// can change between frames, so headless presenters get a single frame unless asked.
+pub fn run_interactive_viewer<P: Presenter + ?Sized>(
+    presenter: &mut P,
+    model: &WavefronObject,
+    width: usize,
+    height: usize,
+    max_frames: Option<usize>,
+    primitives: &PrimitiveStyle,
+    depth: &DepthSettings,
+) -> Result<(), PresentError> {
+    let mut image = Canvas::new(width, height, VIEWER_BACKGROUND);
+    let mut camera = OrbitCamera::default();
+    let mut mode = ViewerMode::Flat;
+    let mut light_index = 0;
+
+    let mut frames = 0u32;
+    let mut total_frames = 0usize;
+    let mut fps_timer = Instant::now();
+    let mut last_frame = None;
+    let max_frames = max_frames.or((!presenter.is_interactive()).then_some(1));
+
+    while presenter.is_open() {
+        for event in presenter.poll_events() {
+            match event {
+                PresenterEvent::Quit => break,
+                PresenterEvent::Key('m') => mode = mode.next(),
+                PresenterEvent::Key('l') => light_index = (light_index + 1) % LIGHT_PRESETS.len(),
+                PresenterEvent::Key('r') => camera = OrbitCamera::default(),
+                PresenterEvent::Key(c) => {
+                    if let Some(picked) = ViewerMode::from_key(c) {
+                        mode = picked;
+                    }
+                }
+                PresenterEvent::Orbit(dx, dy) => {
+                    camera.orbit(dx * ORBIT_RADIANS_PER_PIXEL, dy * ORBIT_RADIANS_PER_PIXEL)
+                }
+                PresenterEvent::Pan(dx, dy) => {
+                    camera.pan_by(2.0 * dx / width as f64, -2.0 * dy / height as f64)
+                }
+                PresenterEvent::Zoom(steps) => camera.zoom_by(ZOOM_PER_WHEEL_STEP.powi(steps)),
             }
         }
 
-        canvas.present();
-        ::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
+        if !presenter.is_open() || max_frames.is_some_and(|max| total_frames >= max) {
+            break;
+        }
+
+        let light = LIGHT_PRESETS[light_index];
+
+        let drawn = render_viewer_frame(model, &camera, mode, light, primitives, depth, &mut image);
+        presenter.present(&image)?;
+
+        last_frame = Some((camera, drawn));
+
+        frames += 1;
+        total_frames += 1;
+
+        if fps_timer.elapsed() >= FPS_REFRESH {
+            let fps = frames as f64 / fps_timer.elapsed().as_secs_f64();
+
+            presenter.set_status(&format!(
+                "naqshsmith viewer - {:?} - {} light - {:.1} fps",
+                mode,
+                light.get_name(),
+                fps
+            ));
+
+            frames = 0;
+            fps_timer = Instant::now();
+        }
     }
+
+    save_depth(
+        depth,
+        last_frame
+            .filter(|_| depth.out.is_some())
+            .map(|(camera, drawn)| frame_depth(model, &camera, drawn, (width, height))),
+    )?;
+
+    info!(frames = total_frames; "viewer closed");
+
+    Ok(())
 }
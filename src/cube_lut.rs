@@ -0,0 +1,163 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use log::info;
+
+#[derive(Debug)]
+pub enum CubeError {
+    Io(PathBuf, io::Error),
+    Malformed(PathBuf, String),
+}
+
+impl fmt::Display for CubeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CubeError::Io(path, err) => write!(f, "could not read {}: {}", path.display(), err),
+            CubeError::Malformed(path, msg) => write!(f, "{}: {}", path.display(), msg),
+        }
+    }
+}
+
+// A 3D lookup table in the Adobe/Resolve .cube format. Entries run red fastest, then
+// green, then blue, and inputs are scaled from the domain onto the lattice.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CubeLut {
+    pub title: Option<String>,
+    pub size: usize,
+    pub domain_min: [f64; 3],
+    pub domain_max: [f64; 3],
+    pub entries: Vec<[f64; 3]>,
+}
+
+fn parse_triplet(args: &[&str], line: usize) -> Result<[f64; 3], String> {
+    let values = args
+        .iter()
+        .map(|a| a.parse::<f64>())
+        .collect::<Result<Vec<f64>, _>>()
+        .ok()
+        .filter(|v| v.len() == 3)
+        .ok_or_else(|| format!("line {}: expected three numbers", line))?;
+
+    Ok([values[0], values[1], values[2]])
+}
+
+impl CubeLut {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut title = None;
+        let mut size = None;
+        let mut domain_min = [0.0; 3];
+        let mut domain_max = [1.0; 3];
+        let mut entries = vec![];
+
+        for (i, line) in text.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let words = line.split_whitespace().collect::<Vec<&str>>();
+
+            match words[0] {
+                "TITLE" => title = Some(line["TITLE".len()..].trim().trim_matches('"').to_string()),
+                "LUT_3D_SIZE" => {
+                    let n = words
+                        .get(1)
+                        .and_then(|n| n.parse::<usize>().ok())
+                        .filter(|n| (2..=256).contains(n))
+                        .ok_or_else(|| format!("line {}: invalid LUT_3D_SIZE", line_number))?;
+
+                    size = Some(n);
+                }
+                "DOMAIN_MIN" => domain_min = parse_triplet(&words[1..], line_number)?,
+                "DOMAIN_MAX" => domain_max = parse_triplet(&words[1..], line_number)?,
+                "LUT_1D_SIZE" => return Err(String::from("1D LUTs are not supported")),
+                _ => entries.push(parse_triplet(&words, line_number)?),
+            }
+        }
+
+        let size = size.ok_or("missing LUT_3D_SIZE")?;
+
+        if entries.len() != size * size * size {
+            return Err(format!(
+                "expected {} entries for a size of {}, found {}",
+                size * size * size,
+                size,
+                entries.len()
+            ));
+        }
+
+        if (0..3).any(|c| domain_max[c] <= domain_min[c]) {
+            return Err(String::from("DOMAIN_MAX must be above DOMAIN_MIN"));
+        }
+
+        Ok(Self {
+            title,
+            size,
+            domain_min,
+            domain_max,
+            entries,
+        })
+    }
+
+    pub fn load(path: PathBuf) -> Result<Self, CubeError> {
+        let text =
+            std::fs::read_to_string(&path).map_err(|err| CubeError::Io(path.clone(), err))?;
+        let lut = Self::parse(&text).map_err(|msg| CubeError::Malformed(path.clone(), msg))?;
+
+        info!(
+            path:% = path.display(),
+            size = lut.size,
+            title:? = lut.title;
+            "lut loaded"
+        );
+
+        Ok(lut)
+    }
+
+    fn entry(&self, r: usize, g: usize, b: usize) -> [f64; 3] {
+        self.entries[r + g * self.size + b * self.size * self.size]
+    }
+
+    // Trilinear between the eight surrounding lattice points.
+    pub fn sample(&self, color: [f64; 3]) -> [f64; 3] {
+        let last = (self.size - 1) as f64;
+        let mut base = [0; 3];
+        let mut fraction = [0.0; 3];
+
+        for c in 0..3 {
+            let t = (color[c] - self.domain_min[c]) / (self.domain_max[c] - self.domain_min[c]);
+            let position = t.clamp(0.0, 1.0) * last;
+            let cell = position.floor().min(last - 1.0);
+
+            base[c] = cell as usize;
+            fraction[c] = position - cell;
+        }
+
+        let mut out = [0.0; 3];
+
+        for corner in 0..8 {
+            let step = [corner & 1, (corner >> 1) & 1, (corner >> 2) & 1];
+            let weight = (0..3)
+                .map(|c| match step[c] {
+                    1 => fraction[c],
+                    _ => 1.0 - fraction[c],
+                })
+                .product::<f64>();
+
+            if weight == 0.0 {
+                continue;
+            }
+
+            let value = self.entry(base[0] + step[0], base[1] + step[1], base[2] + step[2]);
+
+            for c in 0..3 {
+                out[c] += value[c] * weight;
+            }
+        }
+
+        out
+    }
+}
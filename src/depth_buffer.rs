@@ -0,0 +1,94 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+// Larger depth values are closer to the viewer, same as the z-buffer draw modes.
+#[derive(Clone, Debug)]
+pub struct DepthBuffer {
+    width: usize,
+    height: usize,
+    depths: Vec<f64>,
+}
+
+impl DepthBuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            depths: vec![-f64::MAX; width * height],
+        }
+    }
+
+    pub fn get_size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    pub fn get_depth(&self, x: usize, y: usize) -> Option<f64> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        Some(self.depths[x + y * self.width])
+    }
+
+    pub fn is_visible(&self, x: usize, y: usize, depth: f64, bias: f64) -> bool {
+        match self.get_depth(x, y) {
+            Some(stored) => depth + bias >= stored,
+            None => false,
+        }
+    }
+
+    // Whether `depth` is nearer than what is stored, without storing it.
+    pub fn test(&self, x: usize, y: usize, depth: f64) -> bool {
+        self.get_depth(x, y).is_some_and(|stored| stored < depth)
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, depth: f64) {
+        if x < self.width && y < self.height {
+            self.depths[x + y * self.width] = depth;
+        }
+    }
+
+    pub fn test_and_set(&mut self, x: usize, y: usize, depth: f64) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+
+        let stored = &mut self.depths[x + y * self.width];
+
+        if *stored < depth {
+            *stored = depth;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn get_depths(&self) -> &[f64] {
+        &self.depths
+    }
+
+    pub fn get_covered_range(&self) -> Option<(f64, f64)> {
+        self.depths
+            .iter()
+            .filter(|d| **d > -f64::MAX)
+            .fold(None, |range, d| match range {
+                None => Some((*d, *d)),
+                Some((min, max)) => Some((f64::min(min, *d), f64::max(max, *d))),
+            })
+    }
+
+    // Portable float map, little-endian, rows written bottom to top like the canvas.
+    // Pixels no triangle covered come out as negative infinity.
+    pub fn write_pfm(&self, path: PathBuf) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        write!(writer, "Pf\n{} {}\n-1.0\n", self.width, self.height)?;
+
+        for depth in &self.depths {
+            writer.write_all(&(*depth as f32).to_le_bytes())?;
+        }
+
+        writer.flush()
+    }
+}
@@ -0,0 +1,148 @@
+pub mod blend {
+    use crate::color::LinearRgb;
+    use crate::hdr_target::HdrTarget;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum BlendFactor {
+        Zero,
+        One,
+        SrcColor,
+        OneMinusSrcColor,
+        DstColor,
+        OneMinusDstColor,
+        SrcAlpha,
+        OneMinusSrcAlpha,
+    }
+
+    impl BlendFactor {
+        pub const ALL: [BlendFactor; 8] = [
+            BlendFactor::Zero,
+            BlendFactor::One,
+            BlendFactor::SrcColor,
+            BlendFactor::OneMinusSrcColor,
+            BlendFactor::DstColor,
+            BlendFactor::OneMinusDstColor,
+            BlendFactor::SrcAlpha,
+            BlendFactor::OneMinusSrcAlpha,
+        ];
+
+        pub fn from_name(name: &str) -> Option<Self> {
+            Self::ALL.iter().copied().find(|f| f.get_name() == name)
+        }
+
+        pub fn get_name(&self) -> &'static str {
+            match self {
+                BlendFactor::Zero => "zero",
+                BlendFactor::One => "one",
+                BlendFactor::SrcColor => "src_color",
+                BlendFactor::OneMinusSrcColor => "one_minus_src_color",
+                BlendFactor::DstColor => "dst_color",
+                BlendFactor::OneMinusDstColor => "one_minus_dst_color",
+                BlendFactor::SrcAlpha => "src_alpha",
+                BlendFactor::OneMinusSrcAlpha => "one_minus_src_alpha",
+            }
+        }
+
+        // Per channel, in linear light. Colors used as weights are clamped to one so a
+        // bright source cannot amplify what is behind it.
+        fn weight(&self, src: f64, dst: f64, alpha: f64) -> f64 {
+            let (src, dst) = (src.clamp(0.0, 1.0), dst.clamp(0.0, 1.0));
+
+            match self {
+                BlendFactor::Zero => 0.0,
+                BlendFactor::One => 1.0,
+                BlendFactor::SrcColor => src,
+                BlendFactor::OneMinusSrcColor => 1.0 - src,
+                BlendFactor::DstColor => dst,
+                BlendFactor::OneMinusDstColor => 1.0 - dst,
+                BlendFactor::SrcAlpha => alpha,
+                BlendFactor::OneMinusSrcAlpha => 1.0 - alpha,
+            }
+        }
+    }
+
+    // result = src * src_factor + dst * dst_factor, the framebuffer being dst.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct BlendState {
+        pub src: BlendFactor,
+        pub dst: BlendFactor,
+    }
+
+    impl Default for BlendState {
+        fn default() -> Self {
+            Self::OVER
+        }
+    }
+
+    impl BlendState {
+        pub const OPAQUE: BlendState = BlendState {
+            src: BlendFactor::One,
+            dst: BlendFactor::Zero,
+        };
+        pub const OVER: BlendState = BlendState {
+            src: BlendFactor::SrcAlpha,
+            dst: BlendFactor::OneMinusSrcAlpha,
+        };
+        pub const ADDITIVE: BlendState = BlendState {
+            src: BlendFactor::SrcAlpha,
+            dst: BlendFactor::One,
+        };
+        pub const MULTIPLY: BlendState = BlendState {
+            src: BlendFactor::DstColor,
+            dst: BlendFactor::Zero,
+        };
+
+        pub const PRESETS: [(&'static str, BlendState); 4] = [
+            ("opaque", BlendState::OPAQUE),
+            ("over", BlendState::OVER),
+            ("additive", BlendState::ADDITIVE),
+            ("multiply", BlendState::MULTIPLY),
+        ];
+
+        pub fn from_name(name: &str) -> Option<Self> {
+            Self::PRESETS
+                .iter()
+                .find(|(preset, _)| *preset == name)
+                .map(|(_, state)| *state)
+        }
+
+        // Whether a fully opaque fragment simply replaces what is under it.
+        pub fn is_replace_when_opaque(&self) -> bool {
+            matches!(
+                (self.src, self.dst),
+                (BlendFactor::One | BlendFactor::SrcAlpha, BlendFactor::Zero)
+                    | (BlendFactor::SrcAlpha, BlendFactor::OneMinusSrcAlpha)
+            )
+        }
+
+        pub fn apply(&self, src: LinearRgb, alpha: f64, dst: LinearRgb) -> LinearRgb {
+            let alpha = alpha.clamp(0.0, 1.0);
+
+            std::array::from_fn(|c| {
+                let (s, d) = (src[c], dst[c]);
+
+                s * self.src.weight(s, d, alpha) + d * self.dst.weight(s, d, alpha)
+            })
+        }
+    }
+
+    pub fn blend_pixel(
+        target: &mut HdrTarget,
+        x: usize,
+        y: usize,
+        color: LinearRgb,
+        alpha: f64,
+        state: &BlendState,
+    ) {
+        let Some(dst) = target.get_pixel(x, y) else {
+            return;
+        };
+
+        let color = match alpha >= 1.0 && state.is_replace_when_opaque() {
+            true => color,
+            false => state.apply(color, alpha, dst),
+        };
+
+        target.set_pixel(x, y, color);
+    }
+}
@@ -0,0 +1,281 @@
+pub mod draw_debug {
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use std::thread;
+    use std::time::Instant;
+
+    use crate::camera::OrbitCamera;
+    use crate::draw_modes::draw_scene::draw_scene::face_colors;
+    use crate::draw_modes::draw_threaded_with_zbuffer::draw_triangle_threaded_with_zbuffer::draw_triangle_threaded_shaded;
+    use crate::draw_modes::rasterize::rasterize::{
+        interpolate_point2, interpolate_point3, project_face,
+    };
+    use crate::image_canvas::{Canvas, Rgb};
+    use crate::linear_algebra::{calculate_normal, Point2, Point3, TriangleCoords3, Vec3Unsigned};
+    use crate::wavefront_parser::{Vertex, WavefronObject};
+    use log::info;
+
+    pub const UV_CHECKER_CELLS: f64 = 16.0;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum DebugView {
+        Normals,
+        Uvs,
+        Barycentric,
+        Overdraw,
+        VertexColors,
+    }
+
+    fn unit_to_channel(value: f64) -> u8 {
+        (255.0 * value.clamp(0.0, 1.0)).round() as u8
+    }
+
+    pub fn normal_to_color(normal: Point3<f64>) -> Rgb {
+        let n = normal.normalize();
+
+        [
+            unit_to_channel(n.0 * 0.5 + 0.5),
+            unit_to_channel(n.1 * 0.5 + 0.5),
+            unit_to_channel(n.2 * 0.5 + 0.5),
+        ]
+    }
+
+    pub fn uv_to_color(uv: Point2<f64>) -> Rgb {
+        let (u, v) = uv.get_pair_as_clones();
+
+        let cell = (u * UV_CHECKER_CELLS).floor() as i64 + (v * UV_CHECKER_CELLS).floor() as i64;
+        let shade = if cell.rem_euclid(2) == 0 { 1.0 } else { 0.6 };
+
+        [
+            unit_to_channel(u.fract().abs() * shade),
+            unit_to_channel(v.fract().abs() * shade),
+            unit_to_channel(0.25 * shade),
+        ]
+    }
+
+    pub fn barycentric_to_color(barycentric: Point3<f64>) -> Rgb {
+        [
+            unit_to_channel(barycentric.0),
+            unit_to_channel(barycentric.1),
+            unit_to_channel(barycentric.2),
+        ]
+    }
+
+    // Black for no fragments, then blue -> green -> yellow -> red as the count approaches `max`.
+    pub fn overdraw_to_color(count: u32, max: u32) -> Rgb {
+        if count == 0 || max == 0 {
+            return [0, 0, 0];
+        }
+
+        let stops: [(f64, f64, f64); 4] = [
+            (0.0, 0.0, 1.0),
+            (0.0, 1.0, 0.0),
+            (1.0, 1.0, 0.0),
+            (1.0, 0.0, 0.0),
+        ];
+
+        let t = match max {
+            1 => 1.0,
+            _ => (count - 1) as f64 / (max - 1) as f64,
+        };
+
+        let scaled = t * (stops.len() - 1) as f64;
+        let i = usize::min(scaled.floor() as usize, stops.len() - 2);
+        let f = scaled - i as f64;
+
+        let (r0, g0, b0) = stops[i];
+        let (r1, g1, b1) = stops[i + 1];
+
+        [
+            unit_to_channel(r0 + (r1 - r0) * f),
+            unit_to_channel(g0 + (g1 - g0) * f),
+            unit_to_channel(b0 + (b1 - b0) * f),
+        ]
+    }
+
+    struct DebugFace {
+        coords: TriangleCoords3,
+        normals: [Point3<f64>; 3],
+        uvs: Option<[Point2<f64>; 3]>,
+        colors: Option<[Point3<f64>; 3]>,
+    }
+
+    impl DebugFace {
+        fn shade(
+            &self,
+            view: DebugView,
+            pixel: usize,
+            barycentric: Point3<f64>,
+            overdraw: &[AtomicU32],
+        ) -> Option<Rgb> {
+            let color = match view {
+                DebugView::Normals => {
+                    normal_to_color(interpolate_point3(&self.normals, barycentric))
+                }
+                DebugView::Uvs => match &self.uvs {
+                    Some(uvs) => uv_to_color(interpolate_point2(uvs, barycentric)),
+                    None => [255, 0, 255],
+                },
+                DebugView::Barycentric => barycentric_to_color(barycentric),
+                DebugView::VertexColors => match &self.colors {
+                    Some(colors) => {
+                        let Point3(r, g, b) = interpolate_point3(colors, barycentric);
+
+                        [r, g, b].map(|c| c.round().clamp(0.0, 255.0) as u8)
+                    }
+                    None => [255, 0, 255],
+                },
+                DebugView::Overdraw => {
+                    overdraw[pixel].fetch_add(1, Ordering::Relaxed);
+
+                    return None;
+                }
+            };
+
+            Some(color)
+        }
+    }
+
+    fn face_vertices(model: &WavefronObject, verts: Vec3Unsigned<usize>) -> Vec<Vertex> {
+        verts
+            .unravel_vec()
+            .into_iter()
+            .map(|vi| *model.get_vertex_at_index(&vi).unwrap())
+            .collect()
+    }
+
+    fn face_normals(
+        model: &WavefronObject,
+        verts: Vec3Unsigned<usize>,
+        normals: Option<Vec3Unsigned<usize>>,
+    ) -> [Point3<f64>; 3] {
+        match normals {
+            Some(normals) => {
+                let (n1, n2, n3) = normals.unravel();
+
+                [
+                    *model.get_normal_at_index(&n1).unwrap(),
+                    *model.get_normal_at_index(&n2).unwrap(),
+                    *model.get_normal_at_index(&n3).unwrap(),
+                ]
+            }
+            None => {
+                let n = calculate_normal(face_vertices(model, verts)) * -1.0;
+
+                [n, n, n]
+            }
+        }
+    }
+
+    fn face_uvs(model: &WavefronObject, textures: Vec3Unsigned<usize>) -> [Point2<f64>; 3] {
+        let (t1, t2, t3) = textures.unravel();
+
+        let uv = |ti: usize| {
+            let (u, v) = model.get_texture_at_index(&ti).unwrap().unravel_uv_impl();
+
+            Point2(u, v)
+        };
+
+        [uv(t1), uv(t2), uv(t3)]
+    }
+
+    // Runs through the threaded z-buffer pipeline, so a face it drops or misplaces shows
+    // up here too. Each face's normals and UVs come from its own indices; faces missing
+    // them get the flat normal and the magenta UV color.
+    pub fn draw_debug_view(
+        model: &WavefronObject,
+        camera: &OrbitCamera,
+        view: DebugView,
+        image_canvas: &mut Canvas,
+    ) {
+        let start = Instant::now();
+        let (w, h) = image_canvas.get_size();
+        let has_colors = model.has_vertex_colors();
+
+        let faces = model
+            .get_faces()
+            .iter()
+            .filter_map(|face| {
+                let verts = face.get_vertex_indices()?;
+
+                Some(DebugFace {
+                    coords: project_face(model, camera, verts, w, h),
+                    normals: face_normals(model, verts, face.get_normal_indices()),
+                    uvs: face.get_texture_indices().map(|uvs| face_uvs(model, uvs)),
+                    colors: has_colors.then(|| face_colors(model, verts.unravel())),
+                })
+            })
+            .collect::<Vec<DebugFace>>();
+
+        let canvas_mutex = Mutex::new(std::mem::replace(image_canvas, Canvas::new(0, 0, 0)));
+        let zbuffer = Mutex::new(vec![-f64::MAX; w * h]);
+        let overdraw = (0..w * h)
+            .map(|_| AtomicU32::new(0))
+            .collect::<Vec<AtomicU32>>();
+        let fragments = AtomicUsize::new(0);
+
+        // Overdraw counts every covered pixel, hidden or not, so it skips the depth test.
+        let zbuffer = (view != DebugView::Overdraw).then_some(&zbuffer);
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        let chunk = usize::max(1, faces.len().div_ceil(threads));
+
+        let (canvas_ref, overdraw_ref, fragments_ref) = (&canvas_mutex, &overdraw, &fragments);
+
+        thread::scope(|scope| {
+            for faces in faces.chunks(chunk) {
+                scope.spawn(move || {
+                    for face in faces {
+                        draw_triangle_threaded_shaded(
+                            face.coords,
+                            zbuffer,
+                            canvas_ref,
+                            |x, y, bc| {
+                                fragments_ref.fetch_add(1, Ordering::Relaxed);
+
+                                face.shade(view, x + y * w, bc, overdraw_ref)
+                            },
+                        );
+                    }
+                });
+            }
+        });
+
+        *image_canvas = canvas_mutex.into_inner().unwrap();
+
+        if view == DebugView::Overdraw {
+            let overdraw = overdraw
+                .into_iter()
+                .map(AtomicU32::into_inner)
+                .collect::<Vec<u32>>();
+            let max = overdraw.iter().copied().max().unwrap_or(0);
+
+            for y in 0..h {
+                for x in 0..w {
+                    let count = overdraw[x + y * w];
+
+                    if count > 0 {
+                        image_canvas
+                            .set_pixel_rgb(x as i32, y as i32, overdraw_to_color(count, max))
+                            .unwrap();
+                    }
+                }
+            }
+        }
+
+        info!(
+            mode = "debug",
+            view:? = view,
+            faces = faces.len(),
+            fragments = fragments.into_inner(),
+            elapsed:? = start.elapsed();
+            "pass finished"
+        );
+    }
+
+    pub fn debug_renderer(obj_path: PathBuf, view: DebugView, image_canvas: &mut Canvas) {
+        let model = WavefronObject::new(obj_path);
+
+        draw_debug_view(&model, &OrbitCamera::default(), view, image_canvas);
+    }
+}
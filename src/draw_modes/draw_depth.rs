@@ -0,0 +1,147 @@
+pub mod draw_depth {
+    use std::path::PathBuf;
+    use std::time::Instant;
+
+    use crate::camera::OrbitCamera;
+    use crate::depth_buffer::DepthBuffer;
+    use crate::draw_modes::draw_hidden_line::draw_hidden_line::shade_flat_with_depth;
+    use crate::image_canvas::Canvas;
+    use crate::linear_algebra::Point3;
+    use crate::wavefront_parser::WavefronObject;
+    use log::info;
+
+    // Higher values push more of the gray ramp towards the near plane.
+    pub const LOG_DEPTH_STEEPNESS: f64 = 100.0;
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub enum DepthScale {
+        #[default]
+        Linear,
+        Logarithmic,
+    }
+
+    impl DepthScale {
+        pub fn from_name(name: &str) -> Option<Self> {
+            match name {
+                "linear" => Some(DepthScale::Linear),
+                "log" => Some(DepthScale::Logarithmic),
+                _ => None,
+            }
+        }
+
+        pub fn get_name(&self) -> &'static str {
+            match self {
+                DepthScale::Linear => "linear",
+                DepthScale::Logarithmic => "log",
+            }
+        }
+    }
+
+    // `near` and `far` are depth values; since larger z is closer, near > far for a sane range.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct DepthRange {
+        pub near: f64,
+        pub far: f64,
+    }
+
+    // How the depth mode maps depth to gray, and where to save the raw depth of the last
+    // frame, if anywhere. Without a range the covered one is used.
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct DepthSettings {
+        pub scale: DepthScale,
+        pub range: Option<DepthRange>,
+        pub out: Option<PathBuf>,
+    }
+
+    pub fn depth_to_gray(depth: f64, range: DepthRange, scale: DepthScale) -> u8 {
+        let span = range.near - range.far;
+
+        let distance = match span.abs() < f64::EPSILON {
+            true => 0.0,
+            false => ((range.near - depth) / span).clamp(0.0, 1.0),
+        };
+
+        let distance = match scale {
+            DepthScale::Linear => distance,
+            DepthScale::Logarithmic => {
+                (1.0 + distance * LOG_DEPTH_STEEPNESS).ln() / (1.0 + LOG_DEPTH_STEEPNESS).ln()
+            }
+        };
+
+        (255.0 * (1.0 - distance)).round() as u8
+    }
+
+    pub fn visualize_depth(
+        depth_buffer: &DepthBuffer,
+        range: Option<DepthRange>,
+        scale: DepthScale,
+        image_canvas: &mut Canvas,
+    ) {
+        let range = match range.or_else(|| {
+            depth_buffer
+                .get_covered_range()
+                .map(|(far, near)| DepthRange { near, far })
+        }) {
+            Some(range) => range,
+            None => return,
+        };
+
+        let (w, h) = depth_buffer.get_size();
+
+        for y in 0..h {
+            for x in 0..w {
+                let depth = depth_buffer.get_depth(x, y).unwrap();
+
+                if depth == -f64::MAX {
+                    continue;
+                }
+
+                image_canvas
+                    .set_pixel(x as i32, y as i32, depth_to_gray(depth, range, scale))
+                    .unwrap();
+            }
+        }
+    }
+
+    pub fn draw_depth_view(
+        model: &WavefronObject,
+        camera: &OrbitCamera,
+        range: Option<DepthRange>,
+        scale: DepthScale,
+        image_canvas: &mut Canvas,
+    ) -> DepthBuffer {
+        let start = Instant::now();
+        let (w, h) = image_canvas.get_size();
+
+        let mut depth_buffer = DepthBuffer::new(w, h);
+
+        shade_flat_with_depth(
+            model,
+            camera,
+            Point3(0.0, 0.0, -1.0),
+            None,
+            &mut depth_buffer,
+        );
+        visualize_depth(&depth_buffer, range, scale, image_canvas);
+
+        info!(
+            mode = "depth",
+            scale:? = scale,
+            covered_range:? = depth_buffer.get_covered_range(),
+            elapsed:? = start.elapsed();
+            "pass finished"
+        );
+
+        depth_buffer
+    }
+    pub fn depth_renderer(
+        obj_path: PathBuf,
+        range: Option<DepthRange>,
+        scale: DepthScale,
+        image_canvas: &mut Canvas,
+    ) -> DepthBuffer {
+        let model = WavefronObject::new(obj_path);
+
+        draw_depth_view(&model, &OrbitCamera::default(), range, scale, image_canvas)
+    }
+}
@@ -0,0 +1,230 @@
+pub mod draw_hidden_line {
+    use std::collections::BTreeSet;
+    use std::path::PathBuf;
+    use std::time::Instant;
+
+    use crate::camera::OrbitCamera;
+    use crate::color::encode_gray;
+    use crate::depth_buffer::DepthBuffer;
+    use crate::draw_modes::rasterize::rasterize::{
+        project_face, project_vertex, rasterize_line, rasterize_triangle,
+    };
+    use crate::image_canvas::Canvas;
+    use crate::linear_algebra::{calculate_normal_and_intensity, Point3, TriangleCoords3};
+    use crate::wavefront_parser::{Vertex, WavefronObject};
+    use log::info;
+
+    // Like polygon offset: an edge pixel may land up to a pixel off the surface it lies on,
+    // so it is let through `slope` times that surface's steepest depth change per pixel in
+    // front of it, plus `constant` in depth units for rounding.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct DepthBias {
+        pub slope: f64,
+        pub constant: f64,
+    }
+
+    pub const DEFAULT_DEPTH_BIAS: DepthBias = DepthBias {
+        slope: 1.0,
+        constant: 1e-6,
+    };
+
+    // Steepest change of depth per pixel across the triangle's plane, zero when it is seen
+    // edge on.
+    pub fn depth_slope(coords: TriangleCoords3) -> f64 {
+        let (e1x, e1y, e1z) = (
+            coords[1][0] - coords[0][0],
+            coords[1][1] - coords[0][1],
+            coords[1][2] - coords[0][2],
+        );
+        let (e2x, e2y, e2z) = (
+            coords[2][0] - coords[0][0],
+            coords[2][1] - coords[0][1],
+            coords[2][2] - coords[0][2],
+        );
+        let det = e1x * e2y - e2x * e1y;
+
+        if det.abs() < f64::EPSILON {
+            return 0.0;
+        }
+
+        let dzdx = (e1z * e2y - e2z * e1y) / det;
+        let dzdy = (e2z * e1x - e1z * e2x) / det;
+
+        f64::max(dzdx.abs(), dzdy.abs())
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum WireframeStyle {
+        HiddenLine,
+        Overlay,
+    }
+
+    pub fn shade_flat_with_depth(
+        model: &WavefronObject,
+        camera: &OrbitCamera,
+        light_dir: Point3<f64>,
+        image_canvas: Option<&mut Canvas>,
+        depth_buffer: &mut DepthBuffer,
+    ) {
+        shade_flat(model, camera, light_dir, image_canvas, depth_buffer, None);
+    }
+
+    // `slopes`, one per pixel, gets the depth slope of the face left in each pixel.
+    fn shade_flat(
+        model: &WavefronObject,
+        camera: &OrbitCamera,
+        light_dir: Point3<f64>,
+        mut image_canvas: Option<&mut Canvas>,
+        depth_buffer: &mut DepthBuffer,
+        mut slopes: Option<&mut [f64]>,
+    ) {
+        let (w, h) = depth_buffer.get_size();
+
+        for verts in model.get_vert_triplets_from_face_elements() {
+            let coords = project_face(model, camera, verts, w, h);
+
+            let vertices = verts
+                .unravel_vec()
+                .into_iter()
+                .map(|vi| *model.get_vertex_at_index(&vi).unwrap())
+                .collect::<Vec<Vertex>>();
+
+            let intensity = calculate_normal_and_intensity(vertices, light_dir);
+            // Lambert is linear light; the canvas is sRGB.
+            let color = encode_gray(intensity.clamp(0.0, 1.0));
+            let slope = slopes.is_some().then(|| depth_slope(coords));
+
+            rasterize_triangle(coords, w, h, |fragment| {
+                if !depth_buffer.test_and_set(fragment.x, fragment.y, fragment.depth) {
+                    return;
+                }
+
+                if let (Some(slopes), Some(slope)) = (slopes.as_deref_mut(), slope) {
+                    slopes[fragment.x + fragment.y * w] = slope;
+                }
+
+                if let Some(canvas) = image_canvas.as_deref_mut() {
+                    canvas
+                        .set_pixel(fragment.x as i32, fragment.y as i32, color)
+                        .unwrap();
+                }
+            });
+        }
+    }
+
+    pub fn collect_unique_edges(model: &WavefronObject) -> BTreeSet<(usize, usize)> {
+        let mut edges = BTreeSet::new();
+
+        for verts in model.get_vert_triplets_from_face_elements() {
+            for pair in verts.combinate(2) {
+                let (vi, vii) = (pair[0], pair[1]);
+
+                edges.insert((usize::min(vi, vii), usize::max(vi, vii)));
+            }
+        }
+
+        edges
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_edges_with_depth_test(
+        model: &WavefronObject,
+        camera: &OrbitCamera,
+        edges: &BTreeSet<(usize, usize)>,
+        depth_buffer: &DepthBuffer,
+        slopes: &[f64],
+        depth_bias: DepthBias,
+        image_canvas: &mut Canvas,
+        color: u8,
+    ) {
+        let (w, h) = depth_buffer.get_size();
+
+        for (vi, vii) in edges {
+            let from = project_vertex(model, camera, *vi, w, h);
+            let to = project_vertex(model, camera, *vii, w, h);
+
+            rasterize_line(from, to, w, h, |x, y, depth| {
+                let bias = depth_bias.slope * slopes[x + y * w] + depth_bias.constant;
+
+                if depth_buffer.is_visible(x, y, depth, bias) {
+                    image_canvas.set_pixel(x as i32, y as i32, color).unwrap();
+                }
+            });
+        }
+    }
+
+    pub fn draw_hidden_line_view(
+        model: &WavefronObject,
+        camera: &OrbitCamera,
+        light_dir: Point3<f64>,
+        style: WireframeStyle,
+        depth_bias: DepthBias,
+        image_canvas: &mut Canvas,
+        color: u8,
+    ) -> DepthBuffer {
+        let start = Instant::now();
+        let (w, h) = image_canvas.get_size();
+
+        let mut depth_buffer = DepthBuffer::new(w, h);
+        let mut slopes = vec![0.0; w * h];
+
+        let shaded = match style {
+            WireframeStyle::HiddenLine => None,
+            WireframeStyle::Overlay => Some(&mut *image_canvas),
+        };
+
+        shade_flat(
+            model,
+            camera,
+            light_dir,
+            shaded,
+            &mut depth_buffer,
+            Some(&mut slopes),
+        );
+
+        let edges = collect_unique_edges(model);
+
+        draw_edges_with_depth_test(
+            model,
+            camera,
+            &edges,
+            &depth_buffer,
+            &slopes,
+            depth_bias,
+            image_canvas,
+            color,
+        );
+
+        info!(
+            mode = "hidden_line",
+            style:? = style,
+            faces = model.get_n_faces(),
+            edges = edges.len(),
+            elapsed:? = start.elapsed();
+            "pass finished"
+        );
+
+        depth_buffer
+    }
+
+    pub fn hidden_line_wireframe_renderer(
+        obj_path: PathBuf,
+        light_dir: Point3<f64>,
+        style: WireframeStyle,
+        depth_bias: DepthBias,
+        image_canvas: &mut Canvas,
+        color: u8,
+    ) {
+        let model = WavefronObject::new(obj_path);
+
+        draw_hidden_line_view(
+            &model,
+            &OrbitCamera::default(),
+            light_dir,
+            style,
+            depth_bias,
+            image_canvas,
+            color,
+        );
+    }
+}
@@ -0,0 +1,176 @@
+pub mod draw_primitives {
+    use std::time::Instant;
+
+    use crate::camera::OrbitCamera;
+    use crate::color::encode_srgb;
+    use crate::depth_buffer::DepthBuffer;
+    use crate::draw_modes::rasterize::rasterize::{project_vertex, rasterize_line};
+    use crate::image_canvas::{Canvas, Rgb};
+    use crate::linear_algebra::Point3;
+    use crate::wavefront_parser::{PrimitiveElement, WavefronObject};
+    use log::info;
+
+    // Both in pixels.
+    pub const DEFAULT_LINE_WIDTH: f64 = 1.0;
+    pub const DEFAULT_POINT_SIZE: f64 = 3.0;
+
+    // Used for elements whose material has no diffuse color.
+    pub const DEFAULT_PRIMITIVE_COLOR: Rgb = [255, 200, 40];
+
+    // Lines and points are not tied to a face, so there is no slope to scale by. In depth
+    // units, which span [-1, 1] for normalized meshes.
+    pub const PRIMITIVE_DEPTH_BIAS: f64 = 0.01;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct PrimitiveStyle {
+        pub line_width: f64,
+        pub point_size: f64,
+        pub color: Rgb,
+    }
+
+    impl Default for PrimitiveStyle {
+        fn default() -> Self {
+            Self {
+                line_width: DEFAULT_LINE_WIDTH,
+                point_size: DEFAULT_POINT_SIZE,
+                color: DEFAULT_PRIMITIVE_COLOR,
+            }
+        }
+    }
+
+    pub fn has_primitives(model: &WavefronObject) -> bool {
+        !model.get_lines().is_empty() || !model.get_points().is_empty()
+    }
+
+    fn element_color(model: &WavefronObject, element: &PrimitiveElement, fallback: Rgb) -> Rgb {
+        element
+            .tags
+            .material
+            .and_then(|i| model.get_materials()[i].diffuse)
+            .map_or(fallback, encode_srgb)
+    }
+
+    // Covers a disk `size` pixels across around (x, y), all at the center's depth. The bias
+    // lets primitives lying on a surface win against it, the same as hidden-line edges.
+    fn stamp(
+        x: usize,
+        y: usize,
+        depth: f64,
+        size: f64,
+        color: Rgb,
+        depth_buffer: &mut DepthBuffer,
+        image_canvas: &mut Canvas,
+    ) {
+        let radius = ((size.max(1.0) - 1.0) / 2.0).round() as i64;
+
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                if dx * dx + dy * dy > radius * radius + radius {
+                    continue;
+                }
+
+                let (Ok(px), Ok(py)) = (
+                    usize::try_from(x as i64 + dx),
+                    usize::try_from(y as i64 + dy),
+                ) else {
+                    continue;
+                };
+
+                // Also fails outside the buffer, so the pixel below is always in range.
+                if depth_buffer.test_and_set(px, py, depth + PRIMITIVE_DEPTH_BIAS) {
+                    image_canvas
+                        .set_pixel_rgb(px as i32, py as i32, color)
+                        .unwrap();
+                }
+            }
+        }
+    }
+
+    // Faces should already be in the depth buffer so they can hide the primitives behind
+    // them. Indices past the vertex list are skipped.
+    pub fn draw_primitives(
+        model: &WavefronObject,
+        camera: &OrbitCamera,
+        style: &PrimitiveStyle,
+        depth_buffer: &mut DepthBuffer,
+        image_canvas: &mut Canvas,
+    ) {
+        let start = Instant::now();
+        let (w, h) = depth_buffer.get_size();
+
+        let project = |elements: &PrimitiveElement| {
+            elements
+                .vertices
+                .iter()
+                .filter(|vi| model.get_vertex_at_index(vi).is_some())
+                .map(|vi| project_vertex(model, camera, *vi, w, h))
+                .collect::<Vec<Point3<f64>>>()
+        };
+
+        for line in model.get_lines() {
+            let color = element_color(model, line, style.color);
+
+            for segment in project(line).windows(2) {
+                rasterize_line(segment[0], segment[1], w, h, |x, y, depth| {
+                    stamp(
+                        x,
+                        y,
+                        depth,
+                        style.line_width,
+                        color,
+                        depth_buffer,
+                        image_canvas,
+                    );
+                });
+            }
+        }
+
+        // Points without a material color of their own take the vertex colors, if any,
+        // which is how point clouds carry theirs.
+        for points in model.get_points() {
+            let color = element_color(model, points, style.color);
+            let has_diffuse = points
+                .tags
+                .material
+                .and_then(|i| model.get_materials()[i].diffuse)
+                .is_some();
+            let vertex_color = |vi: &usize| match model.has_vertex_colors() && !has_diffuse {
+                true => model.get_color_at_index(vi).copied().unwrap_or(color),
+                false => color,
+            };
+
+            for (vi, Point3(x, y, depth)) in points
+                .vertices
+                .iter()
+                .filter(|vi| model.get_vertex_at_index(vi).is_some())
+                .map(|vi| (vi, project_vertex(model, camera, *vi, w, h)))
+            {
+                let (x, y) = (x.round(), y.round());
+
+                if x < 0.0 || y < 0.0 || x >= w as f64 || y >= h as f64 {
+                    continue;
+                }
+
+                stamp(
+                    x as usize,
+                    y as usize,
+                    depth,
+                    style.point_size,
+                    vertex_color(vi),
+                    depth_buffer,
+                    image_canvas,
+                );
+            }
+        }
+
+        info!(
+            mode = "primitives",
+            lines = model.get_lines().len(),
+            points = model.get_points().len(),
+            line_width = style.line_width,
+            point_size = style.point_size,
+            elapsed:? = start.elapsed();
+            "pass finished"
+        );
+    }
+}
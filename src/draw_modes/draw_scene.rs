@@ -0,0 +1,333 @@
+pub mod draw_scene {
+    use crate::a_buffer::{ABuffer, LayerFragment};
+    use crate::camera::OrbitCamera;
+    use crate::color::{decode_srgb, encode_srgb, srgb_to_linear, LinearRgb};
+    use crate::depth_buffer::DepthBuffer;
+    use crate::draw_modes::blend::blend::{blend_pixel, BlendState};
+    use crate::draw_modes::rasterize::rasterize::{
+        interpolate_point2, interpolate_point3, project_face, rasterize_triangle, uv_derivatives,
+    };
+    use crate::environment::Environment;
+    use crate::gltf_loader::PbrMaterial;
+    use crate::hdr_target::HdrTarget;
+    use crate::image_canvas::Rgb;
+    use crate::linear_algebra::{
+        calculate_intensity, calculate_normal, Matrix3, Point2, Point3, TriangleCoords3,
+    };
+    use crate::texture_cache::{TextureCache, TextureHandle};
+    use crate::texture_sampler::SamplerSettings;
+    use crate::wavefront_parser::{Vertex, WavefronObject};
+
+    // Alpha is the opacity times the texture's alpha times the face's MTL `d`. Fragments
+    // below `alpha_test` are dropped before they touch the depth buffer, the rest are
+    // blended into the canvas. Under an environment, `reflectivity` of the color comes
+    // from its reflection instead, blurrier the higher `roughness` is.
+    #[derive(Clone)]
+    pub struct Material {
+        pub name: String,
+        pub color: Rgb,
+        pub texture: Option<TextureHandle>,
+        pub opacity: f64,
+        pub blend: BlendState,
+        pub alpha_test: Option<f64>,
+        pub depth_write: bool,
+        pub reflectivity: f64,
+        pub roughness: f64,
+    }
+
+    impl Default for Material {
+        fn default() -> Self {
+            Self {
+                name: String::from("default"),
+                color: [255; 3],
+                texture: None,
+                opacity: 1.0,
+                blend: BlendState::default(),
+                alpha_test: None,
+                depth_write: true,
+                reflectivity: 0.0,
+                roughness: DEFAULT_ROUGHNESS,
+            }
+        }
+    }
+
+    impl Material {
+        // The renderer is not PBR yet, so besides the base color and its texture only the
+        // factors carry over, metals being the ones that reflect. glTF factors are linear,
+        // material colors sRGB like everything else 8-bit.
+        pub fn from_pbr(pbr: &PbrMaterial, textures: &mut TextureCache) -> Self {
+            let [r, g, b, _] = pbr.base_color;
+
+            Self {
+                name: pbr.name.clone(),
+                color: encode_srgb([r, g, b]),
+                texture: pbr
+                    .base_color_texture
+                    .clone()
+                    .map(|canvas| textures.insert(canvas)),
+                reflectivity: pbr.metallic.clamp(0.0, 1.0),
+                roughness: pbr.roughness.clamp(0.0, 1.0),
+                ..Material::default()
+            }
+        }
+
+        // Whether drawing order matters, i.e. the mesh has to wait for the opaque ones and
+        // be drawn back to front.
+        pub fn is_transparent(&self, model: &WavefronObject) -> bool {
+            let translucent = self.opacity < 1.0
+                || self.texture.as_ref().is_some_and(|t| t.has_alpha())
+                || model
+                    .get_materials()
+                    .iter()
+                    .any(|m| m.dissolve.is_some_and(|d| d < 1.0));
+
+            match self.blend.is_replace_when_opaque() {
+                true => translucent && self.blend != BlendState::OPAQUE,
+                false => true,
+            }
+        }
+    }
+
+    pub const DEFAULT_ROUGHNESS: f64 = 0.5;
+
+    // Directions are the way the light travels, in the same space as the mesh.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct SceneLight {
+        pub direction: Point3<f64>,
+        pub intensity: f64,
+        pub color: Rgb,
+    }
+
+    // Image-based lighting. `to_environment` takes directions from the space of the mesh
+    // into the map's, and `intensity` scales everything read from it.
+    #[derive(Clone, Copy)]
+    pub struct EnvironmentLight<'a> {
+        pub map: &'a Environment,
+        pub to_environment: Matrix3,
+        pub intensity: f64,
+    }
+
+    impl EnvironmentLight<'_> {
+        fn lookup(&self, direction: Point3<f64>) -> Point3<f64> {
+            (self.to_environment * direction).normalize()
+        }
+
+        pub fn irradiance(&self, normal: Point3<f64>) -> LinearRgb {
+            self.map
+                .irradiance(self.lookup(normal))
+                .map(|c| c * self.intensity)
+        }
+
+        pub fn specular(&self, direction: Point3<f64>, roughness: f64) -> LinearRgb {
+            self.map
+                .specular(self.lookup(direction), roughness)
+                .map(|c| c * self.intensity)
+        }
+
+        pub fn background(&self, direction: Point3<f64>) -> LinearRgb {
+            self.map
+                .sample(self.lookup(direction))
+                .map(|c| c * self.intensity)
+        }
+    }
+
+    fn face_uvs(model: &WavefronObject, textures: (usize, usize, usize)) -> [Point2<f64>; 3] {
+        let uv = |ti: usize| {
+            let (u, v) = model.get_texture_at_index(&ti).unwrap().unravel_uv_impl();
+
+            Point2(u, v)
+        };
+
+        [uv(textures.0), uv(textures.1), uv(textures.2)]
+    }
+
+    pub fn face_colors(model: &WavefronObject, verts: (usize, usize, usize)) -> [Point3<f64>; 3] {
+        let color = |vi: usize| {
+            let [r, g, b] = *model.get_color_at_index(&vi).unwrap();
+
+            Point3(r as f64, g as f64, b as f64)
+        };
+
+        [color(verts.0), color(verts.1), color(verts.2)]
+    }
+
+    fn face_dissolve(model: &WavefronObject, face: usize) -> f64 {
+        model
+            .get_faces()
+            .get(face)
+            .and_then(|f| f.get_tags().material)
+            .and_then(|m| model.get_materials()[m].dissolve)
+            .unwrap_or(1.0)
+    }
+
+    // Flat Lambert per face, summed over the lights, on top of an ambient term, all in
+    // linear light and left unclipped in the HDR target. An environment replaces the
+    // ambient term with its irradiance and adds its reflection. Texels are
+    // multiplied into the material color when the mesh has UVs, and so are the interpolated
+    // vertex colors when it has those. Transparent meshes draw their faces back to front,
+    // or, given an A-buffer, leave their fragments there for it to sort and never write
+    // depth.
+    #[allow(clippy::too_many_arguments)]
+    pub fn shade_material_mesh(
+        model: &WavefronObject,
+        material: &Material,
+        lights: &[SceneLight],
+        ambient: f64,
+        environment: Option<&EnvironmentLight>,
+        sampler: &SamplerSettings,
+        depth_buffer: &mut DepthBuffer,
+        a_buffer: Option<&mut ABuffer>,
+        target: &mut HdrTarget,
+    ) {
+        let (w, h) = depth_buffer.get_size();
+        let camera = OrbitCamera::default();
+
+        let face_vertices = model.get_vert_triplets_from_face_elements();
+        let face_textures = model.get_texture_triplets_from_elements();
+        let texture = material
+            .texture
+            .as_ref()
+            .filter(|_| face_textures.len() == face_vertices.len());
+        let has_colors = model.has_vertex_colors();
+        let transparent = material.is_transparent(model);
+        let has_alpha = transparent || material.alpha_test.is_some();
+        let mut a_buffer = a_buffer.filter(|_| transparent);
+
+        let projected = face_vertices
+            .iter()
+            .map(|verts| project_face(model, &camera, *verts, w, h))
+            .collect::<Vec<TriangleCoords3>>();
+        let mut order = (0..face_vertices.len()).collect::<Vec<usize>>();
+
+        // Larger depth is nearer, so the farthest centroid goes first.
+        if transparent && a_buffer.is_none() {
+            let centroid = |i: usize| (0..3).map(|k| projected[i][k][2]).sum::<f64>();
+
+            order.sort_by(|a, b| centroid(*a).total_cmp(&centroid(*b)));
+        }
+
+        for i in order {
+            let verts = &face_vertices[i];
+            let vertices = verts
+                .unravel_vec()
+                .into_iter()
+                .map(|vi| *model.get_vertex_at_index(&vi).unwrap())
+                .collect::<Vec<Vertex>>();
+
+            let normal = calculate_normal(vertices);
+            // Faces are wound so the normal points into the surface.
+            let outward = (normal * -1.0).normalize();
+            let mut light = match environment {
+                Some(environment) => environment.irradiance(outward),
+                None => [ambient; 3],
+            };
+
+            for scene_light in lights {
+                let lambert = calculate_intensity(normal, scene_light.direction.normalize());
+                let strength = lambert.max(0.0) * scene_light.intensity;
+
+                for (channel, color) in light.iter_mut().zip(scene_light.color) {
+                    *channel += strength * srgb_to_linear(color);
+                }
+            }
+
+            let uvs = texture.map(|_| face_uvs(model, face_textures[i].unravel()));
+            let colors = has_colors.then(|| {
+                face_colors(model, verts.unravel()).map(|Point3(r, g, b)| {
+                    let [r, g, b] = decode_srgb([r as u8, g as u8, b as u8]);
+
+                    Point3(r, g, b)
+                })
+            });
+            let coords = projected[i];
+            let derivatives = uvs.as_ref().map(|uvs| uv_derivatives(coords, uvs));
+            let face_alpha = material.opacity * face_dissolve(model, i);
+
+            // The camera is orthographic, so every face is seen along +z.
+            let reflection =
+                environment
+                    .filter(|_| material.reflectivity > 0.0)
+                    .map(|environment| {
+                        let view = Point3(0.0, 0.0, 1.0);
+                        let mirrored = outward * (2.0 * (outward * view)) - view;
+
+                        environment.specular(mirrored, material.roughness)
+                    });
+
+            rasterize_triangle(coords, w, h, |fragment| {
+                if !depth_buffer.test(fragment.x, fragment.y, fragment.depth) {
+                    return;
+                }
+
+                let uv = uvs
+                    .as_ref()
+                    .map(|uvs| interpolate_point2(uvs, fragment.barycentric));
+
+                let (texel, texel_alpha) = match (texture, uv, derivatives) {
+                    (Some(texture), Some(uv), Some(derivatives)) => (
+                        texture.sample_linear(uv, derivatives, sampler),
+                        match has_alpha {
+                            true => texture.sample_alpha(uv, derivatives, sampler),
+                            false => 1.0,
+                        },
+                    ),
+                    _ => ([1.0; 3], 1.0),
+                };
+
+                let alpha = face_alpha * texel_alpha;
+
+                if material
+                    .alpha_test
+                    .is_some_and(|threshold| alpha < threshold)
+                {
+                    return;
+                }
+
+                let vertex_color = match &colors {
+                    Some(colors) => interpolate_point3(colors, fragment.barycentric),
+                    None => Point3(1.0, 1.0, 1.0),
+                };
+                let base = decode_srgb(material.color);
+
+                let mut color: LinearRgb =
+                    std::array::from_fn(|c| base[c] * texel[c] * vertex_color[c] * light[c]);
+
+                if let Some(reflection) = reflection {
+                    let k = material.reflectivity;
+
+                    for c in 0..3 {
+                        color[c] = color[c] * (1.0 - k) + reflection[c] * k;
+                    }
+                }
+
+                if let Some(a_buffer) = a_buffer.as_deref_mut() {
+                    a_buffer.push(
+                        fragment.x,
+                        fragment.y,
+                        LayerFragment {
+                            depth: fragment.depth,
+                            color,
+                            alpha,
+                            blend: material.blend,
+                        },
+                    );
+
+                    return;
+                }
+
+                if material.depth_write {
+                    depth_buffer.set(fragment.x, fragment.y, fragment.depth);
+                }
+
+                blend_pixel(
+                    target,
+                    fragment.x,
+                    fragment.y,
+                    color,
+                    alpha,
+                    &material.blend,
+                );
+            });
+        }
+    }
+}
@@ -4,7 +4,7 @@ pub mod draw_triangle_threaded_with_zbuffer {
     use std::ops::Deref;
     use std::path::PathBuf;
 
-    use crate::image_canvas::{self, Canvas};
+    use crate::image_canvas::{self, Canvas, Rgb};
     use crate::linear_algebra::{
         calculate_intensity, calculate_normal_and_intensity, Point2, Point3, TriangleCoords,
         TriangleCoords3, UVTriplet,
@@ -13,16 +13,23 @@ pub mod draw_triangle_threaded_with_zbuffer {
     use crate::utils::swap;
     use crate::wavefront_parser::{Vertex, WavefronObject};
     use embedded_graphics::text;
+    use log::{info, log_enabled, trace, Level};
     use rand::Rng;
     use std::sync::{Arc, Mutex, MutexGuard};
+    use std::time::Instant;
     use std::{thread, vec};
 
-    pub fn draw_triangle_threaded_with_zbuffer(
+    // Every threaded mode draws its faces through here. `shade` gets each covered pixel with
+    // its barycentric coordinates once it passes the depth test (or always, without a
+    // z-buffer) and leaves the pixel alone when it returns `None`.
+    pub fn draw_triangle_threaded_shaded<S>(
         coords: TriangleCoords3,
-        zbuffer: &Mutex<Vec<f64>>,
+        zbuffer: Option<&Mutex<Vec<f64>>>,
         canvas_mutex: &Mutex<Canvas>,
-        color: u8,
-    ) {
+        mut shade: S,
+    ) where
+        S: FnMut(usize, usize, Point3<f64>) -> Option<Rgb>,
+    {
         let mut canvas_lock = match canvas_mutex.lock() {
             Ok(p_ok) => p_ok,
             Err(p_err) => p_err.into_inner(),
@@ -33,6 +40,10 @@ pub mod draw_triangle_threaded_with_zbuffer {
 
         let (w, h) = canvas_lock.get_size();
 
+        if w == 0 || h == 0 {
+            return;
+        }
+
         let clamp = Point2((w - 1) as f64, (h - 1) as f64);
 
         for i in 0..3 {
@@ -42,35 +53,67 @@ pub mod draw_triangle_threaded_with_zbuffer {
             }
         }
 
-        let pairs = bbox_min * bbox_max;
+        // Whole pixels, so neighbouring faces sample the same grid and leave no cracks.
+        let pairs = Point2(bbox_min.0.floor() as i32, bbox_min.1.floor() as i32)
+            * Point2(bbox_max.0.ceil() as i32, bbox_max.1.ceil() as i32);
 
-        let mut zbf = match zbuffer.lock() {
+        let mut zbf = zbuffer.map(|zbuffer| match zbuffer.lock() {
             Ok(p_ok) => p_ok,
             Err(p_err) => p_err.into_inner(),
-        };
+        });
 
         pairs
             .into_iter()
             .map(|(i, j)| Point3(i as f64, j as f64, 0.0f64))
             .map(|p| (p, coords.get_barycentric_coords(p)))
-            .map(|(mut p, bc)| {
+            .filter(|(_, bc)| bc.0 >= 0.0 && bc.1 >= 0.0 && bc.2 >= 0.0)
+            .for_each(|(mut p, bc)| {
                 for i in 0usize..3usize {
                     p.2 += coords[i][2] * bc[i];
                 }
 
-                p
-            })
-            .for_each(|p| {
-                if zbf[p.0 as usize + p.1 as usize * w] < p.2 {
-                    zbf[p.0 as usize + p.1 as usize * w] = p.2;
+                let (x, y) = (p.0 as usize, p.1 as usize);
+
+                if let Some(zbf) = zbf.as_mut() {
+                    if zbf[x + y * w] >= p.2 {
+                        return;
+                    }
+
+                    zbf[x + y * w] = p.2;
+                }
+
+                if let Some(color) = shade(x, y, bc) {
+                    let before =
+                        log_enabled!(Level::Trace).then(|| canvas_lock.get_pixel_impl(x, y));
+
                     canvas_lock
-                        .set_pixel(p.0 as i32, p.1 as i32, color)
-                        .unwrap()
+                        .set_pixel_rgb(x as i32, y as i32, color)
+                        .unwrap();
+
+                    trace!(
+                        x = x,
+                        y = y,
+                        before:? = before,
+                        after = canvas_lock.get_pixel_impl(x, y);
+                        "fragment written"
+                    );
                 }
             });
     }
 
+    pub fn draw_triangle_threaded_with_zbuffer(
+        coords: TriangleCoords3,
+        zbuffer: &Mutex<Vec<f64>>,
+        canvas_mutex: &Mutex<Canvas>,
+        color: u8,
+    ) {
+        draw_triangle_threaded_shaded(coords, Some(zbuffer), canvas_mutex, |_, _, _| {
+            Some([color; 3])
+        });
+    }
+
     pub fn shade_threaded_with_zbuffer(obj_path: PathBuf, image_canvas: &Arc<Mutex<Canvas>>) {
+        let start = Instant::now();
         let model = WavefronObject::new(obj_path);
 
         let canv = Arc::clone(image_canvas);
@@ -82,6 +125,7 @@ pub mod draw_triangle_threaded_with_zbuffer {
         let zbuffer = vec![-f64::MAX; w * h];
 
         let face_vertices = model.get_vert_triplets_from_face_elements();
+        let n_faces = face_vertices.len();
 
         let zbuffer_arc = Arc::new(Mutex::new(zbuffer));
 
@@ -116,6 +160,13 @@ pub mod draw_triangle_threaded_with_zbuffer {
             });
 
             ()
-        })
+        });
+
+        info!(
+            mode = "zbuffer",
+            faces = n_faces,
+            elapsed:? = start.elapsed();
+            "pass dispatched"
+        );
     }
 }
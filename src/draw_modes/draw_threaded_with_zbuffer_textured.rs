@@ -4,90 +4,48 @@ pub mod draw_triangle_threaded_with_zbuffer_texture {
     use std::ops::Deref;
     use std::path::PathBuf;
 
+    use crate::draw_modes::draw_threaded_with_zbuffer::draw_triangle_threaded_with_zbuffer::draw_triangle_threaded_shaded;
+    use crate::draw_modes::rasterize::rasterize::{interpolate_point2, uv_derivatives};
     use crate::image_canvas::{self, Canvas};
     use crate::linear_algebra::{
         calculate_intensity, calculate_normal_and_intensity, Point2, Point3, TriangleCoords,
         TriangleCoords3, UVTriplet,
     };
-    use crate::read_tga::read_tga;
+    use crate::texture_cache::TextureCache;
+    use crate::texture_sampler::{MipChain, SamplerSettings};
     use crate::utils::swap;
     use crate::wavefront_parser::{Vertex, WavefronObject};
     use embedded_graphics::text;
+    use log::{info, warn};
     use rand::Rng;
     use std::sync::{Arc, Mutex, MutexGuard};
+    use std::time::Instant;
     use std::{thread, vec};
 
     pub fn draw_triangle_threaded_with_zbuffer_with_texture(
         coords: TriangleCoords3,
         zbuffer: &Mutex<Vec<f64>>,
         canvas_mutex: &Mutex<Canvas>,
-        uv_points: &UVTriplet,
-        texture: &Vec<u8>,
+        uv_points: &[Point2<f64>; 3],
+        texture: &MipChain,
+        sampler: &SamplerSettings,
     ) {
+        let derivatives = uv_derivatives(coords, uv_points);
 
+        draw_triangle_threaded_shaded(coords, Some(zbuffer), canvas_mutex, |_, _, bc| {
+            let uv = interpolate_point2(uv_points, bc);
 
-        let mut canvas_lock = canvas_mutex.lock().unwrap();
-
-        let ss = canvas_lock.get_map();
-
-        let mut bbox_min = Point2(std::f64::MAX, std::f64::MAX);
-        let mut bbox_max = Point2(-std::f64::MAX, -std::f64::MAX);
-
-        let (w, h) = canvas_lock.get_size();
-
-        let clamp = Point2((w - 1) as f64, (h - 1) as f64);
-
-        for i in 0..3 {
-            for j in 0..2 {
-                bbox_min[j] = f64::max(0.0, f64::min(bbox_min[j], coords[i][j] as f64));
-                bbox_max[j] = f64::min(clamp[j], f64::max(bbox_max[j], coords[i][j] as f64));
-            }
-        }
-
-        let pairs = bbox_min * bbox_max;
-
-
-
-        let mut zbf = zbuffer.lock().unwrap();
-        pairs
-            .into_iter()
-            .map(|(i, j)| Point3(i as f64, j as f64, 1.0f64))
-            .map(|p| (p, coords.get_barycentric_coords(p)))
-            .map(|(mut p, bc)| {
-                for i in 0usize..3usize {
-                    p.2 += coords[i][2] * bc[i];
-                }
-                let color = uv_points.get_color(p, coords, texture);
-
-
-                (p, color)
-            })
-            .for_each(
-                |(p, color)| match zbf[p.0 as usize + p.1 as usize * w] < p.2 {
-                    true => {
-                        zbf[p.0 as usize + p.1 as usize * w] = p.2;
-
-                        println!("{} {} {}", p.0 as usize, p.1 as usize, canvas_lock.get_pixel_impl(p.0 as usize,  p.1 as usize));
-
-                        canvas_lock
-                            .set_pixel(p.0 as i32, p.1 as i32, color)
-                            .unwrap();
-
-                        println!("{} {} {}", p.0 as usize, p.1 as usize, canvas_lock.get_pixel_impl(p.0 as usize,  p.1 as usize));
-
-
-                    }
-                    false => (),
-                },
-            );
+            Some(texture.sample(uv, derivatives, sampler))
+        });
     }
 
     pub fn shade_threaded_with_zbuffer_with_texture(
         obj_path: PathBuf,
         texture_path: PathBuf,
+        sampler: SamplerSettings,
         image_canvas: &Arc<Mutex<Canvas>>,
     ) {
-
+        let start = Instant::now();
         let model = WavefronObject::new(obj_path);
 
         let canv = Arc::clone(image_canvas);
@@ -103,8 +61,14 @@ pub mod draw_triangle_threaded_with_zbuffer_texture {
         let zbuffer_arc = Arc::new(Mutex::new(zbuffer));
 
         let face_textures = model.get_texture_triplets_from_elements();
-        let texture = read_tga(texture_path);
-
+        let texture = match TextureCache::default().load(&texture_path) {
+            Ok(texture) => texture,
+            Err(err) => {
+                warn!(error:% = err; "could not load texture");
+                return;
+            }
+        };
+        let n_faces = face_vertices.len();
 
         face_vertices
             .into_iter()
@@ -131,11 +95,11 @@ pub mod draw_triangle_threaded_with_zbuffer_texture {
                     points_uv.push(Point2(u, v));
                 }
 
-                let uv_triplet = UVTriplet::from_vec(points_uv);
-
+                let uv_triplet = [points_uv[0], points_uv[1], points_uv[2]];
 
                 let pts = TriangleCoords3::from_vec(v3);
-                let texture_arc = Arc::new(texture.clone());
+                // Every face thread shares the one decoded texture.
+                let texture_arc = Arc::clone(&texture);
                 let arc_pts = Arc::new(pts);
                 let arc_img_clone = Arc::clone(&image_canvas.clone());
                 let zbuffer_clone = Arc::clone(&zbuffer_arc.clone());
@@ -155,10 +119,20 @@ pub mod draw_triangle_threaded_with_zbuffer_texture {
                         canvas_mutex,
                         uv_points,
                         texture,
+                        &sampler,
                     );
                 });
 
                 ()
-            })
+            });
+
+        info!(
+            mode = "textured_zbuffer",
+            faces = n_faces,
+            filter = sampler.filter.get_name(),
+            lod_bias = sampler.lod_bias,
+            elapsed:? = start.elapsed();
+            "pass dispatched"
+        );
     }
 }
@@ -12,8 +12,10 @@ pub mod draw_wireframe {
     use crate::utils::swap;
     use crate::wavefront_parser::{Vertex, WavefronObject};
     use embedded_graphics::text;
+    use log::info;
     use rand::Rng;
     use std::sync::{Arc, Mutex, MutexGuard};
+    use std::time::Instant;
     use std::{thread, vec};
     pub fn draw_line(from: Point2<i32>, to: Point2<i32>, image_canvas: &mut Canvas, color: u8) {
         let mut steep = false;
@@ -53,10 +55,12 @@ pub mod draw_wireframe {
     }
 
     pub fn wireframe_renderer(obj_path: PathBuf, image_canvas: &mut Canvas, color: u8) {
+        let start = Instant::now();
         let model = WavefronObject::new(obj_path);
-        let (h, w) = image_canvas.get_size();
+        let (w, h) = image_canvas.get_size();
 
         let face_vertices = model.get_vert_triplets_from_face_elements();
+        let n_faces = face_vertices.len();
 
         face_vertices.into_iter().for_each(|veretex_indices| {
             let combination = veretex_indices.combinate(2);
@@ -80,6 +84,13 @@ pub mod draw_wireframe {
 
                 draw_line(from, to, image_canvas, color);
             })
-        })
+        });
+
+        info!(
+            mode = "wireframe",
+            faces = n_faces,
+            elapsed:? = start.elapsed();
+            "pass finished"
+        );
     }
 }
@@ -13,11 +13,14 @@ pub mod flat_shader_renderer {
     use crate::utils::swap;
     use crate::wavefront_parser::{Vertex, WavefronObject};
     use embedded_graphics::text;
+    use log::info;
     use rand::Rng;
     use std::sync::{Arc, Mutex, MutexGuard};
+    use std::time::Instant;
     use std::{thread, vec};
 
     pub fn flat_shader_renderer(obj_path: PathBuf, image_canvas: &Arc<Mutex<Canvas>>) {
+        let start = Instant::now();
         let model = WavefronObject::new(obj_path);
 
         let canv = Arc::clone(&image_canvas);
@@ -27,6 +30,7 @@ pub mod flat_shader_renderer {
         let (w, h) = canv_for_size.get_size();
 
         let face_vertices = model.get_vert_triplets_from_face_elements();
+        let n_faces = face_vertices.len();
 
         face_vertices.into_iter().for_each(|vert_index| {
             let pt_vec = {
@@ -59,6 +63,13 @@ pub mod flat_shader_renderer {
             });
 
             ()
-        })
+        });
+
+        info!(
+            mode = "flat",
+            faces = n_faces,
+            elapsed:? = start.elapsed();
+            "pass dispatched"
+        );
     }
 }
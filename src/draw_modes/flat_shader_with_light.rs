@@ -3,6 +3,7 @@ pub mod flag_shader_with_light {
     use std::ops::Deref;
     use std::path::PathBuf;
 
+    use crate::color::encode_gray;
     use crate::draw_modes::draw_triangle::draw_triangle::draw_triangle_threaded;
     use crate::image_canvas::{self, Canvas};
     use crate::linear_algebra::{
@@ -13,8 +14,10 @@ pub mod flag_shader_with_light {
     use crate::utils::swap;
     use crate::wavefront_parser::{Vertex, WavefronObject};
     use embedded_graphics::text;
+    use log::info;
     use rand::Rng;
     use std::sync::{Arc, Mutex, MutexGuard};
+    use std::time::Instant;
     use std::{thread, vec};
 
     pub fn flat_shader_with_light_renderer(
@@ -22,6 +25,7 @@ pub mod flag_shader_with_light {
         light_dir: Point3<f64>,
         image_canvas: &Arc<Mutex<Canvas>>,
     ) {
+        let start = Instant::now();
         let model = WavefronObject::new(obj_path);
 
         let canv = Arc::clone(&image_canvas);
@@ -31,6 +35,7 @@ pub mod flag_shader_with_light {
         let (w, h) = canv_for_size.get_size();
 
         let face_vertices = model.get_vert_triplets_from_face_elements();
+        let n_faces = face_vertices.len();
 
         let mut i = 0;
 
@@ -67,12 +72,18 @@ pub mod flag_shader_with_light {
                 let intensity = arc_intensity.deref();
 
                 if *intensity > 0.0 {
-                    let color = (255.0 * *intensity).round() as i32;
-                    draw_triangle_threaded(*coords, canvas, color as u8);
+                    draw_triangle_threaded(*coords, canvas, encode_gray(intensity.min(1.0)));
                 }
             });
 
             ()
-        })
+        });
+
+        info!(
+            mode = "flat_light",
+            faces = n_faces,
+            elapsed:? = start.elapsed();
+            "pass dispatched"
+        );
     }
 }
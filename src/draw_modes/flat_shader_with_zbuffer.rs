@@ -13,14 +13,17 @@ pub mod flat_shader_with_zbuffer {
     use crate::utils::swap;
     use crate::wavefront_parser::{Vertex, WavefronObject};
     use embedded_graphics::text;
+    use log::info;
     use rand::Rng;
     use std::sync::{Arc, Mutex, MutexGuard};
+    use std::time::Instant;
     use std::{thread, vec};
 
     pub fn flat_shader_renderer_threaded_with_zbuffer(
         obj_path: PathBuf,
         image_canvas: &Arc<Mutex<Canvas>>,
     ) {
+        let start = Instant::now();
         let model = WavefronObject::new(obj_path);
 
         let canv = Arc::clone(&image_canvas);
@@ -30,6 +33,7 @@ pub mod flat_shader_with_zbuffer {
         let (w, h) = canv_for_size.get_size();
 
         let face_vertices = model.get_vert_triplets_from_face_elements();
+        let n_faces = face_vertices.len();
 
         face_vertices.into_iter().for_each(|vert_index| {
             let pt_vec = {
@@ -62,6 +66,13 @@ pub mod flat_shader_with_zbuffer {
             });
 
             ()
-        })
+        });
+
+        info!(
+            mode = "flat_zbuffer",
+            faces = n_faces,
+            elapsed:? = start.elapsed();
+            "pass dispatched"
+        );
     }
 }
@@ -1,3 +1,9 @@
+pub mod blend;
+pub mod draw_debug;
+pub mod draw_depth;
+pub mod draw_hidden_line;
+pub mod draw_primitives;
+pub mod draw_scene;
 pub mod draw_threaded_with_zbuffer;
 pub mod draw_threaded_with_zbuffer_textured;
 pub mod draw_triangle;
@@ -5,3 +11,4 @@ pub mod draw_wireframe;
 pub mod flat_shader_renderer;
 pub mod flat_shader_with_light;
 pub mod flat_shader_with_zbuffer;
+pub mod rasterize;
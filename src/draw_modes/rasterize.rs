@@ -0,0 +1,150 @@
+pub mod rasterize {
+    use crate::camera::OrbitCamera;
+    use crate::linear_algebra::{Point2, Point3, TriangleCoords3, Vec3Unsigned};
+    use crate::texture_sampler::UvDerivatives;
+    use crate::wavefront_parser::WavefronObject;
+
+    #[derive(Clone, Copy, Debug)]
+    pub struct Fragment {
+        pub x: usize,
+        pub y: usize,
+        pub depth: f64,
+        pub barycentric: Point3<f64>,
+    }
+
+    pub fn project_vertex(
+        model: &WavefronObject,
+        camera: &OrbitCamera,
+        index: usize,
+        w: usize,
+        h: usize,
+    ) -> Point3<f64> {
+        let vertex = model.get_vertex_at_index(&index).unwrap();
+
+        camera
+            .transform_point(vertex.xyz)
+            .from_world_to_screen(w, h)
+    }
+
+    pub fn project_face(
+        model: &WavefronObject,
+        camera: &OrbitCamera,
+        verts: Vec3Unsigned<usize>,
+        w: usize,
+        h: usize,
+    ) -> TriangleCoords3 {
+        let v3 = verts
+            .unravel_vec()
+            .into_iter()
+            .map(|vi| project_vertex(model, camera, vi, w, h))
+            .collect::<Vec<Point3<f64>>>();
+
+        TriangleCoords3::from_vec(v3)
+    }
+
+    pub fn rasterize_triangle<F: FnMut(Fragment)>(
+        coords: TriangleCoords3,
+        w: usize,
+        h: usize,
+        mut shade: F,
+    ) {
+        if w == 0 || h == 0 {
+            return;
+        }
+
+        let mut bbox_min = Point2(f64::MAX, f64::MAX);
+        let mut bbox_max = Point2(-f64::MAX, -f64::MAX);
+        let clamp = Point2((w - 1) as f64, (h - 1) as f64);
+
+        for i in 0..3 {
+            for j in 0..2 {
+                bbox_min[j] = f64::max(0.0, f64::min(bbox_min[j], coords[i][j]));
+                bbox_max[j] = f64::min(clamp[j], f64::max(bbox_max[j], coords[i][j]));
+            }
+        }
+
+        if bbox_min.0 > bbox_max.0 || bbox_min.1 > bbox_max.1 {
+            return;
+        }
+
+        for y in bbox_min.1.floor() as usize..=bbox_max.1.ceil() as usize {
+            for x in bbox_min.0.floor() as usize..=bbox_max.0.ceil() as usize {
+                let bc = coords.get_barycentric_coords(Point3(x as f64, y as f64, 0.0));
+
+                if bc.0 < 0.0 || bc.1 < 0.0 || bc.2 < 0.0 {
+                    continue;
+                }
+
+                let depth = (0..3).map(|i| coords[i][2] * bc[i]).sum::<f64>();
+
+                shade(Fragment {
+                    x,
+                    y,
+                    depth,
+                    barycentric: bc,
+                });
+            }
+        }
+    }
+
+    pub fn interpolate_point3(values: &[Point3<f64>; 3], barycentric: Point3<f64>) -> Point3<f64> {
+        values[0] * barycentric.0 + values[1] * barycentric.1 + values[2] * barycentric.2
+    }
+
+    pub fn interpolate_point2(values: &[Point2<f64>; 3], barycentric: Point3<f64>) -> Point2<f64> {
+        let u =
+            values[0].0 * barycentric.0 + values[1].0 * barycentric.1 + values[2].0 * barycentric.2;
+        let v =
+            values[0].1 * barycentric.0 + values[1].1 * barycentric.1 + values[2].1 * barycentric.2;
+
+        Point2(u, v)
+    }
+
+    // The projection is orthographic, so UVs are affine in screen space and their
+    // derivatives are the same for every fragment of a triangle. Degenerate triangles get
+    // zero derivatives, which samples the base level.
+    pub fn uv_derivatives(coords: TriangleCoords3, uvs: &[Point2<f64>; 3]) -> UvDerivatives {
+        let (e1x, e1y) = (coords[1][0] - coords[0][0], coords[1][1] - coords[0][1]);
+        let (e2x, e2y) = (coords[2][0] - coords[0][0], coords[2][1] - coords[0][1]);
+        let det = e1x * e2y - e2x * e1y;
+
+        if det.abs() < f64::EPSILON {
+            return UvDerivatives {
+                ddx: Point2(0.0, 0.0),
+                ddy: Point2(0.0, 0.0),
+            };
+        }
+
+        let (du1, dv1) = (uvs[1].0 - uvs[0].0, uvs[1].1 - uvs[0].1);
+        let (du2, dv2) = (uvs[2].0 - uvs[0].0, uvs[2].1 - uvs[0].1);
+
+        UvDerivatives {
+            ddx: Point2((du1 * e2y - du2 * e1y) / det, (dv1 * e2y - dv2 * e1y) / det),
+            ddy: Point2((du2 * e1x - du1 * e2x) / det, (dv2 * e1x - dv1 * e2x) / det),
+        }
+    }
+
+    pub fn rasterize_line<F: FnMut(usize, usize, f64)>(
+        from: Point3<f64>,
+        to: Point3<f64>,
+        w: usize,
+        h: usize,
+        mut plot: F,
+    ) {
+        let (dx, dy, dz) = (to.0 - from.0, to.1 - from.1, to.2 - from.2);
+        let steps = f64::max(dx.abs(), dy.abs()).ceil().max(1.0) as usize;
+
+        for step in 0..=steps {
+            let t = step as f64 / steps as f64;
+
+            let x = (from.0 + dx * t).round();
+            let y = (from.1 + dy * t).round();
+
+            if x < 0.0 || y < 0.0 || x >= w as f64 || y >= h as f64 {
+                continue;
+            }
+
+            plot(x as usize, y as usize, from.2 + dz * t);
+        }
+    }
+}
@@ -0,0 +1,354 @@
+use std::f64::consts::PI;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use log::info;
+
+use crate::color::{decode_srgb, LinearRgb};
+use crate::linear_algebra::Point3;
+use crate::texture_loader::{load_texture, Texture, TextureError};
+
+pub const DEFAULT_SPECULAR_LEVELS: usize = 6;
+
+// Widths of the first blurred level and of the map the irradiance is projected from.
+const SPECULAR_WIDTH: usize = 256;
+const IRRADIANCE_WIDTH: usize = 128;
+
+// Convolution constants of a clamped cosine lobe for SH bands 0, 1 and 2.
+const BAND_WEIGHTS: [f64; 3] = [PI, 2.0 * PI / 3.0, PI / 4.0];
+
+// Linear radiance over the sphere, longitude across and latitude up, so the bottom row
+// looks straight down. The centre column looks along -z, away from the default camera.
+#[derive(Clone, Debug)]
+struct EquirectMap {
+    width: usize,
+    height: usize,
+    texels: Vec<LinearRgb>,
+}
+
+fn sh_basis(d: Point3<f64>) -> [f64; 9] {
+    let Point3(x, y, z) = d;
+
+    [
+        0.282095,
+        0.488603 * y,
+        0.488603 * z,
+        0.488603 * x,
+        1.092548 * x * y,
+        1.092548 * y * z,
+        0.315392 * (3.0 * z * z - 1.0),
+        1.092548 * x * z,
+        0.546274 * (x * x - y * y),
+    ]
+}
+
+fn band(coefficient: usize) -> usize {
+    match coefficient {
+        0 => 0,
+        1..=3 => 1,
+        _ => 2,
+    }
+}
+
+impl EquirectMap {
+    fn direction(&self, x: usize, y: usize) -> Point3<f64> {
+        let longitude = ((x as f64 + 0.5) / self.width as f64 - 0.5) * 2.0 * PI;
+        let latitude = ((y as f64 + 0.5) / self.height as f64 - 0.5) * PI;
+
+        Point3(
+            latitude.cos() * longitude.sin(),
+            latitude.sin(),
+            -latitude.cos() * longitude.cos(),
+        )
+    }
+
+    fn solid_angle(&self, y: usize) -> f64 {
+        let latitude = ((y as f64 + 0.5) / self.height as f64 - 0.5) * PI;
+
+        (2.0 * PI / self.width as f64) * (PI / self.height as f64) * latitude.cos()
+    }
+
+    fn get(&self, x: i64, y: i64) -> LinearRgb {
+        let x = x.rem_euclid(self.width as i64) as usize;
+        let y = y.clamp(0, self.height as i64 - 1) as usize;
+
+        self.texels[x + y * self.width]
+    }
+
+    // Bilinear, wrapping around in longitude.
+    fn sample(&self, d: Point3<f64>) -> LinearRgb {
+        let Point3(x, y, z) = d.normalize();
+        let u = 0.5 + x.atan2(-z) / (2.0 * PI);
+        let v = 0.5 + y.clamp(-1.0, 1.0).asin() / PI;
+
+        let (fx, fy) = (u * self.width as f64 - 0.5, v * self.height as f64 - 0.5);
+        let (x0, y0) = (fx.floor(), fy.floor());
+        let (tx, ty) = (fx - x0, fy - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let [a, b, c, d] = [
+            self.get(x0, y0),
+            self.get(x0 + 1, y0),
+            self.get(x0, y0 + 1),
+            self.get(x0 + 1, y0 + 1),
+        ];
+
+        std::array::from_fn(|i| {
+            let bottom = a[i] + (b[i] - a[i]) * tx;
+            let top = c[i] + (d[i] - c[i]) * tx;
+
+            bottom + (top - bottom) * ty
+        })
+    }
+
+    // Box filter weighted by solid angle, so the poles do not count for more than they
+    // cover. Never enlarges the map.
+    fn downsample(&self, width: usize) -> EquirectMap {
+        let width = width.clamp(1, self.width);
+        let height = (width * self.height / self.width).max(1);
+
+        let mut sums = vec![[0.0; 3]; width * height];
+        let mut weights = vec![0.0; width * height];
+
+        for y in 0..self.height {
+            let weight = self.solid_angle(y);
+            let dy = y * height / self.height;
+
+            for x in 0..self.width {
+                let at = x * width / self.width + dy * width;
+                let texel = self.texels[x + y * self.width];
+
+                for c in 0..3 {
+                    sums[at][c] += texel[c] * weight;
+                }
+
+                weights[at] += weight;
+            }
+        }
+
+        let texels = sums
+            .into_iter()
+            .zip(weights)
+            .map(|(sum, weight)| sum.map(|c| c / weight.max(f64::MIN_POSITIVE)))
+            .collect();
+
+        EquirectMap {
+            width,
+            height,
+            texels,
+        }
+    }
+
+    // Convolves with a normalized cos^power lobe around each texel's direction. Only the
+    // rows and columns the lobe can reach above 1% are visited.
+    fn prefilter(&self, power: f64) -> EquirectMap {
+        let directions = (0..self.width * self.height)
+            .map(|i| self.direction(i % self.width, i / self.width))
+            .collect::<Vec<Point3<f64>>>();
+        let reach = match power > 0.0 {
+            true => 0.01f64.powf(1.0 / power).clamp(0.0, 1.0).acos(),
+            false => PI / 2.0,
+        };
+        let row_reach = (reach / PI * self.height as f64).ceil() as i64 + 1;
+
+        let mut texels = vec![[0.0; 3]; self.texels.len()];
+
+        for y in 0..self.height {
+            let latitude = ((y as f64 + 0.5) / self.height as f64 - 0.5) * PI;
+
+            for x in 0..self.width {
+                let n = directions[x + y * self.width];
+                let mut sum = [0.0; 3];
+                let mut total = 0.0;
+
+                let rows = (y as i64 - row_reach).max(0)
+                    ..=(y as i64 + row_reach).min(self.height as i64 - 1);
+
+                for sy in rows {
+                    let sy = sy as usize;
+                    let row_latitude = ((sy as f64 + 0.5) / self.height as f64 - 0.5) * PI;
+                    let cos = latitude.cos().min(row_latitude.cos()).max(1e-6);
+                    let half = ((reach / cos) / (2.0 * PI) * self.width as f64).ceil() as i64 + 1;
+                    let columns = (2 * half + 1).min(self.width as i64);
+                    let solid_angle = self.solid_angle(sy);
+
+                    for k in 0..columns {
+                        let sx = (x as i64 - half + k).rem_euclid(self.width as i64) as usize;
+                        let at = sx + sy * self.width;
+                        let cosine = n * directions[at];
+
+                        if cosine <= 0.0 {
+                            continue;
+                        }
+
+                        let weight = cosine.powf(power) * solid_angle;
+
+                        for (s, texel) in sum.iter_mut().zip(self.texels[at]) {
+                            *s += texel * weight;
+                        }
+
+                        total += weight;
+                    }
+                }
+
+                texels[x + y * self.width] = match total > 0.0 {
+                    true => sum.map(|c| c / total),
+                    false => self.texels[x + y * self.width],
+                };
+            }
+        }
+
+        EquirectMap {
+            width: self.width,
+            height: self.height,
+            texels,
+        }
+    }
+
+    fn project_sh(&self) -> [LinearRgb; 9] {
+        let mut coefficients = [[0.0; 3]; 9];
+
+        for y in 0..self.height {
+            let solid_angle = self.solid_angle(y);
+
+            for x in 0..self.width {
+                let basis = sh_basis(self.direction(x, y));
+                let texel = self.texels[x + y * self.width];
+
+                for (coefficient, weight) in coefficients.iter_mut().zip(basis) {
+                    for c in 0..3 {
+                        coefficient[c] += texel[c] * weight * solid_angle;
+                    }
+                }
+            }
+        }
+
+        coefficients
+    }
+}
+
+// An environment prefiltered for lighting: second-order spherical harmonics for diffuse
+// irradiance, and levels blurred with a widening lobe for glossy reflections, roughness
+// zero being the sharp map and one the whole hemisphere. Directions are in the map's own
+// space; callers rotate into it.
+#[derive(Clone, Debug)]
+pub struct Environment {
+    radiance: EquirectMap,
+    specular: Vec<EquirectMap>,
+    irradiance: [LinearRgb; 9],
+}
+
+impl Environment {
+    pub fn from_texels(width: usize, height: usize, texels: Vec<LinearRgb>, levels: usize) -> Self {
+        let radiance = EquirectMap {
+            width,
+            height,
+            texels,
+        };
+        let levels = levels.max(2);
+
+        let specular = (1..levels)
+            .map(|level| {
+                let roughness = level as f64 / (levels - 1) as f64;
+                let alpha = roughness * roughness;
+                // The Phong exponent whose lobe roughly matches GGX at this roughness.
+                let power = (2.0 / (alpha * alpha) - 2.0).max(0.0);
+                let width = (SPECULAR_WIDTH >> (level - 1)).max(8);
+
+                radiance.downsample(width).prefilter(power)
+            })
+            .collect();
+
+        let irradiance = radiance.downsample(IRRADIANCE_WIDTH).project_sh();
+
+        Self {
+            radiance,
+            specular,
+            irradiance,
+        }
+    }
+
+    // HDR files are linear already; 8-bit images are decoded from sRGB.
+    pub fn load(path: PathBuf) -> Result<Self, TextureError> {
+        let start = Instant::now();
+
+        let (width, height, texels) = match load_texture(path.clone())? {
+            Texture::Hdr(image) => (
+                image.width,
+                image.height,
+                image
+                    .texels
+                    .iter()
+                    .map(|texel| texel.map(|c| c as f64))
+                    .collect(),
+            ),
+            Texture::Ldr(canvas, _) => {
+                let (w, h) = canvas.get_size();
+
+                (
+                    w,
+                    h,
+                    (0..w * h)
+                        .map(|i| decode_srgb(canvas.get_pixel_rgb(i % w, i / w)))
+                        .collect(),
+                )
+            }
+        };
+
+        let environment = Self::from_texels(width, height, texels, DEFAULT_SPECULAR_LEVELS);
+
+        info!(
+            path:% = path.display(),
+            width = width,
+            height = height,
+            levels = environment.get_level_count(),
+            elapsed:? = start.elapsed();
+            "environment prefiltered"
+        );
+
+        Ok(environment)
+    }
+
+    pub fn get_size(&self) -> (usize, usize) {
+        (self.radiance.width, self.radiance.height)
+    }
+
+    // Including the sharp map.
+    pub fn get_level_count(&self) -> usize {
+        self.specular.len() + 1
+    }
+
+    pub fn sample(&self, direction: Point3<f64>) -> LinearRgb {
+        self.radiance.sample(direction)
+    }
+
+    // Cosine-weighted light arriving at a surface facing `normal`, divided by pi so a
+    // white Lambert surface reflects it as is.
+    pub fn irradiance(&self, normal: Point3<f64>) -> LinearRgb {
+        let basis = sh_basis(normal.normalize());
+
+        std::array::from_fn(|c| {
+            let sum = (0..9)
+                .map(|i| BAND_WEIGHTS[band(i)] * self.irradiance[i][c] * basis[i])
+                .sum::<f64>();
+
+            (sum / PI).max(0.0)
+        })
+    }
+
+    // Blends the two levels on either side of `roughness`.
+    pub fn specular(&self, direction: Point3<f64>, roughness: f64) -> LinearRgb {
+        let position = roughness.clamp(0.0, 1.0) * self.specular.len() as f64;
+        let lower = (position.floor() as usize).min(self.specular.len() - 1);
+        let t = position - lower as f64;
+
+        let level = |i: usize| match i {
+            0 => self.radiance.sample(direction),
+            _ => self.specular[i - 1].sample(direction),
+        };
+
+        let (a, b) = (level(lower), level(lower + 1));
+
+        std::array::from_fn(|c| a[c] + (b[c] - a[c]) * t)
+    }
+}
@@ -0,0 +1,221 @@
+use crate::linear_algebra::Point3;
+use crate::wavefront_parser::{ElementTags, FaceTriplet};
+
+// Samples per parametric direction when a file does not say otherwise.
+pub const DEFAULT_TESSELLATION: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Basis {
+    Bezier,
+    BSpline,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FreeFormType {
+    pub basis: Basis,
+    pub rational: bool,
+}
+
+impl FreeFormType {
+    // Arguments of `cstype`; bmatrix, cardinal and taylor are not supported.
+    pub fn from_args(args: &[&str]) -> Option<Self> {
+        let (rational, name) = match args {
+            ["rat", name] => (true, *name),
+            [name] => (false, *name),
+            _ => return None,
+        };
+
+        let basis = match name {
+            "bezier" => Basis::Bezier,
+            "bspline" => Basis::BSpline,
+            _ => return None,
+        };
+
+        Some(Self { basis, rational })
+    }
+
+    pub fn get_args(&self) -> String {
+        let name = match self.basis {
+            Basis::Bezier => "bezier",
+            Basis::BSpline => "bspline",
+        };
+
+        match self.rational {
+            true => format!("rat {}", name),
+            false => name.to_string(),
+        }
+    }
+}
+
+// Control points are 1-based vertex indices, as in faces. `parameters` are the raw `parm`
+// values: knots for B-splines, segment breakpoints for Bezier.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FreeFormCurve {
+    pub kind: FreeFormType,
+    pub degree: usize,
+    pub range: (f64, f64),
+    pub control: Vec<usize>,
+    pub parameters: Vec<f64>,
+    pub tags: ElementTags,
+}
+
+// Control points run along u first, one row of them per v.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FreeFormSurface {
+    pub kind: FreeFormType,
+    pub degree: (usize, usize),
+    pub range_u: (f64, f64),
+    pub range_v: (f64, f64),
+    pub control: Vec<FaceTriplet>,
+    pub parameters_u: Vec<f64>,
+    pub parameters_v: Vec<f64>,
+    pub tags: ElementTags,
+}
+
+// A Bezier with breakpoints b0..bk is the B-spline whose knots repeat every interior
+// breakpoint `degree` times and the ends once more, so both go through de Boor.
+pub fn knot_vector(
+    basis: Basis,
+    degree: usize,
+    parameters: &[f64],
+    n_control: usize,
+) -> Option<Vec<f64>> {
+    if degree == 0 || n_control <= degree {
+        return None;
+    }
+
+    match basis {
+        Basis::BSpline => {
+            let valid = parameters.len() == n_control + degree + 1
+                && parameters.windows(2).all(|k| k[0] <= k[1]);
+
+            valid.then(|| parameters.to_vec())
+        }
+        Basis::Bezier => {
+            let segments = (n_control - 1) / degree;
+
+            if !(n_control - 1).is_multiple_of(degree) || parameters.len() != segments + 1 {
+                return None;
+            }
+
+            let mut knots = vec![parameters[0]];
+
+            for breakpoint in parameters {
+                knots.extend(std::iter::repeat_n(*breakpoint, degree));
+            }
+
+            knots.push(parameters[segments]);
+
+            Some(knots)
+        }
+    }
+}
+
+fn lerp4(a: [f64; 4], b: [f64; 4], t: f64) -> [f64; 4] {
+    [0, 1, 2, 3].map(|i| a[i] + (b[i] - a[i]) * t)
+}
+
+// Points are homogeneous (x w, y w, z w, w) so rational curves come out right.
+pub fn de_boor(knots: &[f64], degree: usize, control: &[[f64; 4]], t: f64) -> [f64; 4] {
+    let n = control.len();
+    let mut k = degree;
+
+    while k + 1 < n && knots[k + 1] <= t {
+        k += 1;
+    }
+
+    let mut d = (0..=degree)
+        .map(|j| control[j + k - degree])
+        .collect::<Vec<[f64; 4]>>();
+
+    for r in 1..=degree {
+        for j in (r..=degree).rev() {
+            let i = j + k - degree;
+            let span = knots[i + degree + 1 - r] - knots[i];
+            let alpha = match span.abs() > f64::EPSILON {
+                true => (t - knots[i]) / span,
+                false => 0.0,
+            };
+
+            d[j] = lerp4(d[j - 1], d[j], alpha);
+        }
+    }
+
+    d[degree]
+}
+
+pub fn from_homogeneous(p: [f64; 4]) -> Point3<f64> {
+    let w = match p[3].abs() > f64::EPSILON {
+        true => p[3],
+        false => 1.0,
+    };
+
+    Point3(p[0] / w, p[1] / w, p[2] / w)
+}
+
+pub fn sample_range(range: (f64, f64), resolution: usize) -> impl Iterator<Item = f64> {
+    let (start, end) = range;
+
+    (0..=resolution).map(move |i| start + (end - start) * i as f64 / resolution as f64)
+}
+
+pub fn tessellate_curve(
+    curve: &FreeFormCurve,
+    control: &[[f64; 4]],
+    resolution: usize,
+) -> Option<Vec<Point3<f64>>> {
+    let knots = knot_vector(
+        curve.kind.basis,
+        curve.degree,
+        &curve.parameters,
+        control.len(),
+    )?;
+
+    Some(
+        sample_range(curve.range, resolution)
+            .map(|t| from_homogeneous(de_boor(&knots, curve.degree, control, t)))
+            .collect(),
+    )
+}
+
+// Returns the (resolution + 1)² sample grid, u running fastest like the control points.
+pub fn tessellate_surface(
+    surface: &FreeFormSurface,
+    control: &[[f64; 4]],
+    resolution: usize,
+) -> Option<Vec<Point3<f64>>> {
+    let (degree_u, degree_v) = surface.degree;
+    let n_u = match surface.kind.basis {
+        Basis::BSpline => surface.parameters_u.len().checked_sub(degree_u + 1)?,
+        Basis::Bezier => surface.parameters_u.len().checked_sub(1)? * degree_u + 1,
+    };
+
+    if n_u == 0 || !control.len().is_multiple_of(n_u) {
+        return None;
+    }
+
+    let n_v = control.len() / n_u;
+    let knots_u = knot_vector(surface.kind.basis, degree_u, &surface.parameters_u, n_u)?;
+    let knots_v = knot_vector(surface.kind.basis, degree_v, &surface.parameters_v, n_v)?;
+
+    let mut grid = vec![];
+
+    for t in sample_range(surface.range_v, resolution) {
+        // Collapse each v-column first, then the u row that is left.
+        let row = (0..n_u)
+            .map(|i| {
+                let column = (0..n_v)
+                    .map(|j| control[i + j * n_u])
+                    .collect::<Vec<[f64; 4]>>();
+
+                de_boor(&knots_v, degree_v, &column, t)
+            })
+            .collect::<Vec<[f64; 4]>>();
+
+        for s in sample_range(surface.range_u, resolution) {
+            grid.push(from_homogeneous(de_boor(&knots_u, degree_u, &row, s)));
+        }
+    }
+
+    Some(grid)
+}
@@ -0,0 +1,351 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use gltf::image::Format;
+use gltf::mesh::Mode;
+use log::{info, warn};
+
+use crate::image_canvas::{Canvas, CHANNELS};
+use crate::linear_algebra::{compose_affine, identity_affine, Affine, Matrix3, Point3};
+use crate::wavefront_mtl::MtlMaterial;
+use crate::wavefront_parser::WavefronObject;
+
+#[derive(Debug)]
+pub enum GltfError {
+    Import(PathBuf, gltf::Error),
+    NoMeshes(PathBuf),
+}
+
+impl fmt::Display for GltfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GltfError::Import(path, err) => write!(f, "{}: {}", path.display(), err),
+            GltfError::NoMeshes(path) => {
+                write!(
+                    f,
+                    "{}: no triangle meshes in the default scene",
+                    path.display()
+                )
+            }
+        }
+    }
+}
+
+// Metallic-roughness parameters as stored in the file; factors are linear and the
+// textures keep their own encoding.
+#[derive(Clone)]
+pub struct PbrMaterial {
+    pub name: String,
+    pub base_color: [f64; 4],
+    pub metallic: f64,
+    pub roughness: f64,
+    pub emissive: [f64; 3],
+    pub base_color_texture: Option<Canvas>,
+    pub metallic_roughness_texture: Option<Canvas>,
+    pub normal_texture: Option<Canvas>,
+}
+
+impl PbrMaterial {
+    // Both are linear, so the base color goes over as is.
+    pub fn to_mtl(&self) -> MtlMaterial {
+        let [r, g, b, a] = self.base_color;
+
+        MtlMaterial {
+            diffuse: Some([r, g, b]),
+            dissolve: (a < 1.0).then_some(a),
+            ..MtlMaterial::named(&self.name)
+        }
+    }
+}
+
+pub struct GltfPrimitive {
+    pub node: String,
+    pub model: WavefronObject,
+    pub material: Option<usize>,
+}
+
+pub struct GltfModel {
+    pub primitives: Vec<GltfPrimitive>,
+    pub materials: Vec<PbrMaterial>,
+}
+
+// Images come top row first; canvases are bottom-up, which lines up with the flipped v.
+fn image_to_canvas(image: &gltf::image::Data) -> Canvas {
+    let (w, h) = (image.width as usize, image.height as usize);
+
+    let (channels, bytes) = match image.format {
+        Format::R8 => (1, 1),
+        Format::R8G8 => (2, 1),
+        Format::R8G8B8 => (3, 1),
+        Format::R8G8B8A8 => (4, 1),
+        Format::R16 => (1, 2),
+        Format::R16G16 => (2, 2),
+        Format::R16G16B16 => (3, 2),
+        Format::R16G16B16A16 => (4, 2),
+        Format::R32G32B32FLOAT => (3, 4),
+        Format::R32G32B32A32FLOAT => (4, 4),
+    };
+
+    let channel = |texel: &[u8], c: usize| -> u8 {
+        let at = &texel[c * bytes..(c + 1) * bytes];
+
+        match bytes {
+            1 => at[0],
+            // Little-endian, so the high byte comes second.
+            2 => at[1],
+            _ => {
+                let value = f32::from_le_bytes([at[0], at[1], at[2], at[3]]);
+
+                (value.clamp(0.0, 1.0) * 255.0).round() as u8
+            }
+        }
+    };
+
+    let mut canvas = Canvas::new(w, h, 0);
+
+    for (row, texels) in image
+        .pixels
+        .chunks_exact(w * channels * bytes)
+        .enumerate()
+        .take(h)
+    {
+        for (x, texel) in texels.chunks_exact(channels * bytes).enumerate() {
+            let rgb = match channels {
+                1 | 2 => [channel(texel, 0); CHANNELS],
+                _ => [channel(texel, 0), channel(texel, 1), channel(texel, 2)],
+            };
+
+            canvas
+                .set_pixel_rgb(x as i32, (h - 1 - row) as i32, rgb)
+                .unwrap();
+        }
+    }
+
+    canvas
+}
+
+// glTF matrices are column-major.
+fn node_affine(node: &gltf::Node) -> Affine {
+    let m = node.transform().matrix().map(|col| col.map(|v| v as f64));
+
+    (
+        Matrix3([
+            [m[0][0], m[1][0], m[2][0]],
+            [m[0][1], m[1][1], m[2][1]],
+            [m[0][2], m[1][2], m[2][2]],
+        ]),
+        Point3(m[3][0], m[3][1], m[3][2]),
+    )
+}
+
+fn triangulate(mode: Mode, indices: &[usize]) -> Option<Vec<[usize; 3]>> {
+    let triangles = match mode {
+        Mode::Triangles => indices
+            .chunks_exact(3)
+            .map(|t| [t[0], t[1], t[2]])
+            .collect(),
+        // Every other strip triangle is wound backwards, so swap it back.
+        Mode::TriangleStrip => (2..indices.len())
+            .map(|i| match i % 2 {
+                0 => [indices[i - 2], indices[i - 1], indices[i]],
+                _ => [indices[i - 1], indices[i - 2], indices[i]],
+            })
+            .collect(),
+        Mode::TriangleFan => (2..indices.len())
+            .map(|i| [indices[0], indices[i - 1], indices[i]])
+            .collect(),
+        _ => return None,
+    };
+
+    Some(triangles)
+}
+
+fn load_primitive(
+    node_name: &str,
+    primitive: &gltf::Primitive,
+    buffers: &[gltf::buffer::Data],
+    world: Affine,
+) -> Option<GltfPrimitive> {
+    let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| &data.0[..]));
+    let (linear, translation) = world;
+
+    let positions = reader
+        .read_positions()?
+        .map(|[x, y, z]| linear * Point3(x as f64, y as f64, z as f64) + translation)
+        .collect::<Vec<Point3<f64>>>();
+
+    let normal_matrix = linear
+        .inverse()
+        .unwrap_or_else(Matrix3::identity)
+        .transpose();
+
+    let normals = reader
+        .read_normals()
+        .map(|normals| {
+            normals
+                .map(|[x, y, z]| (normal_matrix * Point3(x as f64, y as f64, z as f64)).normalize())
+                .collect::<Vec<Point3<f64>>>()
+        })
+        .unwrap_or_default();
+
+    // glTF puts v = 0 at the top of the image, OBJ at the bottom.
+    let uvs = reader
+        .read_tex_coords(0)
+        .map(|uvs| {
+            uvs.into_f32()
+                .map(|[u, v]| (u as f64, 1.0 - v as f64))
+                .collect::<Vec<(f64, f64)>>()
+        })
+        .unwrap_or_default();
+
+    let indices = match reader.read_indices() {
+        Some(indices) => indices.into_u32().map(|i| i as usize).collect(),
+        None => (0..positions.len()).collect::<Vec<usize>>(),
+    };
+
+    let mut triangles = match triangulate(primitive.mode(), &indices) {
+        Some(triangles) => triangles,
+        None => {
+            warn!(node = node_name, mode:? = primitive.mode(); "skipping non-triangle primitive");
+            return None;
+        }
+    };
+
+    if indices.iter().any(|&i| i >= positions.len()) {
+        warn!(node = node_name; "skipping primitive with out of range indices");
+        return None;
+    }
+
+    // A mirroring node transform flips the winding.
+    if linear.determinant() < 0.0 {
+        for t in triangles.iter_mut() {
+            t.swap(1, 2);
+        }
+    }
+
+    Some(GltfPrimitive {
+        node: node_name.to_string(),
+        model: WavefronObject::from_triangles(positions, uvs, normals, &triangles),
+        material: primitive.material().index(),
+    })
+}
+
+fn visit_node(
+    node: gltf::Node,
+    parent: Affine,
+    buffers: &[gltf::buffer::Data],
+    primitives: &mut Vec<GltfPrimitive>,
+) {
+    let world = compose_affine(parent, node_affine(&node));
+    let name = node
+        .name()
+        .map(String::from)
+        .unwrap_or_else(|| format!("node{}", node.index()));
+
+    if let Some(mesh) = node.mesh() {
+        for primitive in mesh.primitives() {
+            primitives.extend(load_primitive(&name, &primitive, buffers, world));
+        }
+    }
+
+    for child in node.children() {
+        visit_node(child, world, buffers, primitives);
+    }
+}
+
+impl GltfModel {
+    // Handles .gltf with external or embedded buffers and images as well as .glb.
+    pub fn load(path: PathBuf) -> Result<Self, GltfError> {
+        let (document, buffers, images) =
+            gltf::import(&path).map_err(|err| GltfError::Import(path.clone(), err))?;
+
+        let textures = images.iter().map(image_to_canvas).collect::<Vec<Canvas>>();
+        let texture = |info: Option<gltf::texture::Texture>| {
+            info.and_then(|t| textures.get(t.source().index()).cloned())
+        };
+
+        let materials = document
+            .materials()
+            .map(|material| {
+                let pbr = material.pbr_metallic_roughness();
+
+                // Named after their index when the file does not, so they stay apart
+                // once merged.
+                let name = material
+                    .name()
+                    .map(String::from)
+                    .unwrap_or_else(|| format!("material{}", material.index().unwrap_or(0)));
+
+                PbrMaterial {
+                    name,
+                    base_color: pbr.base_color_factor().map(|c| c as f64),
+                    metallic: pbr.metallic_factor() as f64,
+                    roughness: pbr.roughness_factor() as f64,
+                    emissive: material.emissive_factor().map(|c| c as f64),
+                    base_color_texture: texture(pbr.base_color_texture().map(|i| i.texture())),
+                    metallic_roughness_texture: texture(
+                        pbr.metallic_roughness_texture().map(|i| i.texture()),
+                    ),
+                    normal_texture: texture(material.normal_texture().map(|n| n.texture())),
+                }
+            })
+            .collect::<Vec<PbrMaterial>>();
+
+        let mut primitives = vec![];
+
+        if let Some(scene) = document
+            .default_scene()
+            .or_else(|| document.scenes().next())
+        {
+            for node in scene.nodes() {
+                visit_node(node, identity_affine(), &buffers, &mut primitives);
+            }
+        }
+
+        if primitives.is_empty() {
+            return Err(GltfError::NoMeshes(path));
+        }
+
+        info!(
+            path:% = path.display(),
+            primitives = primitives.len(),
+            materials = materials.len(),
+            textures = textures.len(),
+            faces = primitives.iter().map(|p| p.model.get_n_faces()).sum::<usize>();
+            "gltf model loaded"
+        );
+
+        Ok(Self {
+            primitives,
+            materials,
+        })
+    }
+
+    // Materials come along as their base color and alpha only; textures and the other
+    // factors need a scene file, which keeps the primitives apart.
+    pub fn merged(&self) -> WavefronObject {
+        let part = |primitive: &GltfPrimitive| match primitive.material {
+            Some(m) => primitive
+                .model
+                .clone()
+                .with_material(self.materials[m].to_mtl()),
+            None => primitive.model.clone(),
+        };
+
+        let mut merged = part(&self.primitives[0]);
+
+        for primitive in &self.primitives[1..] {
+            merged.append(&part(primitive));
+        }
+
+        merged
+    }
+
+    pub fn has_textures(&self) -> bool {
+        self.materials.iter().any(|m| {
+            m.base_color_texture.is_some()
+                || m.metallic_roughness_texture.is_some()
+                || m.normal_texture.is_some()
+        })
+    }
+}
@@ -0,0 +1,164 @@
+use crate::color::{encode_srgb_at, LinearRgb};
+use crate::image_canvas::Canvas;
+use crate::post_process::{convolve_separable, gaussian_kernel};
+
+pub const DEFAULT_BLOOM_THRESHOLD: f64 = 1.0;
+pub const DEFAULT_BLOOM_INTENSITY: f64 = 0.5;
+pub const DEFAULT_BLOOM_RADIUS: f64 = 8.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToneMapping {
+    // Values above one saturate, as with no HDR at all.
+    Clamp,
+    Reinhard,
+    // Narkowicz's fit of the ACES filmic curve.
+    Aces,
+}
+
+impl ToneMapping {
+    pub const ALL: [ToneMapping; 3] =
+        [ToneMapping::Clamp, ToneMapping::Reinhard, ToneMapping::Aces];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|t| t.get_name() == name)
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            ToneMapping::Clamp => "clamp",
+            ToneMapping::Reinhard => "reinhard",
+            ToneMapping::Aces => "aces",
+        }
+    }
+
+    pub fn apply(&self, c: f64) -> f64 {
+        let c = c.max(0.0);
+
+        match self {
+            ToneMapping::Clamp => c.min(1.0),
+            ToneMapping::Reinhard => c / (1.0 + c),
+            ToneMapping::Aces => {
+                ((c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14)).clamp(0.0, 1.0)
+            }
+        }
+    }
+}
+
+// Whatever exceeds `threshold` after exposure is blurred over roughly `radius` pixels and
+// added back `intensity` times.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BloomSettings {
+    pub threshold: f64,
+    pub intensity: f64,
+    pub radius: f64,
+}
+
+impl Default for BloomSettings {
+    fn default() -> Self {
+        Self {
+            threshold: DEFAULT_BLOOM_THRESHOLD,
+            intensity: DEFAULT_BLOOM_INTENSITY,
+            radius: DEFAULT_BLOOM_RADIUS,
+        }
+    }
+}
+
+// Exposure is in stops, so each +1 doubles the light before tone mapping.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ToneSettings {
+    pub operator: ToneMapping,
+    pub exposure: f64,
+    pub bloom: Option<BloomSettings>,
+}
+
+impl Default for ToneSettings {
+    fn default() -> Self {
+        Self {
+            operator: ToneMapping::Clamp,
+            exposure: 0.0,
+            bloom: None,
+        }
+    }
+}
+
+// Linear light in floats, laid out like a canvas with y = 0 the bottom row. Nothing is
+// clipped until `resolve` quantizes it.
+#[derive(Clone, Debug)]
+pub struct HdrTarget {
+    width: usize,
+    height: usize,
+    pixels: Vec<LinearRgb>,
+}
+
+impl HdrTarget {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![[0.0; 3]; width * height],
+        }
+    }
+
+    pub fn get_size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    pub fn clear(&mut self, color: LinearRgb) {
+        self.pixels.fill(color);
+    }
+
+    pub fn get_pixel(&self, x: usize, y: usize) -> Option<LinearRgb> {
+        match x < self.width && y < self.height {
+            true => Some(self.pixels[x + y * self.width]),
+            false => None,
+        }
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: LinearRgb) {
+        if x < self.width && y < self.height {
+            self.pixels[x + y * self.width] = color;
+        }
+    }
+
+    pub fn get_pixels(&self) -> &[LinearRgb] {
+        &self.pixels
+    }
+
+    // Exposure, then bloom, then the tone curve, then sRGB into `image_canvas`, which has
+    // to be the same size.
+    pub fn resolve(&self, settings: &ToneSettings, dither: bool, image_canvas: &mut Canvas) {
+        let scale = settings.exposure.exp2();
+        let mut exposed = self
+            .pixels
+            .iter()
+            .map(|pixel| pixel.map(|c| c * scale))
+            .collect::<Vec<LinearRgb>>();
+
+        if let Some(bloom) = settings.bloom.filter(|b| b.intensity > 0.0) {
+            let bright = exposed
+                .iter()
+                .map(|pixel| pixel.map(|c| (c - bloom.threshold).max(0.0)))
+                .collect::<Vec<LinearRgb>>();
+
+            for (pixel, glow) in exposed.iter_mut().zip(convolve_separable(
+                &bright,
+                self.width,
+                self.height,
+                &gaussian_kernel(bloom.radius),
+            )) {
+                for c in 0..3 {
+                    pixel[c] += glow[c] * bloom.intensity;
+                }
+            }
+        }
+
+        for (i, pixel) in exposed.into_iter().enumerate() {
+            let (x, y) = (i % self.width, i / self.width);
+            let mapped = pixel.map(|c| settings.operator.apply(c));
+
+            image_canvas
+                .set_pixel_rgb(x as i32, y as i32, encode_srgb_at(mapped, x, y, dither))
+                .unwrap();
+        }
+    }
+}
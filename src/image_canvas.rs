@@ -1,10 +1,11 @@
-use crate::linear_algebra::convert_to_screen_coords;
+use log::trace;
 use std::fs;
 use std::io::Write;
 use std::path::PathBuf;
 
-pub type CanvasType = Vec<Vec<u8>>;
+pub type Rgb = [u8; 3];
 
+pub const CHANNELS: usize = 3;
 
 #[derive(Debug)]
 pub enum ImageError {
@@ -14,65 +15,111 @@ pub enum ImageError {
 
 pub type ResultSet = std::result::Result<(), ImageError>;
 
+// Row-major RGB pixels, row `y` starting at byte `y * stride`; y = 0 is the bottom row.
 #[derive(Clone)]
-pub struct Canvas(CanvasType);
+pub struct Canvas {
+    width: usize,
+    height: usize,
+    stride: usize,
+    pixels: Vec<u8>,
+}
 
 impl Canvas {
     pub fn new(width: usize, height: usize, init_value: u8) -> Self {
-        let ys = vec![init_value; height];
-        let xs = vec![ys; width];
+        let stride = width * CHANNELS;
 
-        Canvas(xs)
+        Canvas {
+            width,
+            height,
+            stride,
+            pixels: vec![init_value; stride * height],
+        }
     }
 
-    pub fn set_pixel(&mut self, xi32: i32, yi32: i32, value: u8) -> ResultSet {
-        let (xi, yi) = (
-            convert_to_screen_coords(xi32),
-            convert_to_screen_coords(yi32),
-        );
+    fn get_offset(&self, x: usize, y: usize) -> Result<usize, ImageError> {
+        if y >= self.height {
+            return Err(ImageError::ErrorGettingRow);
+        }
+
+        if x >= self.width {
+            return Err(ImageError::ErrorGettingPixel);
+        }
 
-        println!("{} {} {}", xi32, yi32, value);
+        Ok(y * self.stride + x * CHANNELS)
+    }
 
+    pub fn set_pixel(&mut self, xi32: i32, yi32: i32, value: u8) -> ResultSet {
+        self.set_pixel_rgb(xi32, yi32, [value; 3])
+    }
 
-        let Canvas(canvas) = self;
+    // Writes outside the canvas are clipped, so callers that draw past the edges can keep
+    // unwrapping.
+    pub fn set_pixel_rgb(&mut self, xi32: i32, yi32: i32, value: Rgb) -> ResultSet {
+        trace!(x = xi32, y = yi32, value:? = value; "set pixel");
 
-        let y = canvas.get_mut(yi);
+        let (Ok(x), Ok(y)) = (usize::try_from(xi32), usize::try_from(yi32)) else {
+            return Ok(());
+        };
 
-        match y {
-            Some(xs) => {
-                let x = xs.get_mut(xi);
+        if let Ok(offset) = self.get_offset(x, y) {
+            self.pixels[offset..offset + CHANNELS].copy_from_slice(&value);
+        }
 
-                match x {
-                    Some(pixel) => {
-                        *pixel = value;
+        Ok(())
+    }
 
-                        Ok(())
-                    }
-                    None => Err(ImageError::ErrorGettingPixel),
-                }
-            }
-            None => Err(ImageError::ErrorGettingRow),
+    pub fn clear(&mut self, value: u8) {
+        self.pixels.fill(value);
+    }
+
+    pub fn clear_rgb(&mut self, color: Rgb) {
+        for pixel in self.pixels.chunks_exact_mut(CHANNELS) {
+            pixel.copy_from_slice(&color);
         }
     }
 
     pub fn get_pixel_impl(&self, x: usize, y: usize) -> u8 {
-        let Canvas(canvas) = self;
+        self.get_pixel_rgb(x, y)[0]
+    }
+
+    pub fn get_pixel_rgb(&self, x: usize, y: usize) -> Rgb {
+        let offset = self.get_offset(x, y).unwrap();
 
-        canvas[x][y]
+        [
+            self.pixels[offset],
+            self.pixels[offset + 1],
+            self.pixels[offset + 2],
+        ]
     }
 
     pub fn get_size(&self) -> (usize, usize) {
-        let Canvas(canvas) = self;
+        (self.width, self.height)
+    }
 
-        let h = canvas.len();
-        let w = canvas[0].len();
+    pub fn get_stride(&self) -> usize {
+        self.stride
+    }
 
-        (w, h)
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.pixels
     }
 
-    pub fn get_map(&self) -> CanvasType {
-        let Canvas(canvas) = self;
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        &mut self.pixels
+    }
+
+    pub fn row(&self, y: usize) -> Option<&[u8]> {
+        match y < self.height {
+            true => Some(&self.pixels[y * self.stride..(y + 1) * self.stride]),
+            false => None,
+        }
+    }
+
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &[u8]> {
+        self.pixels.chunks_exact(self.stride.max(1))
+    }
 
-        canvas.to_vec()
+    pub fn rows_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut [u8]> {
+        self.pixels.chunks_exact_mut(self.stride.max(1))
     }
 }
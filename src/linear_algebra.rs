@@ -5,7 +5,7 @@ use num_traits::{NumOps, PrimInt, Signed, ToPrimitive, Unsigned};
 #[derive(Clone, Copy, Debug)]
 pub struct Point2<T: Signed + NumOps + Clone + ToPrimitive>(pub T, pub T);
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Point3<T: Signed + NumOps + Clone + ToPrimitive>(pub T, pub T, pub T);
 
 #[derive(Clone, Copy, Debug)]
@@ -214,7 +214,7 @@ impl std::ops::BitXor for Point3<f64> {
         let Point3(ax, ay, az) = self;
         let Point3(bx, by, bz) = rhs;
 
-        let i = ay * bz - az * bz;
+        let i = ay * bz - az * by;
         let j = ax * bz - az * bx;
         let k = ax * by - ay * bx;
 
@@ -248,6 +248,336 @@ impl std::ops::Mul for Point3<f64> {
     }
 }
 
+impl std::ops::Add for Point3<f64> {
+    type Output = Point3<f64>;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let (x, y, z) = self.unravel();
+        let (xo, yo, zo) = rhs.unravel();
+
+        Point3(x + xo, y + yo, z + zo)
+    }
+}
+
+impl std::ops::Sub for Point3<f64> {
+    type Output = Point3<f64>;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        let (x, y, z) = self.unravel();
+        let (xo, yo, zo) = rhs.unravel();
+
+        Point3(x - xo, y - yo, z - zo)
+    }
+}
+
+impl std::ops::Mul<f64> for Point3<f64> {
+    type Output = Point3<f64>;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        let (x, y, z) = self.unravel();
+
+        Point3(x * rhs, y * rhs, z * rhs)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Matrix3(pub [[f64; 3]; 3]);
+
+impl Matrix3 {
+    pub fn identity() -> Self {
+        Matrix3([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]])
+    }
+
+    pub fn rotation_x(angle: f64) -> Self {
+        let (s, c) = angle.sin_cos();
+
+        Matrix3([[1.0, 0.0, 0.0], [0.0, c, -s], [0.0, s, c]])
+    }
+
+    pub fn rotation_y(angle: f64) -> Self {
+        let (s, c) = angle.sin_cos();
+
+        Matrix3([[c, 0.0, s], [0.0, 1.0, 0.0], [-s, 0.0, c]])
+    }
+
+    pub fn rotation_z(angle: f64) -> Self {
+        let (s, c) = angle.sin_cos();
+
+        Matrix3([[c, -s, 0.0], [s, c, 0.0], [0.0, 0.0, 1.0]])
+    }
+
+    pub fn transpose(&self) -> Self {
+        let Matrix3(m) = self;
+
+        Matrix3([
+            [m[0][0], m[1][0], m[2][0]],
+            [m[0][1], m[1][1], m[2][1]],
+            [m[0][2], m[1][2], m[2][2]],
+        ])
+    }
+
+    pub fn scale(s: Point3<f64>) -> Self {
+        let Point3(x, y, z) = s;
+
+        Matrix3([[x, 0.0, 0.0], [0.0, y, 0.0], [0.0, 0.0, z]])
+    }
+
+    pub fn determinant(&self) -> f64 {
+        let Matrix3(m) = self;
+
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    pub fn inverse(&self) -> Option<Self> {
+        let det = self.determinant();
+
+        if det.abs() < f64::EPSILON {
+            return None;
+        }
+
+        let Matrix3(m) = self;
+        let mut inv = [[0.0; 3]; 3];
+
+        // Adjugate over determinant; the cyclic indices give the cofactor signs for free.
+        for (i, row) in inv.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                let (r1, r2) = ((j + 1) % 3, (j + 2) % 3);
+                let (c1, c2) = ((i + 1) % 3, (i + 2) % 3);
+
+                *cell = (m[r1][c1] * m[r2][c2] - m[r1][c2] * m[r2][c1]) / det;
+            }
+        }
+
+        Some(Matrix3(inv))
+    }
+}
+
+impl std::ops::Mul for Matrix3 {
+    type Output = Matrix3;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let (Matrix3(a), Matrix3(b)) = (self, rhs);
+        let mut m = [[0.0; 3]; 3];
+
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                *cell = (0..3).map(|k| a[i][k] * b[k][j]).sum();
+            }
+        }
+
+        Matrix3(m)
+    }
+}
+
+impl std::ops::Mul<Point3<f64>> for Matrix3 {
+    type Output = Point3<f64>;
+
+    fn mul(self, rhs: Point3<f64>) -> Self::Output {
+        let Matrix3(m) = self;
+        let Point3(x, y, z) = rhs;
+
+        Point3(
+            m[0][0] * x + m[0][1] * y + m[0][2] * z,
+            m[1][0] * x + m[1][1] * y + m[1][2] * z,
+            m[2][0] * x + m[2][1] * y + m[2][2] * z,
+        )
+    }
+}
+
+// Unit quaternions only; every constructor and operation below keeps them normalized.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Quaternion {
+    pub fn identity() -> Self {
+        Self {
+            w: 1.0,
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        }
+    }
+
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Self {
+        Self { w, x, y, z }.normalize()
+    }
+
+    pub fn from_axis_angle(axis: Point3<f64>, angle: f64) -> Self {
+        let Point3(x, y, z) = axis.normalize();
+        let (s, c) = (angle / 2.0).sin_cos();
+
+        Self::new(c, x * s, y * s, z * s)
+    }
+
+    // Same order as the orbit camera: yaw about y first, then pitch about x, then roll about z.
+    pub fn from_euler_degrees(yaw: f64, pitch: f64, roll: f64) -> Self {
+        let qy = Self::from_axis_angle(Point3(0.0, 1.0, 0.0), yaw.to_radians());
+        let qx = Self::from_axis_angle(Point3(1.0, 0.0, 0.0), pitch.to_radians());
+        let qz = Self::from_axis_angle(Point3(0.0, 0.0, 1.0), roll.to_radians());
+
+        qz * qx * qy
+    }
+
+    pub fn dot(&self, rhs: &Self) -> f64 {
+        self.w * rhs.w + self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
+    }
+
+    pub fn normalize(&self) -> Self {
+        let length = self.dot(self).sqrt();
+
+        if length < f64::EPSILON {
+            return Self::identity();
+        }
+
+        Self {
+            w: self.w / length,
+            x: self.x / length,
+            y: self.y / length,
+            z: self.z / length,
+        }
+    }
+
+    // Takes the short way round, and falls back to a normalized lerp when the two are so
+    // close that sin(theta) would blow up.
+    pub fn slerp(&self, other: &Self, t: f64) -> Self {
+        let mut cos_theta = self.dot(other);
+        let mut end = *other;
+
+        if cos_theta < 0.0 {
+            cos_theta = -cos_theta;
+            end = Self {
+                w: -end.w,
+                x: -end.x,
+                y: -end.y,
+                z: -end.z,
+            };
+        }
+
+        let (a, b) = if cos_theta > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let theta = cos_theta.acos();
+            let sin_theta = theta.sin();
+
+            (
+                ((1.0 - t) * theta).sin() / sin_theta,
+                (t * theta).sin() / sin_theta,
+            )
+        };
+
+        Self::new(
+            a * self.w + b * end.w,
+            a * self.x + b * end.x,
+            a * self.y + b * end.y,
+            a * self.z + b * end.z,
+        )
+    }
+
+    pub fn get_rotation_matrix(&self) -> Matrix3 {
+        let Self { w, x, y, z } = *self;
+
+        Matrix3([
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+            ],
+            [
+                2.0 * (x * y + w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - w * x),
+            ],
+            [
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                1.0 - 2.0 * (x * x + y * y),
+            ],
+        ])
+    }
+}
+
+impl std::ops::Mul for Quaternion {
+    type Output = Quaternion;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let (a, b) = (self, rhs);
+
+        Quaternion::new(
+            a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+            a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+            a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+            a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+        )
+    }
+}
+
+// Scale, then rotate, then translate.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    pub translation: Point3<f64>,
+    pub rotation: Quaternion,
+    pub scale: Point3<f64>,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            translation: Point3(0.0, 0.0, 0.0),
+            rotation: Quaternion::identity(),
+            scale: Point3(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl Transform {
+    pub fn get_linear(&self) -> Matrix3 {
+        self.rotation.get_rotation_matrix() * Matrix3::scale(self.scale)
+    }
+
+    pub fn apply_point(&self, p: Point3<f64>) -> Point3<f64> {
+        self.get_linear() * p + self.translation
+    }
+
+    pub fn get_affine(&self) -> Affine {
+        (self.get_linear(), self.translation)
+    }
+
+    // World to camera space for a camera placed with this transform: undo the placement,
+    // then apply the scale as a zoom.
+    pub fn get_view_affine(&self) -> Affine {
+        let view = Matrix3::scale(self.scale) * self.rotation.get_rotation_matrix().transpose();
+
+        (view, view * (self.translation * -1.0))
+    }
+}
+
+// A linear part followed by a translation.
+pub type Affine = (Matrix3, Point3<f64>);
+
+pub fn identity_affine() -> Affine {
+    (Matrix3::identity(), Point3(0.0, 0.0, 0.0))
+}
+
+// `outer` is applied after `inner`.
+pub fn compose_affine(outer: Affine, inner: Affine) -> Affine {
+    let ((a_outer, b_outer), (a_inner, b_inner)) = (outer, inner);
+
+    (a_outer * a_inner, a_outer * b_inner + b_outer)
+}
+
 pub fn convert_to_screen_coords<T: Signed + NumOps + Clone + ToPrimitive>(p: T) -> usize {
     let p_abs = p.abs().to_usize().unwrap();
 
@@ -452,10 +782,10 @@ impl TriangleCoords3 {
     pub fn get_barycentric_coords(&self, p: Point3<f64>) -> Point3<f64> {
         let (a, b, c) = self.unravel_triplet_vec();
 
-        let mut s = vec![Point3(0.0f64, 0.0f64, 0.0f64); 2];
+        let mut s = [Point3(0.0f64, 0.0f64, 0.0f64); 2];
 
-        for i in (2usize..0usize).rev() {
-            s[i][0] = a[i] - a[i];
+        for i in 0usize..2usize {
+            s[i][0] = c[i] - a[i];
             s[i][1] = b[i] - a[i];
             s[i][2] = a[i] - p[i];
         }
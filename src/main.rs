@@ -1,19 +1,64 @@
 #![allow(unused)]
 
-use std::{path::PathBuf, sync::{Arc, Mutex}};
+use std::{path::{Path, PathBuf}, sync::{Arc, Mutex}};
 
 use linear_algebra::{TriangleCoords, Point2, Point3};
+use animation::{AnimationError, KeyframeAnimation};
 use wavefront_parser::WavefronObject;
 
+mod animation;
+mod camera;
+mod cli;
 mod context;
+mod depth_buffer;
 mod image_canvas;
 mod linear_algebra;
+mod presenter;
 mod utils;
 mod wavefront_parser;
 mod read_tga;
+mod scene;
+mod write_image;
 mod draw_modes;
+mod gltf_loader;
+mod model_loader;
+mod ply_loader;
+mod stl_loader;
+mod free_form;
+mod wavefront_mtl;
+mod wavefront_writer;
+mod texture_loader;
+mod texture_sampler;
+mod texture_cache;
+mod a_buffer;
+mod color;
+mod hdr_target;
+mod cube_lut;
+mod post_process;
+mod environment;
 
 
+#[test]
+fn test_cross_product() {
+    assert_eq!(Point3(1.0, 0.0, 0.0) ^ Point3(0.0, 1.0, 0.0), Point3(0.0, 0.0, 1.0));
+    assert_eq!(Point3(0.0, 1.0, 0.0) ^ Point3(0.0, 0.0, 1.0), Point3(1.0, 0.0, 0.0));
+    assert_eq!(Point3(1.0, 2.0, 3.0) ^ Point3(4.0, 5.0, 6.0), Point3(-3.0, 6.0, -3.0));
+}
+
+#[test]
+fn test_barycentric_coords() {
+    let triangle = linear_algebra::TriangleCoords3::from_vec(vec![
+        Point3(0.0, 0.0, 0.0),
+        Point3(4.0, 0.0, 0.0),
+        Point3(0.0, 4.0, 0.0),
+    ]);
+    let inside = triangle.get_barycentric_coords(Point3(1.0, 1.0, 0.0));
+    let outside = triangle.get_barycentric_coords(Point3(3.0, 3.0, 0.0));
+
+    assert_eq!(inside, Point3(0.5, 0.25, 0.25));
+    assert_eq!(outside, Point3(-0.5, 0.75, 0.75));
+}
+
 #[test]
 fn test_wave_front() {
     let model = WavefronObject::new(PathBuf::from("resources/african_head.obj"));
@@ -27,21 +72,1136 @@ fn test_wave_front() {
     
 }
 
+#[test]
+fn test_wave_front_round_trip() {
+    let model = WavefronObject::new(PathBuf::from("resources/african_head.obj"));
+    let out_path = std::env::temp_dir().join("naqshsmith_round_trip.obj");
+
+    wavefront_writer::write_obj(&model, &out_path).unwrap();
+
+    let reparsed = WavefronObject::new(out_path.clone());
+
+    std::fs::remove_file(out_path).unwrap();
+
+    assert_eq!(reparsed.get_n_faces(), 2492);
+    assert_eq!(reparsed.get_groups(), ["head"]);
+    assert!(model == reparsed);
+}
+
+#[test]
+fn test_wave_front_relative_indices() {
+    let path = std::env::temp_dir().join("naqshsmith_relative.obj");
+
+    std::fs::write(
+        &path,
+        "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvt 1 0\nvt 0 1\nvn 0 0 1\n\
+         f -3/-3 -2/-2 -1/-1\nv 1 1 0\nf -4//1 -1 -2\nf 1 2 -9\nf 1 x 2\n\
+         f 1 2 99\nf 1/9 2 3\nf 1//4 2 3\nl 1 500\np 4 5\nl 1 -1\n",
+    )
+    .unwrap();
+
+    let model = WavefronObject::new(path.clone());
+
+    std::fs::remove_file(path).unwrap();
+
+    // Counting back past the first vertex, past the last element read, or not a number
+    // at all skips the element.
+    let faces = model.get_faces();
+
+    assert_eq!(faces.len(), 2);
+    assert_eq!(
+        faces[0].get_triplets(),
+        ((Some(1), Some(2), Some(3)), (Some(1), Some(2), Some(3)), (None, None, None))
+    );
+    assert_eq!(
+        faces[1].get_triplets(),
+        ((Some(1), Some(4), Some(3)), (None, None, None), (Some(1), None, None))
+    );
+    assert!(model.get_points().is_empty());
+    assert_eq!(model.get_lines().len(), 1);
+    assert_eq!(model.get_lines()[0].vertices, [1, 4]);
+}
+
+#[test]
+fn test_wave_front_materials() {
+    let dir = std::env::temp_dir().join("naqshsmith_materials");
+    let (source, copy) = (dir.join("source"), dir.join("copy"));
+
+    std::fs::create_dir_all(&source).unwrap();
+    std::fs::create_dir_all(&copy).unwrap();
+
+    // `usemtl` comes before the library that defines it.
+    std::fs::write(
+        source.join("model.obj"),
+        "v 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl skin\nmtllib model.mtl\nf 1 2 3\n\
+         usemtl missing\nf 1 2 3\n",
+    )
+    .unwrap();
+    std::fs::write(
+        source.join("model.mtl"),
+        "newmtl skin\nKd 1 0 0\nmap_Kd -s 2 2 1 textures/skin.tga\n",
+    )
+    .unwrap();
+
+    let model = WavefronObject::new(source.join("model.obj"));
+
+    wavefront_writer::write_obj(&model, &copy.join("model.obj")).unwrap();
+
+    let mtl = std::fs::read_to_string(copy.join("model.mtl")).unwrap();
+
+    std::fs::remove_dir_all(dir).unwrap();
+
+    let materials = model.get_materials();
+
+    assert_eq!(materials.len(), 2);
+    assert_eq!(materials[0].diffuse, Some([1.0, 0.0, 0.0]));
+    assert_eq!(
+        materials[0].get_map_file("map_Kd").map(PathBuf::from),
+        Some(source.join("textures/skin.tga"))
+    );
+    assert_eq!(materials[1].diffuse, None);
+    assert!(mtl.contains("map_Kd -s 2 2 1 ../source/textures/skin.tga\n"));
+}
+
+#[test]
+fn test_gltf_nodes_and_indices() {
+    let dir = std::env::temp_dir();
+    let (gltf_path, bin_path) = (dir.join("naqshsmith_nodes.gltf"), dir.join("naqshsmith_nodes.bin"));
+
+    // A unit quad as a strip, and a triangle whose last index is out of range.
+    let mut bin = vec![];
+    for c in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0] {
+        bin.extend(c.to_le_bytes());
+    }
+    for i in [0u16, 1, 2, 3, 0, 1, 9, 0] {
+        bin.extend(i.to_le_bytes());
+    }
+
+    std::fs::write(&bin_path, &bin).unwrap();
+    std::fs::write(
+        &gltf_path,
+        r#"{
+            "asset": {"version": "2.0"},
+            "buffers": [{"uri": "naqshsmith_nodes.bin", "byteLength": 64}],
+            "bufferViews": [
+                {"buffer": 0, "byteOffset": 0, "byteLength": 48},
+                {"buffer": 0, "byteOffset": 48, "byteLength": 8},
+                {"buffer": 0, "byteOffset": 56, "byteLength": 6}
+            ],
+            "accessors": [
+                {"bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3",
+                 "min": [0, 0, 0], "max": [1, 1, 0]},
+                {"bufferView": 1, "componentType": 5123, "count": 4, "type": "SCALAR"},
+                {"bufferView": 2, "componentType": 5123, "count": 3, "type": "SCALAR"}
+            ],
+            "materials": [{"pbrMetallicRoughness": {"baseColorFactor": [1, 0, 0, 0.5]}}],
+            "meshes": [
+                {"primitives": [{"attributes": {"POSITION": 0}, "indices": 1, "mode": 5,
+                                 "material": 0}]},
+                {"primitives": [{"attributes": {"POSITION": 0}, "indices": 2}]}
+            ],
+            "nodes": [
+                {"name": "parent", "translation": [1, 0, 0], "scale": [2, 2, 2], "children": [1]},
+                {"name": "child", "translation": [0, 1, 0], "mesh": 0},
+                {"name": "broken", "mesh": 1}
+            ],
+            "scenes": [{"nodes": [0, 2]}],
+            "scene": 0
+        }"#,
+    )
+    .unwrap();
+
+    let gltf = gltf_loader::GltfModel::load(gltf_path.clone()).unwrap();
+    let model = model_loader::load_model(gltf_path.clone(), &Default::default()).unwrap();
+
+    std::fs::remove_file(gltf_path).unwrap();
+    std::fs::remove_file(bin_path).unwrap();
+
+    assert_eq!(gltf.primitives.len(), 1);
+    assert_eq!(gltf.primitives[0].node, "child");
+
+    // The child's offset is scaled by the parent before the parent's own offset.
+    let corner = model.get_vertex_at_index(&4).unwrap().xyz;
+
+    assert_eq!((corner.0, corner.1, corner.2), (3.0, 4.0, 0.0));
+
+    // The second strip triangle is turned back to the first one's winding.
+    assert_eq!(model.get_n_faces(), 2);
+    assert_eq!(model.get_faces()[1].get_triplets().0, (Some(3), Some(2), Some(4)));
+    assert_eq!(model.get_faces()[1].get_tags().material, Some(0));
+    assert_eq!(model.get_materials()[0].name, "material0");
+    assert_eq!(model.get_materials()[0].diffuse, Some([1.0, 0.0, 0.0]));
+    assert_eq!(model.get_materials()[0].dissolve, Some(0.5));
+}
+
+#[test]
+fn test_ply_models() {
+    let header = "ply\nformat ascii 1.0\nelement vertex 4\nproperty float x\nproperty float y\n\
+                  property float z\nproperty uchar red\nproperty uchar green\nproperty uchar blue\n";
+    let vertices = "0 0 0 255 0 0\n1 0 0 0 255 0\n0 1 0 0 0 255\n1 1 0 9 9 9\n";
+    let mesh_path = std::env::temp_dir().join("naqshsmith_mesh.ply");
+    let cloud_path = std::env::temp_dir().join("naqshsmith_cloud.ply");
+
+    std::fs::write(
+        &mesh_path,
+        format!(
+            "{}element face 1\nproperty list uchar int vertex_indices\nend_header\n{}4 0 1 3 2\n",
+            header, vertices
+        ),
+    )
+    .unwrap();
+    std::fs::write(&cloud_path, format!("{}end_header\n{}", header, vertices)).unwrap();
+
+    let mesh = ply_loader::load_ply(mesh_path.clone()).unwrap();
+    let cloud = ply_loader::load_ply(cloud_path.clone()).unwrap();
+
+    std::fs::remove_file(mesh_path).unwrap();
+    std::fs::remove_file(cloud_path).unwrap();
+
+    // The quad is fanned into two triangles.
+    assert_eq!(mesh.get_n_faces(), 2);
+    assert_eq!(mesh.get_colors()[1], [0, 255, 0]);
+    assert!(mesh.get_points().is_empty());
+
+    assert_eq!(cloud.get_n_faces(), 0);
+    assert!(cloud.has_vertex_colors());
+    assert_eq!(cloud.get_points().len(), 1);
+    assert_eq!(cloud.get_points()[0].vertices, [1, 2, 3, 4]);
+}
+
+#[test]
+fn test_stl_welding() {
+    let path = std::env::temp_dir().join("naqshsmith_weld.stl");
+    let facet = |corners: [[f64; 3]; 3]| {
+        let corners = corners
+            .iter()
+            .map(|[x, y, z]| format!("vertex {} {} {}\n", x, y, z))
+            .collect::<String>();
+
+        format!("facet normal 0 0 1\nouter loop\n{}endloop\nendfacet\n", corners)
+    };
+
+    // The second facet's shared corners are 1e-4 off, the third's 0.1 off.
+    std::fs::write(
+        &path,
+        format!(
+            "solid weld\n{}{}{}endsolid weld\n",
+            facet([[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]),
+            facet([[1.0001, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0001, 0.0]]),
+            facet([[0.1, 0.0, 0.0], [-1.0, 0.0, 0.0], [0.0, 0.1, 0.0]]),
+        ),
+    )
+    .unwrap();
+
+    let welded = stl_loader::load_stl(path.clone(), 1e-3).unwrap();
+    let exact = stl_loader::load_stl(path.clone(), 0.0).unwrap();
+
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(welded.get_n_faces(), 3);
+    assert_eq!(welded.get_vertices().len(), 7);
+    assert_eq!(exact.get_vertices().len(), 9);
+}
+
+#[test]
+fn test_read_tga() {
+    let texture = read_tga::read_tga(PathBuf::from("resources/african_head_diffuse.tga")).unwrap();
+
+    assert_eq!(texture.get_size(), (1024, 1024));
+    assert_eq!(texture.format, read_tga::TgaFormat::Rgb);
+    assert_eq!(texture.data.len(), 1024 * 1024 * 3);
+
+    let truncated = read_tga::decode_tga(&[0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4, 0, 4, 0, 24, 0]);
+
+    assert!(truncated.is_err());
+}
+
+#[test]
+fn test_canvas_clips_writes() {
+    let mut canvas = image_canvas::Canvas::new(4, 3, 0);
+
+    for (x, y) in [(-1, 0), (0, -1), (4, 0), (0, 3), (i32::MIN, i32::MAX)] {
+        assert!(canvas.set_pixel_rgb(x, y, [255, 0, 0]).is_ok());
+    }
+
+    assert!(canvas.as_bytes().iter().all(|b| *b == 0));
+
+    canvas.set_pixel_rgb(3, 2, [1, 2, 3]).unwrap();
+
+    assert_eq!(canvas.get_pixel_rgb(3, 2), [1, 2, 3]);
+}
+
+#[test]
+fn test_texture_cache() {
+    let path = PathBuf::from("resources/african_head_diffuse.tga");
+    let copy_path = std::env::temp_dir().join("naqshsmith_cache_copy.tga");
+
+    std::fs::copy(&path, &copy_path).unwrap();
+
+    let mut cache = texture_cache::TextureCache::new(4 * 1024 * 1024);
+    let first = cache.load(&path).unwrap();
+    let again = cache.load(&path).unwrap();
+    let copy = cache.load(&copy_path).unwrap();
+
+    std::fs::remove_file(copy_path).unwrap();
+
+    assert!(Arc::ptr_eq(&first, &again));
+    assert!(Arc::ptr_eq(&first, &copy));
+    assert_eq!(cache.len(), 1);
+    assert_eq!(cache.get_stats().misses, 1);
+    assert_eq!(cache.get_stats().bytes, first.get_byte_count());
+
+    // Over budget, the older texture goes but its handle stays usable.
+    let small = cache.insert(image_canvas::Canvas::new(4, 4, 7));
+
+    assert_eq!(cache.len(), 1);
+    assert_eq!(cache.get_stats().evictions, 1);
+    assert_eq!(first.get_size(), (1024, 1024));
+    assert!(Arc::ptr_eq(&small, &cache.insert(image_canvas::Canvas::new(4, 4, 7))));
+
+    // A match is checked against the cached file on disk, so once that file changes the
+    // same contents under another path are loaded again.
+    let changed_path = std::env::temp_dir().join("naqshsmith_cache_changed.tga");
+    let mut cache = texture_cache::TextureCache::default();
+
+    std::fs::copy(&path, &changed_path).unwrap();
+
+    let before = cache.load(&changed_path).unwrap();
+
+    std::fs::write(&changed_path, b"changed").unwrap();
+
+    let original = cache.load(&path).unwrap();
+
+    std::fs::remove_file(changed_path).unwrap();
+
+    assert!(!Arc::ptr_eq(&before, &original));
+    assert_eq!(cache.get_stats().misses, 2);
+}
+
+#[test]
+fn test_srgb_round_trip() {
+    for value in 0..=255u8 {
+        assert_eq!(color::encode_srgb(color::decode_srgb([value; 3])), [value; 3]);
+    }
+
+    assert!((color::srgb_to_linear(128) - 0.2158605).abs() < 1e-6);
+}
+
+#[test]
+fn test_threaded_textured_triangle_stays_inside() {
+    let canvas = Mutex::new(image_canvas::Canvas::new(8, 8, 0));
+    let zbuffer = Mutex::new(vec![-f64::MAX; 64]);
+    let texture = texture_sampler::MipChain::new(image_canvas::Canvas::new(1, 1, 255));
+    let coords = linear_algebra::TriangleCoords3::from_vec(vec![
+        Point3(1.0, 1.0, 0.0),
+        Point3(6.0, 1.0, 0.0),
+        Point3(1.0, 6.0, 0.0),
+    ]);
+
+    draw_modes::draw_threaded_with_zbuffer_textured::draw_triangle_threaded_with_zbuffer_texture::draw_triangle_threaded_with_zbuffer_with_texture(
+        coords,
+        &zbuffer,
+        &canvas,
+        &[Point2(0.0, 0.0), Point2(1.0, 0.0), Point2(0.0, 1.0)],
+        &texture,
+        &Default::default(),
+    );
+
+    let canvas = canvas.lock().unwrap();
+
+    assert_eq!(canvas.get_pixel_rgb(2, 2), [255; 3]);
+    assert_eq!(canvas.get_pixel_rgb(6, 6), [0; 3]);
+    assert_eq!(canvas.get_pixel_rgb(5, 5), [0; 3]);
+}
+
+#[test]
+fn test_sampler_flags() {
+    let args = |line: &str| line.split(' ').map(String::from).collect::<Vec<String>>();
+
+    let display = match cli::parse_args(&args("--filter nearest --lod-bias 1.5")).unwrap() {
+        cli::Command::Demo { display } => display,
+        other => panic!("expected the demo, got {:?}", other),
+    };
+    let sampler = display.get_sampler_settings(Default::default());
+
+    assert_eq!(sampler.filter, texture_sampler::TextureFilter::Nearest);
+    assert_eq!(sampler.lod_bias, 1.5);
+    assert_eq!(sampler.max_anisotropy, texture_sampler::DEFAULT_MAX_ANISOTROPY);
+
+    for bad in ["--lod-bias nan", "--max-anisotropy 0", "--filter cubic"] {
+        assert!(cli::parse_args(&args(bad)).is_err());
+    }
+
+    // A 2x2 checker keeps its texels with nearest filtering and averages out to one flat
+    // color when biased down to the last mip level.
+    let mut checker = image_canvas::Canvas::new(2, 2, 0);
+
+    checker.set_pixel(1, 0, 255).unwrap();
+    checker.set_pixel(0, 1, 255).unwrap();
+
+    let texture = texture_sampler::MipChain::new(checker);
+    let coords = linear_algebra::TriangleCoords3::from_vec(vec![
+        Point3(0.0, 0.0, 0.0),
+        Point3(16.0, 0.0, 0.0),
+        Point3(0.0, 16.0, 0.0),
+    ]);
+    let draw = |sampler: texture_sampler::SamplerSettings| {
+        let canvas = Mutex::new(image_canvas::Canvas::new(16, 16, 0));
+        let zbuffer = Mutex::new(vec![-f64::MAX; 256]);
+
+        draw_modes::draw_threaded_with_zbuffer_textured::draw_triangle_threaded_with_zbuffer_texture::draw_triangle_threaded_with_zbuffer_with_texture(
+            coords,
+            &zbuffer,
+            &canvas,
+            &[Point2(0.0, 0.0), Point2(1.0, 0.0), Point2(0.0, 1.0)],
+            &texture,
+            &sampler,
+        );
+
+        let canvas = canvas.into_inner().unwrap();
+
+        (canvas.get_pixel_rgb(2, 2)[0], canvas.get_pixel_rgb(10, 2)[0])
+    };
+
+    assert_eq!(draw(sampler), (0, 255));
+
+    let (a, b) = draw(texture_sampler::SamplerSettings {
+        filter: texture_sampler::TextureFilter::Trilinear,
+        lod_bias: 8.0,
+        ..sampler
+    });
+
+    assert_eq!(a, b);
+    assert!(a > 0 && a < 255);
+}
+
+#[test]
+fn test_turntable_flags() {
+    let parse = |flags: &str| {
+        let args = format!("turntable model.obj {}", flags)
+            .split_whitespace()
+            .map(String::from)
+            .collect::<Vec<String>>();
+
+        cli::parse_args(&args)
+    };
+
+    match parse("--fps 24 --degrees -90 --pitch 15 --zoom 0.5").unwrap() {
+        cli::Command::Turntable { turntable, .. } => {
+            assert_eq!(turntable.fps, 24.0);
+            assert_eq!(turntable.degrees, -90.0);
+            assert_eq!(turntable.pitch, 15.0);
+            assert_eq!(turntable.zoom, 0.5);
+        }
+        other => panic!("expected a turntable, got {:?}", other),
+    }
+
+    for bad in [
+        "--fps NaN",
+        "--fps 0",
+        "--fps inf",
+        "--degrees NaN",
+        "--degrees -inf",
+        "--pitch nan",
+        "--zoom 0",
+        "--zoom -2",
+        "--zoom inf",
+    ] {
+        assert!(parse(bad).is_err(), "{} was accepted", bad);
+    }
+}
+
+#[test]
+fn test_hdr_texture_sampling() {
+    let image = texture_loader::HdrImage {
+        width: 2,
+        height: 2,
+        texels: vec![[4.0, 2.0, 0.5], [0.0; 3], [0.0; 3], [0.0; 3]],
+    };
+    let texture = texture_loader::Texture::Hdr(image).into_mip_chain();
+    let footprint = |d: f64| texture_sampler::UvDerivatives {
+        ddx: Point2(d, 0.0),
+        ddy: Point2(0.0, d),
+    };
+    let nearest = texture_sampler::SamplerSettings {
+        filter: texture_sampler::TextureFilter::Nearest,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        texture.sample_linear(Point2(0.25, 0.25), footprint(0.5), &nearest),
+        [4.0, 2.0, 0.5]
+    );
+
+    // The 1x1 level averages the four texels without clipping the bright one first.
+    let coarse = texture.sample_linear(Point2(0.5, 0.5), footprint(1.0), &Default::default());
+
+    assert_eq!(coarse, [1.0, 0.5, 0.125]);
+}
+
+#[test]
+fn test_environment_lighting() {
+    let uniform = environment::Environment::from_texels(32, 16, vec![[0.5, 0.25, 1.0]; 512], 4);
+
+    for normal in [Point3(0.0, 1.0, 0.0), Point3(0.3, -0.2, 0.9)] {
+        let irradiance = uniform.irradiance(normal);
+
+        for (c, expected) in irradiance.iter().zip([0.5, 0.25, 1.0]) {
+            assert!((c - expected).abs() < 0.01, "{:?}", irradiance);
+        }
+    }
+
+    // Bright sky above, dark ground below.
+    let texels = (0..512).map(|i| [(i / 32 >= 8) as u8 as f64; 3]).collect();
+    let sky = environment::Environment::from_texels(32, 16, texels, 4);
+
+    assert!(sky.irradiance(Point3(0.0, 1.0, 0.0))[0] > 0.9);
+    assert!(sky.irradiance(Point3(0.0, -1.0, 0.0))[0] < 0.1);
+    assert!(sky.specular(Point3(0.0, 1.0, 0.0), 0.0)[0] > 0.99);
+    assert!(sky.specular(Point3(1.0, 0.0, 0.0), 1.0)[0] > 0.3);
+}
+
+#[test]
+fn test_cube_lut_and_post_spec() {
+    let identity = "LUT_3D_SIZE 2\n0 0 0\n1 0 0\n0 1 0\n1 1 0\n0 0 1\n1 0 1\n0 1 1\n1 1 1\n";
+    let lut = cube_lut::CubeLut::parse(identity).unwrap();
+
+    assert_eq!(lut.sample([0.25, 0.5, 0.75]), [0.25, 0.5, 0.75]);
+    assert!(cube_lut::CubeLut::parse("LUT_3D_SIZE 2\n0 0 0\n").is_err());
+
+    let spec = post_process::PostDescription::parse_spec("dof:focus=0.2,radius=4").unwrap();
+
+    assert_eq!(
+        spec.build(Path::new("")),
+        Ok(post_process::PostEffect::DepthOfField {
+            focus: Some(0.2),
+            range: post_process::DEFAULT_DOF_RANGE,
+            radius: 4.0,
+        })
+    );
+    assert!(post_process::PostDescription::parse_spec("fxaa:amount=1")
+        .unwrap()
+        .build(Path::new(""))
+        .is_err());
+}
+
+#[test]
+fn test_blend_presets() {
+    use draw_modes::blend::blend::{BlendFactor, BlendState};
+
+    let src = [0.8, 0.4, 2.0];
+    let dst = [0.5, 1.0, 0.25];
+    let blend = |name: &str, alpha: f64| BlendState::from_name(name).unwrap().apply(src, alpha, dst);
+
+    assert_eq!(blend("opaque", 0.25), src);
+    assert_eq!(blend("over", 0.25), [0.575, 0.85, 0.6875]);
+    assert_eq!(blend("additive", 0.5), [0.9, 1.2, 1.25]);
+    // Only the weights are clamped; the source itself keeps its range.
+    assert_eq!(blend("multiply", 0.5), [0.4, 0.4, 0.5]);
+    // Alpha outside [0, 1] is clamped before it weighs anything.
+    assert_eq!(blend("over", 3.0), src);
+    assert_eq!(blend("over", -1.0), dst);
+
+    assert!(BlendState::OVER.is_replace_when_opaque());
+    assert!(!BlendState::ADDITIVE.is_replace_when_opaque());
+    assert_eq!(BlendState::default(), BlendState::OVER);
+    assert_eq!(BlendState::from_name("screen"), None);
+    assert_eq!(
+        BlendFactor::from_name("one_minus_dst_color"),
+        Some(BlendFactor::OneMinusDstColor)
+    );
+}
+
+#[test]
+fn test_a_buffer_overflow_and_resolve() {
+    use draw_modes::blend::blend::BlendState;
+
+    let fragment = |depth: f64, color: color::LinearRgb| a_buffer::LayerFragment {
+        depth,
+        color,
+        alpha: 0.5,
+        blend: BlendState::OVER,
+    };
+
+    let mut layers = a_buffer::ABuffer::new(2, 1, 2);
+
+    layers.push(0, 0, fragment(0.1, [1.0, 0.0, 0.0]));
+    layers.push(0, 0, fragment(0.5, [0.0, 1.0, 0.0]));
+    // Full: the nearer fragment takes the farthest one's place, the farther one is dropped.
+    layers.push(0, 0, fragment(0.3, [0.0, 0.0, 1.0]));
+    layers.push(0, 0, fragment(0.0, [1.0, 1.0, 1.0]));
+    layers.push(1, 0, fragment(0.2, [1.0, 1.0, 1.0]));
+    layers.push(2, 0, fragment(0.9, [1.0, 1.0, 1.0]));
+
+    let mut depths = layers
+        .get_fragments(0, 0)
+        .unwrap()
+        .iter()
+        .map(|f| f.depth)
+        .collect::<Vec<f64>>();
+
+    depths.sort_by(f64::total_cmp);
+
+    assert_eq!(depths, [0.3, 0.5]);
+    assert_eq!(
+        layers.get_stats(),
+        a_buffer::ABufferStats {
+            fragments: 5,
+            overflowed_pixels: 1,
+            dropped_fragments: 2,
+            max_layers_used: 2,
+        }
+    );
+
+    // Far to near: the blue layer goes down first and the green one over it. The second
+    // pixel's fragment is behind the opaque surface there.
+    let mut depth_buffer = depth_buffer::DepthBuffer::new(2, 1);
+    let mut target = hdr_target::HdrTarget::new(2, 1);
+
+    depth_buffer.test_and_set(1, 0, 0.6);
+    layers.resolve(&depth_buffer, &mut target);
+
+    assert_eq!(target.get_pixel(0, 0), Some([0.0, 0.5, 0.25]));
+    assert_eq!(target.get_pixel(1, 0), Some([0.0, 0.0, 0.0]));
+}
+
+#[test]
+fn test_tone_mapping_and_exposure() {
+    use hdr_target::{HdrTarget, ToneMapping, ToneSettings};
+
+    assert_eq!(ToneMapping::Clamp.apply(0.25), 0.25);
+    assert_eq!(ToneMapping::Clamp.apply(4.0), 1.0);
+    assert_eq!(ToneMapping::Reinhard.apply(1.0), 0.5);
+    assert_eq!(ToneMapping::Reinhard.apply(3.0), 0.75);
+    assert_eq!(ToneMapping::Aces.apply(0.0), 0.0);
+    assert_eq!(ToneMapping::Aces.apply(100.0), 1.0);
+
+    // Negative light is black, and every curve keeps brighter inputs brighter.
+    for operator in ToneMapping::ALL {
+        assert_eq!(operator.apply(-2.0), 0.0);
+
+        let curve = [0.1, 0.5, 1.0, 2.0].map(|c| operator.apply(c));
+
+        assert!(curve.windows(2).all(|w| w[0] < w[1] || w[1] == 1.0));
+    }
+
+    // Each stop of exposure doubles the light before the curve.
+    let resolve = |light: f64, exposure: f64, operator: ToneMapping| {
+        let mut target = HdrTarget::new(1, 1);
+        let mut canvas = image_canvas::Canvas::new(1, 1, 0);
+        let settings = ToneSettings {
+            operator,
+            exposure,
+            bloom: None,
+        };
+
+        target.clear([light; 3]);
+        target.resolve(&settings, false, &mut canvas);
+
+        canvas.get_pixel_rgb(0, 0)
+    };
+
+    for operator in ToneMapping::ALL {
+        assert_eq!(resolve(0.25, 1.0, operator), resolve(0.5, 0.0, operator));
+        assert_eq!(resolve(2.0, -2.0, operator), resolve(0.5, 0.0, operator));
+    }
+
+    assert_eq!(resolve(0.3, 2.0, ToneMapping::Clamp), [255; 3]);
+    assert_ne!(resolve(0.3, 2.0, ToneMapping::Reinhard), [255; 3]);
+}
+
+#[test]
+fn test_slerp_and_easing() {
+    use animation::Interpolation;
+    use linear_algebra::Quaternion;
+
+    let close = |a: Point3<f64>, b: Point3<f64>| {
+        (a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9 && (a.2 - b.2).abs() < 1e-9
+    };
+    let turn = |q: Quaternion| q.get_rotation_matrix() * Point3(1.0, 0.0, 0.0);
+
+    let z = Point3(0.0, 0.0, 1.0);
+    let start = Quaternion::identity();
+    let end = Quaternion::from_axis_angle(z, std::f64::consts::FRAC_PI_2);
+
+    assert!(close(turn(start.slerp(&end, 0.0)), Point3(1.0, 0.0, 0.0)));
+    assert!(close(turn(start.slerp(&end, 1.0)), Point3(0.0, 1.0, 0.0)));
+    assert!(close(
+        turn(start.slerp(&end, 0.5)),
+        turn(Quaternion::from_axis_angle(z, std::f64::consts::FRAC_PI_4))
+    ));
+
+    // The negated quaternion is the same rotation, and slerp still takes the short way.
+    let flipped = Quaternion {
+        w: -end.w,
+        x: -end.x,
+        y: -end.y,
+        z: -end.z,
+    };
+
+    assert!(close(turn(start.slerp(&flipped, 0.5)), turn(start.slerp(&end, 0.5))));
+
+    // Nearly equal rotations fall back to a normalized lerp instead of dividing by ~0.
+    let nudged = Quaternion::from_axis_angle(z, 1e-6);
+    let between = start.slerp(&nudged, 0.5);
+
+    assert!((between.dot(&between) - 1.0).abs() < 1e-12);
+    assert!(close(turn(between), turn(Quaternion::from_axis_angle(z, 5e-7))));
+
+    assert_eq!(Interpolation::Step.ease(0.7), 0.0);
+    assert_eq!(Interpolation::Linear.ease(0.3), 0.3);
+
+    // The default handles are a symmetric ease-in-out.
+    let ease = Interpolation::Bezier(animation::DEFAULT_BEZIER_HANDLES);
+
+    assert!(ease.ease(0.0).abs() < 1e-9);
+    assert!((ease.ease(1.0) - 1.0).abs() < 1e-9);
+    assert!((ease.ease(0.5) - 0.5).abs() < 1e-9);
+    assert!(ease.ease(0.2) < 0.2);
+    assert!(ease.ease(0.8) > 0.8);
+
+    // Handles on the diagonal make the curve the identity.
+    let straight = Interpolation::Bezier([0.25, 0.25, 0.75, 0.75]);
+
+    for t in [0.1, 0.35, 0.6, 0.9] {
+        assert!((straight.ease(t) - t).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn test_de_boor_and_bezier() {
+    use free_form::{de_boor, from_homogeneous, knot_vector, tessellate_curve, Basis};
+
+    // Each Bezier breakpoint is repeated `degree` times, the ends once more.
+    assert_eq!(
+        knot_vector(Basis::Bezier, 3, &[0.0, 1.0, 2.0], 7),
+        Some(vec![0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 2.0, 2.0, 2.0, 2.0])
+    );
+    assert_eq!(knot_vector(Basis::Bezier, 3, &[0.0, 1.0], 5), None);
+    assert_eq!(knot_vector(Basis::BSpline, 1, &[0.0, 2.0, 1.0], 1), None);
+    assert_eq!(knot_vector(Basis::BSpline, 2, &[0.0, 1.0, 2.0], 3), None);
+
+    let point = |x: f64, y: f64| [x, y, 0.0, 1.0];
+    let at = |knots: &[f64], degree: usize, control: &[[f64; 4]], t: f64| {
+        from_homogeneous(de_boor(knots, degree, control, t))
+    };
+
+    // A cubic Bezier hits its end points and weighs the controls 1:3:3:1 halfway.
+    let cubic = [point(0.0, 0.0), point(1.0, 2.0), point(3.0, 2.0), point(4.0, 0.0)];
+    let knots = knot_vector(Basis::Bezier, 3, &[0.0, 1.0], 4).unwrap();
+
+    assert_eq!(at(&knots, 3, &cubic, 0.0), Point3(0.0, 0.0, 0.0));
+    assert_eq!(at(&knots, 3, &cubic, 1.0), Point3(4.0, 0.0, 0.0));
+    assert_eq!(at(&knots, 3, &cubic, 0.5), Point3(2.0, 1.5, 0.0));
+
+    // A uniform quadratic B-spline weighs its controls 1:6:1 in the middle of its span.
+    let quadratic = [point(0.0, 0.0), point(4.0, 4.0), point(8.0, 0.0)];
+    let knots = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0];
+
+    assert_eq!(at(&knots, 2, &quadratic, 2.5), Point3(4.0, 3.0, 0.0));
+
+    // A rational quadratic with a middle weight of cos 45° is an exact quarter circle.
+    let w = std::f64::consts::FRAC_1_SQRT_2;
+    let curve = free_form::FreeFormCurve {
+        kind: free_form::FreeFormType {
+            basis: Basis::Bezier,
+            rational: true,
+        },
+        degree: 2,
+        range: (0.0, 1.0),
+        control: vec![1, 2, 3],
+        parameters: vec![0.0, 1.0],
+        tags: wavefront_parser::ElementTags::default(),
+    };
+    let samples =
+        tessellate_curve(&curve, &[point(1.0, 0.0), [w, w, 0.0, w], point(0.0, 1.0)], 8).unwrap();
+
+    assert_eq!(samples.len(), 9);
+
+    for Point3(x, y, _) in samples {
+        assert!((x.hypot(y) - 1.0).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn test_wave_front_trimmed_surface() {
+    let path = std::env::temp_dir().join("naqshsmith_trimmed.obj");
+
+    // The trimming loop is skipped, so the patch is kept whole.
+    std::fs::write(
+        &path,
+        "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nvp 0.2 0.2\nvp 0.8 0.8\n\
+         cstype bezier\ndeg 1\ncurv2 1 2\nend\ndeg 1 1\nsurf 0 1 0 1 1 2 3 4\n\
+         parm u 0 1\nparm v 0 1\ntrim 0 1 1\nend\n",
+    )
+    .unwrap();
+
+    let model = WavefronObject::new(path.clone());
+
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(model.get_space_vertices().len(), 2);
+    assert_eq!(model.get_curves().len(), 0);
+    assert_eq!(model.get_surfaces().len(), 1);
+    assert_eq!(model.tessellated(2).get_n_faces(), 8);
+}
+
+#[test]
+fn test_hidden_line_bias() {
+    use draw_modes::draw_hidden_line::draw_hidden_line::{
+        draw_hidden_line_view, WireframeStyle, DEFAULT_DEPTH_BIAS,
+    };
+    use draw_modes::rasterize::rasterize::{project_vertex, rasterize_line};
+
+    let path = std::env::temp_dir().join("naqshsmith_hidden_line.obj");
+
+    // A steep face, and a small one just behind a flat face.
+    std::fs::write(
+        &path,
+        "v -0.9 -0.9 -0.9\nv 0.9 -0.9 -0.9\nv 0 0.9 0.9\n\
+         v -0.5 -0.5 1\nv 0.5 -0.5 1\nv 0 0.5 1\n\
+         v -0.2 -0.4 0.995\nv 0.2 -0.4 0.995\nv 0 -0.1 0.995\n\
+         f 1 2 3\nf 4 5 6\nf 7 8 9\n",
+    )
+    .unwrap();
+
+    let model = WavefronObject::new(path.clone());
+
+    std::fs::remove_file(path).unwrap();
+
+    let camera = camera::OrbitCamera::default();
+    let mut canvas = image_canvas::Canvas::new(64, 64, 0);
+
+    draw_hidden_line_view(
+        &model,
+        &camera,
+        Point3(0.0, 0.0, -1.0),
+        WireframeStyle::HiddenLine,
+        DEFAULT_DEPTH_BIAS,
+        &mut canvas,
+        255,
+    );
+
+    let lit = |x: usize, y: usize| canvas.get_pixel_rgb(x, y) == [255; 3];
+
+    // Every pixel of the steep face's outline shows, apart from where the flat face covers it.
+    let mut outline = 0;
+    let mut shown = 0;
+
+    for (a, b) in [(1, 2), (2, 3), (1, 3)] {
+        let from = project_vertex(&model, &camera, a, 64, 64);
+        let to = project_vertex(&model, &camera, b, 64, 64);
+
+        rasterize_line(from, to, 64, 64, |x, y, _| {
+            if !(16..=48).contains(&x) || !(16..=48).contains(&y) {
+                outline += 1;
+                shown += lit(x, y) as usize;
+            }
+        });
+    }
+
+    assert!(outline > 0);
+    assert_eq!(shown, outline);
+
+    // The small face is 0.005 behind the flat one, which is seen head on.
+    assert!((20..=29).all(|y| (26..=39).all(|x| !lit(x, y))));
+}
+
+#[test]
+fn test_depth_pfm() {
+    let out_path = std::env::temp_dir().join("naqshsmith_depth.pfm");
+    let mut depth = depth_buffer::DepthBuffer::new(2, 2);
+
+    depth.test_and_set(0, 0, 0.25);
+    depth.test_and_set(1, 0, -0.5);
+    depth.test_and_set(1, 1, 1.0);
+    depth.write_pfm(out_path.clone()).unwrap();
+
+    let bytes = std::fs::read(&out_path).unwrap();
+
+    std::fs::remove_file(out_path).unwrap();
+
+    let header = b"Pf\n2 2\n-1.0\n";
+    let payload = bytes[header.len()..]
+        .chunks(4)
+        .map(|c| f32::from_le_bytes(c.try_into().unwrap()))
+        .collect::<Vec<f32>>();
+
+    // Bottom row first, the uncovered pixel as negative infinity.
+    assert_eq!(&bytes[..header.len()], header);
+    assert_eq!(payload, [0.25, -0.5, f32::NEG_INFINITY, 1.0]);
+}
+
+#[test]
+fn test_headless_view_stops() {
+    let model = WavefronObject::new(PathBuf::from("resources/african_head.obj"));
+    let mut out = Vec::new();
+    let mut presenter = presenter::RawFramePresenter::new(&mut out, None);
+
+    context::run_interactive_viewer(
+        &mut presenter,
+        &model,
+        16,
+        8,
+        None,
+        &Default::default(),
+        &Default::default(),
+    )
+    .unwrap();
+
+    assert_eq!(out.len(), 16 * 8 * 3);
+}
+
+#[test]
+fn test_debug_views() {
+    use draw_modes::draw_debug::draw_debug::{
+        draw_debug_view, normal_to_color, overdraw_to_color, uv_to_color, DebugView,
+    };
+
+    let path = std::env::temp_dir().join("naqshsmith_debug.obj");
+
+    // Only the second face has UVs and normals, and it is drawn twice.
+    std::fs::write(
+        &path,
+        "v -0.9 -0.9 0\nv -0.1 -0.9 0\nv -0.9 -0.1 0\nv 0.1 0.1 0\nv 0.9 0.1 0\nv 0.1 0.9 0\n\
+         vt 0.25 0.25\nvn 1 0 0\nf 1 2 3\nf 4/1/1 5/1/1 6/1/1\nf 4/1/1 5/1/1 6/1/1\n",
+    )
+    .unwrap();
+
+    let model = WavefronObject::new(path.clone());
+
+    std::fs::remove_file(path).unwrap();
+
+    let draw = |view| {
+        let mut canvas = image_canvas::Canvas::new(40, 40, 0);
+
+        draw_debug_view(&model, &camera::OrbitCamera::default(), view, &mut canvas);
+
+        (canvas.get_pixel_rgb(6, 6), canvas.get_pixel_rgb(26, 26))
+    };
+
+    let (bare, full) = draw(DebugView::Normals);
+
+    assert_eq!(full, normal_to_color(Point3(1.0, 0.0, 0.0)));
+    assert_ne!(bare, full);
+
+    assert_eq!(
+        draw(DebugView::Uvs),
+        ([255, 0, 255], uv_to_color(Point2(0.25, 0.25)))
+    );
+
+    let (bare, full) = draw(DebugView::Barycentric);
+
+    for color in [bare, full] {
+        let sum = color.iter().map(|c| *c as u32).sum::<u32>();
+
+        assert!((253..=257).contains(&sum));
+    }
+
+    assert_eq!(
+        draw(DebugView::Overdraw),
+        (overdraw_to_color(1, 2), overdraw_to_color(2, 2))
+    );
+}
+
 
 use draw_modes::draw_threaded_with_zbuffer_textured::draw_triangle_threaded_with_zbuffer_texture::shade_threaded_with_zbuffer_with_texture;
 
 
 
 fn main() {
-    let mut image_canvas = crate::image_canvas::Canvas::new(800, 800, 20);
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let command = match cli::parse_args(&args) {
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(err) = run(command) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
+
+fn run(command: cli::Command) -> Result<(), String> {
+    match command {
+        cli::Command::Demo { display } => run_demo(&display),
+        cli::Command::View {
+            model_path,
+            load_options,
+            display,
+        } => {
+            let model = model_loader::load_model(model_path, &load_options)?;
+            let (w, h) = display.size;
+            let mut presenter =
+                cli::make_presenter(&display, "naqshsmith viewer").map_err(|e| e.to_string())?;
+
+            crate::context::run_interactive_viewer(
+                presenter.as_mut(),
+                &model,
+                w,
+                h,
+                display.max_frames,
+                &display.get_primitive_style(),
+                &display.get_depth_settings(),
+            )
+            .map_err(|e| e.to_string())
+        }
+        cli::Command::Turntable {
+            model_path,
+            load_options,
+            display,
+            turntable,
+            mode,
+            light,
+        } => {
+            let model = model_loader::load_model(model_path, &load_options)?;
+            let mut presenter =
+                cli::make_presenter(&display, "naqshsmith turntable").map_err(|e| e.to_string())?;
+
+            animation::render_turntable(
+                presenter.as_mut(),
+                &model,
+                &turntable,
+                mode,
+                light,
+                &display.get_primitive_style(),
+                &display.get_depth_settings(),
+                display.size,
+            )
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+        }
+        cli::Command::Animate {
+            model_path,
+            load_options,
+            animation_path,
+            display,
+            mode,
+        } => {
+            let animation =
+                KeyframeAnimation::load(animation_path.clone()).map_err(|e| match e {
+                    AnimationError::Io(..) => e.to_string(),
+                    _ => format!("{}: {}", animation_path.display(), e),
+                })?;
+            let model = model_loader::load_model(model_path, &load_options)?;
+            let mut presenter =
+                cli::make_presenter(&display, "naqshsmith animation").map_err(|e| e.to_string())?;
+
+            animation::render_keyframes(
+                presenter.as_mut(),
+                &model,
+                &animation,
+                mode,
+                &display.get_primitive_style(),
+                &display.get_depth_settings(),
+                display.size,
+            )
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+        }
+        cli::Command::Scene {
+            scene_path,
+            mut display,
+            size_override,
+        } => {
+            let mut scene = scene::Scene::load(scene_path.clone()).map_err(|e| match e {
+                scene::SceneError::Io(..) => e.to_string(),
+                _ => format!("{}: {}", scene_path.display(), e),
+            })?;
+
+            match size_override {
+                true => scene.size = display.size,
+                false => display.size = scene.size,
+            }
+
+            if let Some(line_width) = display.line_width {
+                scene.primitives.line_width = line_width;
+            }
+
+            if let Some(point_size) = display.point_size {
+                scene.primitives.point_size = point_size;
+            }
+
+            if display.dither {
+                scene.dither = true;
+            }
+
+            if let Some(tone_mapping) = display.tone_mapping {
+                scene.tone.operator = tone_mapping;
+            }
+
+            if let Some(exposure) = display.exposure {
+                scene.tone.exposure = exposure;
+            }
+
+            if let Some(scale) = display.depth_scale {
+                scene.depth.scale = scale;
+            }
+
+            if let Some(range) = display.depth_range {
+                scene.depth.range = Some(range);
+            }
+
+            if let Some(out) = &display.depth_out {
+                scene.depth.out = Some(out.clone());
+            }
+
+            scene.sampler = display.get_sampler_settings(scene.sampler);
+
+            // Relative LUT paths on the command line are taken from the working directory.
+            if let Some(post) = &display.post {
+                scene.post = post
+                    .iter()
+                    .map(|p| p.build(Path::new("")))
+                    .collect::<Result<_, _>>()
+                    .map_err(|e| format!("--post: {}", e))?;
+            }
+
+            let mut presenter =
+                cli::make_presenter(&display, "naqshsmith scene").map_err(|e| e.to_string())?;
+
+            scene::render_scene(presenter.as_mut(), &scene)
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        }
+        cli::Command::Export {
+            model_path,
+            load_options,
+            out_path,
+        } => {
+            let model = model_loader::load_model(model_path, &load_options)?;
+
+            wavefront_writer::write_obj(&model, &out_path)
+                .map_err(|e| format!("could not write {}: {}", out_path.display(), e))
+        }
+    }
+}
+
+fn run_demo(display: &cli::DisplayOptions) -> Result<(), String> {
+    let (w, h) = display.size;
+    let mut image_canvas = crate::image_canvas::Canvas::new(w, h, 20);
 
     let obj_path = PathBuf::from("/media/chubak/MARK SOLID/naqshsmit2/resources/african_head.obj");
-    let texture_path = PathBuf::from("/media/chubak/MARK SOLID/naqshsmit2/resources/african_head_diffuse.tga");
+    let texture_path =
+        PathBuf::from("/media/chubak/MARK SOLID/naqshsmit2/resources/african_head_diffuse.tga");
+
+    let arc_mutex_canv = Arc::new(Mutex::new(image_canvas));
 
-   let arc_mutex_canv = Arc::new(Mutex::new(image_canvas));
+    shade_threaded_with_zbuffer_with_texture(
+        obj_path,
+        texture_path,
+        display.get_sampler_settings(Default::default()),
+        &arc_mutex_canv,
+    );
 
-   shade_threaded_with_zbuffer_with_texture(obj_path, texture_path, &arc_mutex_canv);
-  
+    let mut presenter = cli::make_presenter(display, "naqshsmith").map_err(|e| e.to_string())?;
 
-   crate::context::display_threaded_image_on_screen(arc_mutex_canv);
+    crate::context::display_threaded_image_on_screen(presenter.as_mut(), arc_mutex_canv)
+        .map_err(|e| e.to_string())
 }
@@ -0,0 +1,80 @@
+use std::path::{Path, PathBuf};
+
+use log::warn;
+
+use crate::free_form::DEFAULT_TESSELLATION;
+use crate::gltf_loader::GltfModel;
+use crate::ply_loader::load_ply;
+use crate::stl_loader::{load_stl, DEFAULT_WELD_TOLERANCE};
+use crate::wavefront_parser::WavefronObject;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModelFormat {
+    Obj,
+    Gltf,
+    Stl,
+    Ply,
+}
+
+impl ModelFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+
+        match extension.as_str() {
+            "obj" => Some(ModelFormat::Obj),
+            "gltf" | "glb" => Some(ModelFormat::Gltf),
+            "stl" => Some(ModelFormat::Stl),
+            "ply" => Some(ModelFormat::Ply),
+            _ => None,
+        }
+    }
+}
+
+pub const SUPPORTED_EXTENSIONS: &str = ".obj, .gltf, .glb, .stl or .ply";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LoadOptions {
+    // Distance under which STL corners are merged into one vertex.
+    pub weld_tolerance: f64,
+    // Samples per direction for OBJ curves and surfaces.
+    pub tessellation: usize,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        Self {
+            weld_tolerance: DEFAULT_WELD_TOLERANCE,
+            tessellation: DEFAULT_TESSELLATION,
+        }
+    }
+}
+
+// Every format ends up as a `WavefronObject`, which is what the draw modes consume.
+// Multi-part files are merged into one object.
+pub fn load_model(path: PathBuf, options: &LoadOptions) -> Result<WavefronObject, String> {
+    if !path.is_file() {
+        return Err(format!("no such file: {}", path.display()));
+    }
+
+    match ModelFormat::from_path(&path) {
+        Some(ModelFormat::Obj) => Ok(WavefronObject::new(path).tessellated(options.tessellation)),
+        Some(ModelFormat::Gltf) => {
+            let model = GltfModel::load(path.clone()).map_err(|err| err.to_string())?;
+
+            if model.has_textures() {
+                warn!(path:% = path.display(); "gltf textures are only used in scene files, dropping them");
+            }
+
+            Ok(model.merged())
+        }
+        Some(ModelFormat::Stl) => {
+            load_stl(path, options.weld_tolerance).map_err(|err| err.to_string())
+        }
+        Some(ModelFormat::Ply) => load_ply(path).map_err(|err| err.to_string()),
+        None => Err(format!(
+            "{}: unknown model format, expected {}",
+            path.display(),
+            SUPPORTED_EXTENSIONS
+        )),
+    }
+}
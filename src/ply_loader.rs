@@ -0,0 +1,405 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use log::info;
+
+use crate::image_canvas::Rgb;
+use crate::linear_algebra::Point3;
+use crate::wavefront_parser::{ElementTags, PrimitiveElement, WavefronObject};
+
+#[derive(Debug)]
+pub enum PlyError {
+    Io(PathBuf, io::Error),
+    Malformed(PathBuf, String),
+}
+
+impl fmt::Display for PlyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlyError::Io(path, err) => write!(f, "could not read {}: {}", path.display(), err),
+            PlyError::Malformed(path, msg) => write!(f, "{}: {}", path.display(), msg),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PlyFormat {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ScalarType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl ScalarType {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "char" | "int8" => Some(ScalarType::I8),
+            "uchar" | "uint8" => Some(ScalarType::U8),
+            "short" | "int16" => Some(ScalarType::I16),
+            "ushort" | "uint16" => Some(ScalarType::U16),
+            "int" | "int32" => Some(ScalarType::I32),
+            "uint" | "uint32" => Some(ScalarType::U32),
+            "float" | "float32" => Some(ScalarType::F32),
+            "double" | "float64" => Some(ScalarType::F64),
+            _ => None,
+        }
+    }
+
+    fn get_size(&self) -> usize {
+        match self {
+            ScalarType::I8 | ScalarType::U8 => 1,
+            ScalarType::I16 | ScalarType::U16 => 2,
+            ScalarType::I32 | ScalarType::U32 | ScalarType::F32 => 4,
+            ScalarType::F64 => 8,
+        }
+    }
+
+    // Color channels are stored either as integers over the type's range or as floats
+    // in [0, 1].
+    fn to_channel(self, value: f64) -> u8 {
+        let unit = match self {
+            ScalarType::F32 | ScalarType::F64 => value,
+            ScalarType::U16 | ScalarType::I16 => value / 65535.0,
+            _ => value / 255.0,
+        };
+
+        (unit.clamp(0.0, 1.0) * 255.0).round() as u8
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum PropertyKind {
+    Scalar(ScalarType),
+    List(ScalarType, ScalarType),
+}
+
+#[derive(Clone, Debug)]
+struct Property {
+    name: String,
+    kind: PropertyKind,
+}
+
+#[derive(Clone, Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+impl Element {
+    fn find(&self, names: &[&str]) -> Option<usize> {
+        names
+            .iter()
+            .find_map(|name| self.properties.iter().position(|p| p.name == *name))
+    }
+}
+
+struct PlyHeader {
+    format: PlyFormat,
+    elements: Vec<Element>,
+    body_offset: usize,
+}
+
+fn parse_header(bytes: &[u8]) -> Result<PlyHeader, String> {
+    let mut format = None;
+    let mut elements: Vec<Element> = vec![];
+    let mut offset = 0;
+
+    for (number, raw) in bytes.split(|b| *b == b'\n').enumerate() {
+        offset += raw.len() + 1;
+
+        let line = std::str::from_utf8(raw)
+            .map_err(|_| format!("header line {} is not valid UTF-8", number + 1))?;
+        let tokens = line.split_whitespace().collect::<Vec<&str>>();
+        let error = |msg: &str| format!("header line {}: {}", number + 1, msg);
+
+        if number == 0 {
+            match tokens.first() {
+                Some(&"ply") => continue,
+                _ => return Err(String::from("missing 'ply' magic")),
+            }
+        }
+
+        match tokens.as_slice() {
+            ["format", name, _version] => {
+                format = Some(match *name {
+                    "ascii" => PlyFormat::Ascii,
+                    "binary_little_endian" => PlyFormat::BinaryLittleEndian,
+                    "binary_big_endian" => PlyFormat::BinaryBigEndian,
+                    _ => return Err(error(&format!("unknown format '{}'", name))),
+                });
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count.parse().map_err(|_| error("invalid element count"))?,
+                properties: vec![],
+            }),
+            ["property", "list", count, item, name] => {
+                let element = elements
+                    .last_mut()
+                    .ok_or_else(|| error("property before any element"))?;
+                let scalar = |t: &str| {
+                    ScalarType::from_name(t).ok_or_else(|| error(&format!("unknown type '{}'", t)))
+                };
+
+                element.properties.push(Property {
+                    name: name.to_string(),
+                    kind: PropertyKind::List(scalar(count)?, scalar(item)?),
+                });
+            }
+            ["property", ty, name] => {
+                let element = elements
+                    .last_mut()
+                    .ok_or_else(|| error("property before any element"))?;
+                let ty = ScalarType::from_name(ty)
+                    .ok_or_else(|| error(&format!("unknown type '{}'", ty)))?;
+
+                element.properties.push(Property {
+                    name: name.to_string(),
+                    kind: PropertyKind::Scalar(ty),
+                });
+            }
+            ["end_header"] => {
+                return Ok(PlyHeader {
+                    format: format.ok_or("header has no format line")?,
+                    elements,
+                    body_offset: offset.min(bytes.len()),
+                })
+            }
+            ["comment", ..] | ["obj_info", ..] | [] => (),
+            _ => return Err(error(&format!("unexpected '{}'", line.trim()))),
+        }
+    }
+
+    Err(String::from("missing end_header"))
+}
+
+enum BodyReader<'a> {
+    Ascii(std::str::SplitWhitespace<'a>),
+    Binary {
+        bytes: &'a [u8],
+        position: usize,
+        big_endian: bool,
+    },
+}
+
+impl<'a> BodyReader<'a> {
+    fn new(format: PlyFormat, body: &'a [u8]) -> Result<Self, String> {
+        match format {
+            PlyFormat::Ascii => std::str::from_utf8(body)
+                .map(|text| BodyReader::Ascii(text.split_whitespace()))
+                .map_err(|_| String::from("ascii body is not valid UTF-8")),
+            _ => Ok(BodyReader::Binary {
+                bytes: body,
+                position: 0,
+                big_endian: format == PlyFormat::BinaryBigEndian,
+            }),
+        }
+    }
+
+    fn read(&mut self, ty: ScalarType) -> Result<f64, String> {
+        match self {
+            BodyReader::Ascii(tokens) => tokens
+                .next()
+                .ok_or_else(|| String::from("unexpected end of data"))?
+                .parse::<f64>()
+                .map_err(|_| String::from("invalid number in body")),
+            BodyReader::Binary {
+                bytes,
+                position,
+                big_endian,
+            } => {
+                let size = ty.get_size();
+                let mut raw = bytes
+                    .get(*position..*position + size)
+                    .ok_or_else(|| String::from("unexpected end of data"))?
+                    .to_vec();
+                *position += size;
+
+                if *big_endian {
+                    raw.reverse();
+                }
+
+                let value = match ty {
+                    ScalarType::I8 => raw[0] as i8 as f64,
+                    ScalarType::U8 => raw[0] as f64,
+                    ScalarType::I16 => i16::from_le_bytes([raw[0], raw[1]]) as f64,
+                    ScalarType::U16 => u16::from_le_bytes([raw[0], raw[1]]) as f64,
+                    ScalarType::I32 => i32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as f64,
+                    ScalarType::U32 => u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as f64,
+                    ScalarType::F32 => f32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as f64,
+                    ScalarType::F64 => f64::from_le_bytes([
+                        raw[0], raw[1], raw[2], raw[3], raw[4], raw[5], raw[6], raw[7],
+                    ]),
+                };
+
+                Ok(value)
+            }
+        }
+    }
+
+    // One entry per property; lists come back as their items.
+    fn read_entry(&mut self, element: &Element) -> Result<Vec<Vec<f64>>, String> {
+        element
+            .properties
+            .iter()
+            .map(|property| match property.kind {
+                PropertyKind::Scalar(ty) => Ok(vec![self.read(ty)?]),
+                PropertyKind::List(count, item) => {
+                    let n = self.read(count)?;
+
+                    if n < 0.0 {
+                        return Err(format!("negative list length in '{}'", property.name));
+                    }
+
+                    (0..n as usize).map(|_| self.read(item)).collect()
+                }
+            })
+            .collect()
+    }
+}
+
+// Vertex attributes go by their common names; anything else on the vertex, and every
+// element besides vertices and faces, is read and dropped.
+pub fn load_ply(path: PathBuf) -> Result<WavefronObject, PlyError> {
+    let bytes = std::fs::read(&path).map_err(|err| PlyError::Io(path.clone(), err))?;
+    let malformed = |msg: String| PlyError::Malformed(path.clone(), msg);
+
+    let header = parse_header(&bytes).map_err(malformed)?;
+    let mut reader =
+        BodyReader::new(header.format, &bytes[header.body_offset..]).map_err(malformed)?;
+
+    let mut positions = vec![];
+    let mut normals = vec![];
+    let mut uvs = vec![];
+    let mut colors: Vec<Rgb> = vec![];
+    let mut triangles = vec![];
+    let mut has_vertices = false;
+
+    for element in &header.elements {
+        let entries = (0..element.count)
+            .map(|_| reader.read_entry(element))
+            .collect::<Result<Vec<Vec<Vec<f64>>>, String>>()
+            .map_err(|msg| malformed(format!("in element '{}': {}", element.name, msg)))?;
+
+        match element.name.as_str() {
+            "vertex" => {
+                has_vertices = true;
+
+                let xyz = [
+                    element.find(&["x"]),
+                    element.find(&["y"]),
+                    element.find(&["z"]),
+                ];
+                let normal = [
+                    element.find(&["nx"]),
+                    element.find(&["ny"]),
+                    element.find(&["nz"]),
+                ];
+                let uv = [
+                    element.find(&["u", "s", "texture_u", "texture_s"]),
+                    element.find(&["v", "t", "texture_v", "texture_t"]),
+                ];
+                let rgb = [
+                    element.find(&["red", "r", "diffuse_red"]),
+                    element.find(&["green", "g", "diffuse_green"]),
+                    element.find(&["blue", "b", "diffuse_blue"]),
+                ];
+
+                let [Some(x), Some(y), Some(z)] = xyz else {
+                    return Err(malformed(String::from("vertex element without x, y and z")));
+                };
+
+                for entry in &entries {
+                    positions.push(Point3(entry[x][0], entry[y][0], entry[z][0]));
+
+                    if let [Some(nx), Some(ny), Some(nz)] = normal {
+                        normals.push(Point3(entry[nx][0], entry[ny][0], entry[nz][0]));
+                    }
+
+                    if let [Some(u), Some(v)] = uv {
+                        uvs.push((entry[u][0], entry[v][0]));
+                    }
+
+                    if let [Some(r), Some(g), Some(b)] = rgb {
+                        colors.push([r, g, b].map(|c| match element.properties[c].kind {
+                            PropertyKind::Scalar(ty) => ty.to_channel(entry[c][0]),
+                            PropertyKind::List(..) => 255,
+                        }));
+                    }
+                }
+            }
+            "face" => {
+                let Some(indices) = element.find(&["vertex_indices", "vertex_index"]) else {
+                    return Err(malformed(String::from(
+                        "face element without vertex_indices",
+                    )));
+                };
+
+                for entry in &entries {
+                    let corners = entry[indices]
+                        .iter()
+                        // Negative indices land out of range and are caught below.
+                        .map(|i| match *i >= 0.0 {
+                            true => *i as usize,
+                            false => usize::MAX,
+                        })
+                        .collect::<Vec<usize>>();
+
+                    for i in 2..corners.len() {
+                        triangles.push([corners[0], corners[i - 1], corners[i]]);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    if !has_vertices {
+        return Err(malformed(String::from("no vertex element")));
+    }
+
+    if let Some(t) = triangles.iter().flatten().find(|&&i| i >= positions.len()) {
+        return Err(malformed(format!(
+            "face index {} out of range for {} vertices",
+            t,
+            positions.len()
+        )));
+    }
+
+    info!(
+        path:% = path.display(),
+        format:? = header.format,
+        vertices = positions.len(),
+        faces = triangles.len(),
+        colors = !colors.is_empty();
+        "ply model loaded"
+    );
+
+    // Scans often come as bare point clouds; without faces every vertex becomes a point.
+    let points = match triangles.is_empty() {
+        true => vec![PrimitiveElement {
+            vertices: (1..=positions.len()).collect(),
+            tags: ElementTags::default(),
+        }],
+        false => vec![],
+    };
+
+    Ok(
+        WavefronObject::from_triangles(positions, uvs, normals, &triangles)
+            .with_colors(colors)
+            .with_points(points),
+    )
+}
@@ -0,0 +1,613 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::color::{
+    encode_srgb_at, linear_to_srgb, srgb_curve_to_linear, srgb_to_linear, LinearRgb,
+};
+use crate::cube_lut::CubeLut;
+use crate::depth_buffer::DepthBuffer;
+use crate::image_canvas::Canvas;
+
+pub const DEFAULT_BLUR_RADIUS: f64 = 2.0;
+pub const DEFAULT_SHARPEN_AMOUNT: f64 = 0.5;
+pub const DEFAULT_SHARPEN_RADIUS: f64 = 1.0;
+pub const DEFAULT_VIGNETTE_STRENGTH: f64 = 0.5;
+pub const DEFAULT_VIGNETTE_RADIUS: f64 = 0.5;
+pub const DEFAULT_DOF_RANGE: f64 = 0.1;
+pub const DEFAULT_DOF_RADIUS: f64 = 6.0;
+pub const MAX_DOF_RADIUS: f64 = 32.0;
+
+pub const EFFECT_NAMES: [&str; 7] = [
+    "box-blur",
+    "gaussian-blur",
+    "sharpen",
+    "fxaa",
+    "vignette",
+    "lut",
+    "dof",
+];
+
+// Stages run in order over the finished canvas, in linear light except for LUT grading,
+// which looks up display values the way .cube files expect.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PostEffect {
+    BoxBlur {
+        radius: usize,
+    },
+    GaussianBlur {
+        radius: f64,
+    },
+    // Unsharp mask: adds back `amount` times the difference from a gaussian blur.
+    Sharpen {
+        amount: f64,
+        radius: f64,
+    },
+    Fxaa,
+    // Darkens towards the corners starting at `radius`, where 0 is the centre and 1 the
+    // corners, reaching `strength` at the corners.
+    Vignette {
+        strength: f64,
+        radius: f64,
+    },
+    // `amount` mixes between the input and the graded colour.
+    ColorGrade {
+        lut: Arc<CubeLut>,
+        amount: f64,
+    },
+    // Depths within `range` of `focus` stay sharp, and the blur grows to `radius` pixels
+    // by twice that. Without a focus depth the one under the centre of the frame is used.
+    DepthOfField {
+        focus: Option<f64>,
+        range: f64,
+        radius: f64,
+    },
+}
+
+impl PostEffect {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            PostEffect::BoxBlur { .. } => "box-blur",
+            PostEffect::GaussianBlur { .. } => "gaussian-blur",
+            PostEffect::Sharpen { .. } => "sharpen",
+            PostEffect::Fxaa => "fxaa",
+            PostEffect::Vignette { .. } => "vignette",
+            PostEffect::ColorGrade { .. } => "lut",
+            PostEffect::DepthOfField { .. } => "dof",
+        }
+    }
+
+    pub fn needs_depth(&self) -> bool {
+        matches!(self, PostEffect::DepthOfField { .. })
+    }
+}
+
+// One `[[post]]` table of a scene, or one `--post name:key=value,...` on the command line.
+// Which keys apply depends on the effect.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PostDescription {
+    pub effect: String,
+    pub radius: Option<f64>,
+    pub amount: Option<f64>,
+    pub strength: Option<f64>,
+    pub path: Option<PathBuf>,
+    pub focus: Option<f64>,
+    pub range: Option<f64>,
+}
+
+fn check_range(name: &str, value: f64, valid: bool, expected: &str) -> Result<f64, String> {
+    match value.is_finite() && valid {
+        true => Ok(value),
+        false => Err(format!("{} must be {}", name, expected)),
+    }
+}
+
+impl PostDescription {
+    pub fn parse_spec(spec: &str) -> Result<Self, String> {
+        let (effect, options) = spec.split_once(':').unwrap_or((spec, ""));
+        let mut description = PostDescription {
+            effect: effect.to_string(),
+            ..PostDescription::default()
+        };
+
+        for option in options.split(',').filter(|o| !o.is_empty()) {
+            let (key, value) = option
+                .split_once('=')
+                .ok_or_else(|| format!("expected key=value, found '{}'", option))?;
+            let number = || {
+                value
+                    .parse::<f64>()
+                    .map_err(|_| format!("{} expects a number, found '{}'", key, value))
+            };
+
+            match key {
+                "radius" => description.radius = Some(number()?),
+                "amount" => description.amount = Some(number()?),
+                "strength" => description.strength = Some(number()?),
+                "focus" => description.focus = Some(number()?),
+                "range" => description.range = Some(number()?),
+                "path" => description.path = Some(PathBuf::from(value)),
+                _ => return Err(format!("unknown option '{}'", key)),
+            }
+        }
+
+        Ok(description)
+    }
+
+    fn check_keys(&self, allowed: &[&str]) -> Result<(), String> {
+        let given = [
+            ("radius", self.radius.is_some()),
+            ("amount", self.amount.is_some()),
+            ("strength", self.strength.is_some()),
+            ("path", self.path.is_some()),
+            ("focus", self.focus.is_some()),
+            ("range", self.range.is_some()),
+        ];
+
+        match given
+            .iter()
+            .find(|(name, set)| *set && !allowed.contains(name))
+        {
+            Some((name, _)) => Err(format!("{} does not take `{}`", self.effect, name)),
+            None => Ok(()),
+        }
+    }
+
+    // A relative LUT path is taken from `base`.
+    pub fn build(&self, base: &Path) -> Result<PostEffect, String> {
+        match self.effect.as_str() {
+            "box-blur" => {
+                self.check_keys(&["radius"])?;
+
+                let radius = self.radius.unwrap_or(DEFAULT_BLUR_RADIUS);
+
+                check_range(
+                    "radius",
+                    radius,
+                    radius >= 1.0 && radius.fract() == 0.0,
+                    "a whole number of pixels, at least 1",
+                )?;
+
+                Ok(PostEffect::BoxBlur {
+                    radius: radius as usize,
+                })
+            }
+            "gaussian-blur" => {
+                self.check_keys(&["radius"])?;
+
+                let radius = self.radius.unwrap_or(DEFAULT_BLUR_RADIUS);
+
+                Ok(PostEffect::GaussianBlur {
+                    radius: check_range("radius", radius, radius > 0.0, "a positive number")?,
+                })
+            }
+            "sharpen" => {
+                self.check_keys(&["amount", "radius"])?;
+
+                let amount = self.amount.unwrap_or(DEFAULT_SHARPEN_AMOUNT);
+                let radius = self.radius.unwrap_or(DEFAULT_SHARPEN_RADIUS);
+
+                Ok(PostEffect::Sharpen {
+                    amount: check_range("amount", amount, amount >= 0.0, "non-negative")?,
+                    radius: check_range("radius", radius, radius > 0.0, "a positive number")?,
+                })
+            }
+            "fxaa" => {
+                self.check_keys(&[])?;
+
+                Ok(PostEffect::Fxaa)
+            }
+            "vignette" => {
+                self.check_keys(&["strength", "radius"])?;
+
+                let strength = self.strength.unwrap_or(DEFAULT_VIGNETTE_STRENGTH);
+                let radius = self.radius.unwrap_or(DEFAULT_VIGNETTE_RADIUS);
+
+                Ok(PostEffect::Vignette {
+                    strength: check_range(
+                        "strength",
+                        strength,
+                        (0.0..=1.0).contains(&strength),
+                        "between 0 and 1",
+                    )?,
+                    radius: check_range(
+                        "radius",
+                        radius,
+                        (0.0..1.0).contains(&radius),
+                        "at least 0 and below 1",
+                    )?,
+                })
+            }
+            "lut" => {
+                self.check_keys(&["path", "amount"])?;
+
+                let path = self
+                    .path
+                    .as_ref()
+                    .ok_or("lut needs a `path` to a .cube file")?;
+                let amount = self.amount.unwrap_or(1.0);
+
+                check_range(
+                    "amount",
+                    amount,
+                    (0.0..=1.0).contains(&amount),
+                    "between 0 and 1",
+                )?;
+
+                let lut = CubeLut::load(base.join(path)).map_err(|err| err.to_string())?;
+
+                Ok(PostEffect::ColorGrade {
+                    lut: Arc::new(lut),
+                    amount,
+                })
+            }
+            "dof" => {
+                self.check_keys(&["focus", "range", "radius"])?;
+
+                let range = self.range.unwrap_or(DEFAULT_DOF_RANGE);
+                let radius = self.radius.unwrap_or(DEFAULT_DOF_RADIUS);
+
+                if let Some(focus) = self.focus {
+                    check_range("focus", focus, true, "a finite number")?;
+                }
+
+                Ok(PostEffect::DepthOfField {
+                    focus: self.focus,
+                    range: check_range("range", range, range > 0.0, "a positive number")?,
+                    radius: check_range(
+                        "radius",
+                        radius,
+                        (1.0..=MAX_DOF_RADIUS).contains(&radius),
+                        &format!("between 1 and {}", MAX_DOF_RADIUS),
+                    )?,
+                })
+            }
+            other => Err(format!(
+                "unknown effect '{}', expected one of {}",
+                other,
+                EFFECT_NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+pub fn gaussian_kernel(radius: f64) -> Vec<f64> {
+    let sigma = (radius / 3.0).max(0.5);
+    let reach = radius.ceil().max(1.0) as i64;
+
+    let weights = (-reach..=reach)
+        .map(|i| (-((i * i) as f64) / (2.0 * sigma * sigma)).exp())
+        .collect::<Vec<f64>>();
+    let total = weights.iter().sum::<f64>();
+
+    weights.into_iter().map(|w| w / total).collect()
+}
+
+pub fn box_kernel(radius: usize) -> Vec<f64> {
+    vec![1.0 / (2 * radius + 1) as f64; 2 * radius + 1]
+}
+
+// Runs a symmetric kernel across and then down, clamping at the edges.
+pub fn convolve_separable(
+    pixels: &[LinearRgb],
+    width: usize,
+    height: usize,
+    kernel: &[f64],
+) -> Vec<LinearRgb> {
+    let reach = (kernel.len() / 2) as i64;
+    let (w, h) = (width as i64, height as i64);
+
+    let pass = |source: &[LinearRgb], dx: i64, dy: i64| {
+        let mut out = vec![[0.0; 3]; source.len()];
+
+        for y in 0..h {
+            for x in 0..w {
+                let mut sum = [0.0; 3];
+
+                for (k, weight) in kernel.iter().enumerate() {
+                    let offset = k as i64 - reach;
+                    let sx = (x + offset * dx).clamp(0, w - 1);
+                    let sy = (y + offset * dy).clamp(0, h - 1);
+                    let texel = source[(sx + sy * w) as usize];
+
+                    for c in 0..3 {
+                        sum[c] += texel[c] * weight;
+                    }
+                }
+
+                out[(x + y * w) as usize] = sum;
+            }
+        }
+
+        out
+    };
+
+    pass(&pass(pixels, 1, 0), 0, 1)
+}
+
+// Perceptual luma, which is what FXAA's thresholds are tuned for.
+fn luma(color: LinearRgb) -> f64 {
+    linear_to_srgb(0.299 * color[0] + 0.587 * color[1] + 0.114 * color[2])
+}
+
+fn smoothstep(edge0: f64, edge1: f64, x: f64) -> f64 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+
+    t * t * (3.0 - 2.0 * t)
+}
+
+struct Frame {
+    width: usize,
+    height: usize,
+    pixels: Vec<LinearRgb>,
+}
+
+impl Frame {
+    fn get(&self, x: i64, y: i64) -> LinearRgb {
+        let x = x.clamp(0, self.width as i64 - 1) as usize;
+        let y = y.clamp(0, self.height as i64 - 1) as usize;
+
+        self.pixels[x + y * self.width]
+    }
+
+    // Bilinear, with pixel centres at half-integer coordinates.
+    fn sample(&self, u: f64, v: f64) -> LinearRgb {
+        let (u, v) = (u - 0.5, v - 0.5);
+        let (x0, y0) = (u.floor(), v.floor());
+        let (fx, fy) = (u - x0, v - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let [a, b, c, d] = [
+            self.get(x0, y0),
+            self.get(x0 + 1, y0),
+            self.get(x0, y0 + 1),
+            self.get(x0 + 1, y0 + 1),
+        ];
+
+        std::array::from_fn(|i| {
+            let bottom = a[i] + (b[i] - a[i]) * fx;
+            let top = c[i] + (d[i] - c[i]) * fx;
+
+            bottom + (top - bottom) * fy
+        })
+    }
+
+    fn apply(&mut self, effect: &PostEffect, depth_buffer: Option<&DepthBuffer>) {
+        let (w, h) = (self.width, self.height);
+
+        match effect {
+            PostEffect::BoxBlur { radius } => {
+                self.pixels = convolve_separable(&self.pixels, w, h, &box_kernel(*radius));
+            }
+            PostEffect::GaussianBlur { radius } => {
+                self.pixels = convolve_separable(&self.pixels, w, h, &gaussian_kernel(*radius));
+            }
+            PostEffect::Sharpen { amount, radius } => {
+                let blurred = convolve_separable(&self.pixels, w, h, &gaussian_kernel(*radius));
+
+                for (pixel, blur) in self.pixels.iter_mut().zip(blurred) {
+                    for c in 0..3 {
+                        pixel[c] = (pixel[c] + (pixel[c] - blur[c]) * amount).max(0.0);
+                    }
+                }
+            }
+            PostEffect::Fxaa => self.fxaa(),
+            PostEffect::Vignette { strength, radius } => {
+                let (cx, cy) = (w as f64 / 2.0, h as f64 / 2.0);
+                let corner = (cx * cx + cy * cy).sqrt();
+
+                for (i, pixel) in self.pixels.iter_mut().enumerate() {
+                    let dx = (i % w) as f64 + 0.5 - cx;
+                    let dy = (i / w) as f64 + 0.5 - cy;
+                    let distance = (dx * dx + dy * dy).sqrt() / corner;
+                    let factor = 1.0 - strength * smoothstep(*radius, 1.0, distance);
+
+                    *pixel = pixel.map(|c| c * factor);
+                }
+            }
+            PostEffect::ColorGrade { lut, amount } => {
+                for pixel in self.pixels.iter_mut() {
+                    let display = pixel.map(linear_to_srgb);
+                    let graded = lut.sample(display);
+
+                    *pixel = std::array::from_fn(|c| {
+                        srgb_curve_to_linear(display[c] + (graded[c] - display[c]) * amount)
+                    });
+                }
+            }
+            PostEffect::DepthOfField {
+                focus,
+                range,
+                radius,
+            } => {
+                if let Some(depth_buffer) = depth_buffer {
+                    self.depth_of_field(depth_buffer, *focus, *range, *radius);
+                }
+            }
+        }
+    }
+
+    // The classic FXAA: blends along the local edge direction, estimated from the luma of
+    // the diagonal neighbours, and falls back to the shorter blend when the longer one
+    // picks up colour from across the edge.
+    fn fxaa(&mut self) {
+        const REDUCE_MIN: f64 = 1.0 / 128.0;
+        const REDUCE_MUL: f64 = 1.0 / 8.0;
+        const SPAN_MAX: f64 = 8.0;
+
+        let lumas = self.pixels.iter().map(|p| luma(*p)).collect::<Vec<f64>>();
+        let (w, h) = (self.width as i64, self.height as i64);
+        let luma_at = |x: i64, y: i64| lumas[(x.clamp(0, w - 1) + y.clamp(0, h - 1) * w) as usize];
+
+        let mut out = self.pixels.clone();
+
+        for y in 0..h {
+            for x in 0..w {
+                let (nw, ne) = (luma_at(x - 1, y + 1), luma_at(x + 1, y + 1));
+                let (sw, se) = (luma_at(x - 1, y - 1), luma_at(x + 1, y - 1));
+                let m = luma_at(x, y);
+
+                let luma_min = m.min(nw).min(ne).min(sw).min(se);
+                let luma_max = m.max(nw).max(ne).max(sw).max(se);
+
+                let dir = [-((nw + ne) - (sw + se)), (nw + sw) - (ne + se)];
+
+                if dir[0] == 0.0 && dir[1] == 0.0 {
+                    continue;
+                }
+
+                let reduce = ((nw + ne + sw + se) * 0.25 * REDUCE_MUL).max(REDUCE_MIN);
+                let scale = 1.0 / (dir[0].abs().min(dir[1].abs()) + reduce);
+                let dir = dir.map(|d| (d * scale).clamp(-SPAN_MAX, SPAN_MAX));
+
+                let (u, v) = (x as f64 + 0.5, y as f64 + 0.5);
+                let at = |t: f64| self.sample(u + dir[0] * t, v + dir[1] * t);
+
+                let (a0, a1) = (at(1.0 / 3.0 - 0.5), at(2.0 / 3.0 - 0.5));
+                let (b0, b1) = (at(-0.5), at(0.5));
+
+                let near: LinearRgb = std::array::from_fn(|c| 0.5 * (a0[c] + a1[c]));
+                let far: LinearRgb =
+                    std::array::from_fn(|c| 0.5 * near[c] + 0.25 * (b0[c] + b1[c]));
+
+                let far_luma = luma(far);
+
+                out[(x + y * w) as usize] = match far_luma < luma_min || far_luma > luma_max {
+                    true => near,
+                    false => far,
+                };
+            }
+        }
+
+        self.pixels = out;
+    }
+
+    // Gathers over a disc as wide as each pixel's circle of confusion, counting only the
+    // neighbours whose own circle reaches back, so sharp surfaces do not smear into the
+    // blurred ones behind them. Pixels no mesh covered are blurred the most.
+    fn depth_of_field(
+        &mut self,
+        depth_buffer: &DepthBuffer,
+        focus: Option<f64>,
+        range: f64,
+        radius: f64,
+    ) {
+        let (w, h) = (self.width, self.height);
+        let covered = |d: f64| d > -f64::MAX;
+
+        let focus = match focus {
+            Some(focus) => focus,
+            None => match depth_buffer.get_depth(w / 2, h / 2).filter(|d| covered(*d)) {
+                Some(depth) => depth,
+                None => match depth_buffer.get_covered_range() {
+                    Some((near, far)) => (near + far) / 2.0,
+                    None => return,
+                },
+            },
+        };
+
+        let confusion = depth_buffer
+            .get_depths()
+            .iter()
+            .map(|d| match covered(*d) {
+                true => radius * (((d - focus).abs() - range) / range).clamp(0.0, 1.0),
+                false => radius,
+            })
+            .collect::<Vec<f64>>();
+
+        let reach = radius.ceil() as i64;
+        let mut offsets = vec![];
+
+        for dy in -reach..=reach {
+            for dx in -reach..=reach {
+                let distance = ((dx * dx + dy * dy) as f64).sqrt();
+
+                if distance <= radius {
+                    offsets.push((dx, dy, distance));
+                }
+            }
+        }
+
+        offsets.sort_by(|a, b| a.2.total_cmp(&b.2));
+
+        let mut out = self.pixels.clone();
+
+        for y in 0..h as i64 {
+            for x in 0..w as i64 {
+                let at = (x + y * w as i64) as usize;
+                let own = confusion[at];
+
+                if own < 0.5 {
+                    continue;
+                }
+
+                let mut sum = [0.0; 3];
+                let mut total = 0.0;
+
+                for &(dx, dy, distance) in offsets.iter().take_while(|o| o.2 <= own) {
+                    let (sx, sy) = (x + dx, y + dy);
+
+                    if sx < 0 || sy < 0 || sx >= w as i64 || sy >= h as i64 {
+                        continue;
+                    }
+
+                    let neighbour = (sx + sy * w as i64) as usize;
+
+                    if distance > 0.0 && confusion[neighbour] + 0.5 < distance {
+                        continue;
+                    }
+
+                    for (s, value) in sum.iter_mut().zip(self.pixels[neighbour]) {
+                        *s += value;
+                    }
+
+                    total += 1.0;
+                }
+
+                out[at] = sum.map(|c| c / total);
+            }
+        }
+
+        self.pixels = out;
+    }
+}
+
+// Runs `effects` over the canvas. Depth of field is skipped without a depth buffer, which
+// has to match the canvas in size.
+pub fn apply_post_effects(
+    effects: &[PostEffect],
+    depth_buffer: Option<&DepthBuffer>,
+    dither: bool,
+    image_canvas: &mut Canvas,
+) {
+    if effects.is_empty() {
+        return;
+    }
+
+    let (width, height) = image_canvas.get_size();
+    let mut frame = Frame {
+        width,
+        height,
+        pixels: (0..width * height)
+            .map(|i| {
+                image_canvas
+                    .get_pixel_rgb(i % width, i / width)
+                    .map(srgb_to_linear)
+            })
+            .collect(),
+    };
+
+    for effect in effects {
+        frame.apply(effect, depth_buffer);
+    }
+
+    for (i, pixel) in frame.pixels.into_iter().enumerate() {
+        let (x, y) = (i % width, i / width);
+
+        image_canvas
+            .set_pixel_rgb(x as i32, y as i32, encode_srgb_at(pixel, x, y, dither))
+            .unwrap();
+    }
+}
@@ -0,0 +1,392 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use crate::image_canvas::Canvas;
+use crate::write_image::{write_image, ImageFormat};
+
+#[derive(Debug)]
+pub enum PresentError {
+    Io(io::Error),
+    Backend(String),
+}
+
+impl fmt::Display for PresentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PresentError::Io(err) => write!(f, "i/o error while presenting: {}", err),
+            PresentError::Backend(msg) => write!(f, "display backend error: {}", msg),
+        }
+    }
+}
+
+impl From<io::Error> for PresentError {
+    fn from(err: io::Error) -> Self {
+        PresentError::Io(err)
+    }
+}
+
+// Mouse deltas are in window pixels, wheel steps are signed notches.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PresenterEvent {
+    Quit,
+    Key(char),
+    Orbit(f64, f64),
+    Pan(f64, f64),
+    Zoom(i32),
+}
+
+pub trait Presenter {
+    fn present(&mut self, image: &Canvas) -> Result<(), PresentError>;
+
+    fn poll_events(&mut self) -> Vec<PresenterEvent> {
+        vec![]
+    }
+
+    fn set_status(&mut self, _status: &str) {}
+
+    fn is_open(&self) -> bool {
+        true
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+pub struct FileSequencePresenter {
+    directory: PathBuf,
+    prefix: String,
+    format: ImageFormat,
+    frame: usize,
+    max_frames: Option<usize>,
+}
+
+impl FileSequencePresenter {
+    pub fn new(directory: PathBuf, format: ImageFormat, max_frames: Option<usize>) -> Self {
+        Self {
+            directory,
+            prefix: String::from("frame_"),
+            format,
+            frame: 0,
+            max_frames,
+        }
+    }
+
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    pub fn get_frame_path(&self, frame: usize) -> PathBuf {
+        self.directory.join(format!(
+            "{}{:05}.{}",
+            self.prefix,
+            frame,
+            self.format.get_extension()
+        ))
+    }
+
+    pub fn get_frames_written(&self) -> usize {
+        self.frame
+    }
+}
+
+impl Presenter for FileSequencePresenter {
+    fn present(&mut self, image: &Canvas) -> Result<(), PresentError> {
+        if self.frame == 0 {
+            std::fs::create_dir_all(&self.directory)?;
+        }
+
+        write_image(self.get_frame_path(self.frame), image, self.format)?;
+        self.frame += 1;
+
+        Ok(())
+    }
+
+    fn is_open(&self) -> bool {
+        match self.max_frames {
+            Some(max) => self.frame < max,
+            None => true,
+        }
+    }
+}
+
+// Packed RGB24 frames, top row first, back to back with no header; this is what
+// `ffmpeg -f rawvideo -pix_fmt rgb24 -s WxH -i -` expects.
+pub struct RawFramePresenter<W: Write> {
+    out: W,
+    frame: usize,
+    max_frames: Option<usize>,
+}
+
+impl RawFramePresenter<io::Stdout> {
+    pub fn stdout(max_frames: Option<usize>) -> Self {
+        Self::new(io::stdout(), max_frames)
+    }
+}
+
+impl<W: Write> RawFramePresenter<W> {
+    pub fn new(out: W, max_frames: Option<usize>) -> Self {
+        Self {
+            out,
+            frame: 0,
+            max_frames,
+        }
+    }
+}
+
+impl<W: Write> Presenter for RawFramePresenter<W> {
+    fn present(&mut self, image: &Canvas) -> Result<(), PresentError> {
+        for row in image.rows().rev() {
+            self.out.write_all(row)?;
+        }
+
+        self.out.flush()?;
+        self.frame += 1;
+
+        Ok(())
+    }
+
+    fn is_open(&self) -> bool {
+        match self.max_frames {
+            Some(max) => self.frame < max,
+            None => true,
+        }
+    }
+}
+
+pub const DEFAULT_TERMINAL_COLUMNS: usize = 80;
+
+// Each character cell is an upper half block: foreground is the top pixel, background the
+// bottom one, so a cell covers two image rows.
+pub struct TerminalPresenter<W: Write> {
+    out: W,
+    columns: usize,
+    status: String,
+    cleared: bool,
+}
+
+impl TerminalPresenter<io::Stdout> {
+    pub fn stdout(columns: usize) -> Self {
+        Self::new(io::stdout(), columns)
+    }
+}
+
+impl<W: Write> TerminalPresenter<W> {
+    pub fn new(out: W, columns: usize) -> Self {
+        Self {
+            out,
+            columns: columns.max(1),
+            status: String::new(),
+            cleared: false,
+        }
+    }
+
+    pub fn encode_frame(&self, image: &Canvas) -> String {
+        let (w, h) = image.get_size();
+
+        if w == 0 || h == 0 {
+            return String::new();
+        }
+
+        let columns = usize::min(self.columns, w);
+        let rows = usize::max(1, (columns * h / w) / 2 * 2);
+
+        // Image rows are bottom-up, the terminal draws top-down.
+        let sample = |col: usize, row: usize| {
+            let x = col * w / columns;
+            let y = h - 1 - usize::min(h - 1, row * h / rows);
+
+            image.get_pixel_rgb(x, y)
+        };
+
+        let mut frame = String::with_capacity(rows / 2 * columns * 40);
+
+        for cell_row in (0..rows).step_by(2) {
+            for col in 0..columns {
+                let [tr, tg, tb] = sample(col, cell_row);
+                let [br, bg, bb] = sample(col, usize::min(cell_row + 1, rows - 1));
+
+                frame.push_str(&format!(
+                    "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m\u{2580}",
+                    tr, tg, tb, br, bg, bb
+                ));
+            }
+
+            frame.push_str("\x1b[0m\n");
+        }
+
+        frame
+    }
+}
+
+impl<W: Write> Presenter for TerminalPresenter<W> {
+    fn present(&mut self, image: &Canvas) -> Result<(), PresentError> {
+        let frame = self.encode_frame(image);
+
+        if !self.cleared {
+            self.out.write_all(b"\x1b[2J")?;
+            self.cleared = true;
+        }
+
+        self.out.write_all(b"\x1b[H")?;
+        self.out.write_all(frame.as_bytes())?;
+        writeln!(self.out, "\x1b[2K{}", self.status)?;
+        self.out.flush()?;
+
+        Ok(())
+    }
+
+    fn set_status(&mut self, status: &str) {
+        self.status = status.to_string();
+    }
+}
+
+#[cfg(feature = "sdl")]
+pub use sdl_presenter::SdlPresenter;
+
+#[cfg(feature = "sdl")]
+mod sdl_presenter {
+    use sdl2::event::Event;
+    use sdl2::keyboard::Keycode;
+    use sdl2::pixels::PixelFormatEnum;
+    use sdl2::render::{Texture, WindowCanvas};
+    use sdl2::EventPump;
+
+    use super::{PresentError, Presenter, PresenterEvent};
+    use crate::image_canvas::Canvas;
+
+    pub struct SdlPresenter {
+        _sdl_context: sdl2::Sdl,
+        canvas: WindowCanvas,
+        texture: Option<(Texture, usize, usize)>,
+        event_pump: EventPump,
+        open: bool,
+    }
+
+    fn backend_error<E: ToString>(err: E) -> PresentError {
+        PresentError::Backend(err.to_string())
+    }
+
+    impl SdlPresenter {
+        pub fn new(title: &str, width: usize, height: usize) -> Result<Self, PresentError> {
+            let sdl_context = sdl2::init().map_err(backend_error)?;
+            let video_subsystem = sdl_context.video().map_err(backend_error)?;
+
+            let window = video_subsystem
+                .window(title, width as u32, height as u32)
+                .position_centered()
+                .build()
+                .map_err(backend_error)?;
+
+            let canvas = window
+                .into_canvas()
+                .present_vsync()
+                .build()
+                .map_err(backend_error)?;
+
+            let event_pump = sdl_context.event_pump().map_err(backend_error)?;
+
+            Ok(Self {
+                _sdl_context: sdl_context,
+                canvas,
+                texture: None,
+                event_pump,
+                open: true,
+            })
+        }
+    }
+
+    impl Presenter for SdlPresenter {
+        fn present(&mut self, image: &Canvas) -> Result<(), PresentError> {
+            let (w, h) = image.get_size();
+
+            let stale = !matches!(&self.texture, Some((_, tw, th)) if *tw == w && *th == h);
+
+            if stale {
+                let texture = self
+                    .canvas
+                    .texture_creator()
+                    .create_texture_streaming(PixelFormatEnum::RGB24, w as u32, h as u32)
+                    .map_err(backend_error)?;
+
+                self.texture = Some((texture, w, h));
+            }
+
+            let (texture, _, _) = self.texture.as_mut().unwrap();
+
+            texture
+                .with_lock(None, |buffer: &mut [u8], pitch: usize| {
+                    for (y, row) in image.rows().enumerate() {
+                        buffer[y * pitch..y * pitch + row.len()].copy_from_slice(row);
+                    }
+                })
+                .map_err(backend_error)?;
+
+            // Canvas rows are bottom-up, SDL's are top-down.
+            self.canvas
+                .copy_ex(texture, None, None, 0.0, None, false, true)
+                .map_err(backend_error)?;
+            self.canvas.present();
+
+            Ok(())
+        }
+
+        fn poll_events(&mut self) -> Vec<PresenterEvent> {
+            let mut events = vec![];
+
+            for event in self.event_pump.poll_iter() {
+                match event {
+                    Event::Quit { .. }
+                    | Event::KeyDown {
+                        keycode: Some(Keycode::Escape),
+                        ..
+                    } => {
+                        self.open = false;
+                        events.push(PresenterEvent::Quit);
+                    }
+                    Event::KeyDown {
+                        keycode: Some(keycode),
+                        ..
+                    } => {
+                        let name = keycode.name();
+                        let mut chars = name.chars();
+
+                        if let (Some(c), None) = (chars.next(), chars.next()) {
+                            events.push(PresenterEvent::Key(c.to_ascii_lowercase()));
+                        }
+                    }
+                    Event::MouseMotion {
+                        mousestate,
+                        xrel,
+                        yrel,
+                        ..
+                    } => {
+                        if mousestate.left() {
+                            events.push(PresenterEvent::Orbit(xrel as f64, yrel as f64));
+                        } else if mousestate.right() || mousestate.middle() {
+                            events.push(PresenterEvent::Pan(xrel as f64, yrel as f64));
+                        }
+                    }
+                    Event::MouseWheel { y, .. } => events.push(PresenterEvent::Zoom(y)),
+                    _ => {}
+                }
+            }
+
+            events
+        }
+
+        fn set_status(&mut self, status: &str) {
+            let _ = self.canvas.window_mut().set_title(status);
+        }
+
+        fn is_open(&self) -> bool {
+            self.open
+        }
+
+        fn is_interactive(&self) -> bool {
+            true
+        }
+    }
+}
@@ -1,13 +1,312 @@
-use embedded_graphics::pixelcolor::Rgb888;
-use std::{fs, path::PathBuf};
-use tinytga::Tga;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
 
-pub fn read_tga(path: PathBuf) -> Vec<u8> {
-    let data = fs::read(path).unwrap();
+use log::debug;
+use tinytga::{Bpp, ImageOrigin, ImageType, ParseError, RawTga};
 
-    let tga: Tga<Rgb888> = Tga::from_slice(data.as_slice()).unwrap();
+use crate::image_canvas::Canvas;
 
-    let map = tga.as_raw().image_data().to_vec();
+#[derive(Debug)]
+pub enum TgaError {
+    Io(PathBuf, io::Error),
+    Malformed(PathBuf, String),
+}
+
+impl fmt::Display for TgaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TgaError::Io(path, err) => write!(f, "could not read {}: {}", path.display(), err),
+            TgaError::Malformed(path, msg) => write!(f, "{}: {}", path.display(), msg),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TgaFormat {
+    Gray,
+    GrayAlpha,
+    Rgb,
+    Rgba,
+}
+
+impl TgaFormat {
+    pub fn get_channels(&self) -> usize {
+        match self {
+            TgaFormat::Gray => 1,
+            TgaFormat::GrayAlpha => 2,
+            TgaFormat::Rgb => 3,
+            TgaFormat::Rgba => 4,
+        }
+    }
+
+    pub fn has_alpha(&self) -> bool {
+        matches!(self, TgaFormat::GrayAlpha | TgaFormat::Rgba)
+    }
+}
+
+// Rows run top to bottom and pixels left to right whatever the file's origin was, with
+// `format.get_channels()` bytes per pixel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TgaImage {
+    pub width: usize,
+    pub height: usize,
+    pub format: TgaFormat,
+    pub data: Vec<u8>,
+}
+
+impl TgaImage {
+    pub fn get_size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    pub fn get_pixel_rgba(&self, x: usize, y: usize) -> Option<[u8; 4]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        let channels = self.format.get_channels();
+        let at = (x + y * self.width) * channels;
+        let p = &self.data[at..at + channels];
+
+        Some(match self.format {
+            TgaFormat::Gray => [p[0], p[0], p[0], 255],
+            TgaFormat::GrayAlpha => [p[0], p[0], p[0], p[1]],
+            TgaFormat::Rgb => [p[0], p[1], p[2], 255],
+            TgaFormat::Rgba => [p[0], p[1], p[2], p[3]],
+        })
+    }
+
+    // The canvas is bottom-up, which is also where OBJ puts v = 0. Alpha is dropped.
+    pub fn to_canvas(&self) -> Canvas {
+        let mut canvas = Canvas::new(self.width, self.height, 0);
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let [r, g, b, _] = self.get_pixel_rgba(x, y).unwrap();
+
+                canvas
+                    .set_pixel_rgb(x as i32, (self.height - 1 - y) as i32, [r, g, b])
+                    .unwrap();
+            }
+        }
+
+        canvas
+    }
+
+    // Same layout as `to_canvas`, with the alpha value in every channel.
+    pub fn alpha_to_canvas(&self) -> Option<Canvas> {
+        if !self.format.has_alpha() {
+            return None;
+        }
+
+        let mut canvas = Canvas::new(self.width, self.height, 0);
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let [_, _, _, a] = self.get_pixel_rgba(x, y).unwrap();
+
+                canvas
+                    .set_pixel(x as i32, (self.height - 1 - y) as i32, a)
+                    .unwrap();
+            }
+        }
+
+        Some(canvas)
+    }
+}
+
+fn describe(err: ParseError) -> String {
+    match err {
+        ParseError::ColorMap => String::from("truncated or invalid color map"),
+        ParseError::Header => String::from("truncated or invalid header"),
+        ParseError::Footer => String::from("invalid footer"),
+        ParseError::UnsupportedImageType(t) => format!("unsupported image type {}", t),
+        ParseError::UnsupportedBpp(bits) => format!("unsupported depth of {} bits", bits),
+        err => format!("{:?}", err),
+    }
+}
+
+fn read_value(data: &[u8], at: usize, bytes: usize) -> Option<u32> {
+    let raw = data.get(at..at + bytes)?;
+
+    Some(raw.iter().rev().fold(0, |value, b| value << 8 | *b as u32))
+}
+
+// One raw value per pixel in file order: colors, gray levels or color map indices.
+fn unpack_values(data: &[u8], count: usize, bytes: usize, rle: bool) -> Option<Vec<u32>> {
+    if !rle {
+        return (0..count)
+            .map(|i| read_value(data, i * bytes, bytes))
+            .collect();
+    }
+
+    let mut values = Vec::with_capacity(count);
+    let mut at = 0;
+
+    // Each packet is a count byte, then either one value repeated or that many values.
+    while values.len() < count {
+        let packet = *data.get(at)?;
+        let length = (packet & 0x7f) as usize + 1;
+        at += 1;
+
+        if packet & 0x80 != 0 {
+            let value = read_value(data, at, bytes)?;
+
+            values.extend(std::iter::repeat_n(value, length));
+            at += bytes;
+        } else {
+            for _ in 0..length {
+                values.push(read_value(data, at, bytes)?);
+                at += bytes;
+            }
+        }
+    }
+
+    // A run may spill over the last row.
+    values.truncate(count);
+
+    Some(values)
+}
+
+// 16-bit colors are A1R5G5B5, the wider ones BGR(A) in little-endian order.
+fn push_color(out: &mut Vec<u8>, value: u32, bpp: Bpp, format: TgaFormat) -> Result<(), String> {
+    let (r, g, b, a) = match bpp {
+        Bpp::Bits16 => {
+            let expand = |c: u32| ((c << 3) | (c >> 2)) as u8;
+            let alpha = match value & 0x8000 != 0 {
+                true => 255,
+                false => 0,
+            };
+
+            (
+                expand((value >> 10) & 31),
+                expand((value >> 5) & 31),
+                expand(value & 31),
+                alpha,
+            )
+        }
+        Bpp::Bits24 | Bpp::Bits32 => (
+            (value >> 16) as u8,
+            (value >> 8) as u8,
+            value as u8,
+            (value >> 24) as u8,
+        ),
+        _ => return Err(format!("unsupported color depth of {} bits", bpp.bits())),
+    };
+
+    match format {
+        TgaFormat::Rgba => out.extend_from_slice(&[r, g, b, a]),
+        _ => out.extend_from_slice(&[r, g, b]),
+    }
+
+    Ok(())
+}
+
+pub fn decode_tga(bytes: &[u8]) -> Result<TgaImage, String> {
+    let raw = RawTga::from_slice(bytes).map_err(describe)?;
+    let header = raw.header();
+
+    let (width, height) = (header.width as usize, header.height as usize);
+    let image_type = raw.image_type();
+
+    if image_type == ImageType::Empty || width == 0 || height == 0 {
+        return Err(String::from("image has no pixel data"));
+    }
+
+    let color_map = match image_type {
+        ImageType::ColorMapped | ImageType::RleColorMapped => Some(
+            raw.color_map()
+                .ok_or("color-mapped image without a color map")?,
+        ),
+        _ => None,
+    };
+
+    let color_bpp = raw.color_bpp();
+    let format = match (image_type.is_monochrome(), color_bpp) {
+        (true, Bpp::Bits8) => TgaFormat::Gray,
+        (true, Bpp::Bits16) => TgaFormat::GrayAlpha,
+        (true, bpp) => {
+            return Err(format!(
+                "unsupported grayscale depth of {} bits",
+                bpp.bits()
+            ))
+        }
+        (false, Bpp::Bits16) if header.alpha_channel_depth == 0 => TgaFormat::Rgb,
+        (false, Bpp::Bits24) => TgaFormat::Rgb,
+        (false, _) => TgaFormat::Rgba,
+    };
+
+    let values = unpack_values(
+        raw.image_data(),
+        width * height,
+        raw.image_data_bpp().bytes() as usize,
+        image_type.is_rle(),
+    )
+    .ok_or("pixel data is truncated")?;
+
+    let mut decoded = Vec::with_capacity(width * height * format.get_channels());
+
+    for value in values {
+        let value = match color_map {
+            // tinytga indexes the map from zero, the file from its first entry.
+            Some(map) => value
+                .checked_sub(header.color_map_start as u32)
+                .and_then(|i| map.get_raw(i as usize))
+                .ok_or_else(|| format!("color index {} is outside the color map", value))?,
+            None => value,
+        };
+
+        match format {
+            TgaFormat::Gray => decoded.push(value as u8),
+            TgaFormat::GrayAlpha => decoded.extend_from_slice(&[value as u8, (value >> 8) as u8]),
+            _ => push_color(&mut decoded, value, color_bpp, format)?,
+        }
+    }
+
+    // Files are stored bottom row first unless the descriptor says otherwise, and rarely
+    // right to left.
+    let origin = raw.image_origin();
+    let flip_x = matches!(origin, ImageOrigin::BottomRight | ImageOrigin::TopRight);
+    let flip_y = matches!(origin, ImageOrigin::BottomLeft | ImageOrigin::BottomRight);
+    let channels = format.get_channels();
+
+    let mut data = vec![0; decoded.len()];
+
+    for (i, pixel) in decoded.chunks_exact(channels).enumerate() {
+        let (mut x, mut y) = (i % width, i / width);
+
+        if flip_x {
+            x = width - 1 - x;
+        }
+
+        if flip_y {
+            y = height - 1 - y;
+        }
+
+        let at = (x + y * width) * channels;
+        data[at..at + channels].copy_from_slice(pixel);
+    }
+
+    debug!(
+        width = width,
+        height = height,
+        image_type:? = image_type,
+        format:? = format,
+        origin:? = origin;
+        "tga decoded"
+    );
+
+    Ok(TgaImage {
+        width,
+        height,
+        format,
+        data,
+    })
+}
+
+pub fn read_tga(path: PathBuf) -> Result<TgaImage, TgaError> {
+    let bytes = std::fs::read(&path).map_err(|err| TgaError::Io(path.clone(), err))?;
 
-    map
+    decode_tga(&bytes).map_err(|msg| TgaError::Malformed(path, msg))
 }
@@ -0,0 +1,1333 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use log::{debug, info, warn};
+use serde::Deserialize;
+
+use crate::a_buffer::{ABuffer, DEFAULT_MAX_LAYERS};
+use crate::animation::{
+    join_key, AnimationDescription, AnimationError, KeyframeAnimation, ScaleValue,
+};
+use crate::camera::OrbitCamera;
+use crate::color::decode_srgb;
+use crate::context::{render_viewer_frame, save_depth, LightPreset, ViewerMode};
+use crate::depth_buffer::DepthBuffer;
+use crate::draw_modes::blend::blend::{BlendFactor, BlendState};
+use crate::draw_modes::draw_depth::draw_depth::{DepthRange, DepthScale, DepthSettings};
+use crate::draw_modes::draw_hidden_line::draw_hidden_line::shade_flat_with_depth;
+use crate::draw_modes::draw_primitives::draw_primitives::{
+    draw_primitives, has_primitives, PrimitiveStyle, DEFAULT_LINE_WIDTH, DEFAULT_POINT_SIZE,
+};
+use crate::draw_modes::draw_scene::draw_scene::{
+    shade_material_mesh, EnvironmentLight, Material, SceneLight, DEFAULT_ROUGHNESS,
+};
+use crate::environment::Environment;
+use crate::gltf_loader::GltfModel;
+use crate::hdr_target::{
+    BloomSettings, HdrTarget, ToneMapping, ToneSettings, DEFAULT_BLOOM_INTENSITY,
+    DEFAULT_BLOOM_RADIUS, DEFAULT_BLOOM_THRESHOLD,
+};
+use crate::image_canvas::{Canvas, Rgb};
+use crate::linear_algebra::{
+    compose_affine, identity_affine, Affine, Matrix3, Point3, Quaternion, Transform,
+};
+use crate::model_loader::{load_model, LoadOptions, ModelFormat, SUPPORTED_EXTENSIONS};
+use crate::post_process::{apply_post_effects, PostDescription, PostEffect};
+use crate::presenter::{PresentError, Presenter};
+use crate::texture_cache::{TextureCache, DEFAULT_TEXTURE_BUDGET};
+use crate::texture_sampler::{SamplerSettings, TextureFilter, DEFAULT_MAX_ANISOTROPY};
+use crate::wavefront_parser::WavefronObject;
+
+pub const DEFAULT_AMBIENT: f64 = 0.1;
+pub const DEFAULT_BACKGROUND: Rgb = [20, 20, 20];
+pub const DEFAULT_ENVIRONMENT_FOV: f64 = 60.0;
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(PathBuf, io::Error),
+    Parse(String),
+    Invalid { key: String, message: String },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(path, err) => write!(f, "could not read {}: {}", path.display(), err),
+            SceneError::Parse(msg) => write!(f, "{}", msg),
+            SceneError::Invalid { key, message } => write!(f, "{}: {}", key, message),
+        }
+    }
+}
+
+impl From<AnimationError> for SceneError {
+    fn from(err: AnimationError) -> Self {
+        match err {
+            AnimationError::Io(path, err) => SceneError::Io(path, err),
+            AnimationError::Parse(msg) => SceneError::Parse(msg),
+            AnimationError::Invalid { key, message } => SceneError::Invalid { key, message },
+        }
+    }
+}
+
+fn invalid(key: &str, message: String) -> SceneError {
+    SceneError::Invalid {
+        key: key.to_string(),
+        message,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SceneRenderMode {
+    // Materials, textures and every scene light.
+    Shaded,
+    // One of the viewer's modes over all meshes merged together.
+    Viewer(ViewerMode),
+}
+
+impl SceneRenderMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "shaded" => Some(SceneRenderMode::Shaded),
+            _ => ViewerMode::from_name(name).map(SceneRenderMode::Viewer),
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            SceneRenderMode::Shaded => "shaded",
+            SceneRenderMode::Viewer(mode) => mode.get_name(),
+        }
+    }
+}
+
+// How transparent meshes are put together: sorted per mesh and per face, or through an
+// A-buffer holding up to `max_layers` fragments per pixel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transparency {
+    Sorted,
+    Oit { max_layers: usize },
+}
+
+impl Transparency {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Transparency::Sorted => "sorted",
+            Transparency::Oit { .. } => "oit",
+        }
+    }
+}
+
+// Lights the shaded mode in place of the ambient term. `rotation` turns the map about the
+// vertical axis, in degrees. As a background it is seen through a pinhole with a vertical
+// field of view of `fov` degrees, since the orthographic camera would see one direction.
+pub struct SceneEnvironment {
+    pub map: Environment,
+    pub intensity: f64,
+    pub rotation: f64,
+    pub background: bool,
+    pub fov: f64,
+}
+
+impl SceneEnvironment {
+    // `view` takes world directions into the space the meshes are shaded in.
+    pub fn get_light(&self, view: Matrix3) -> EnvironmentLight<'_> {
+        EnvironmentLight {
+            map: &self.map,
+            to_environment: Matrix3::rotation_y(-self.rotation.to_radians()) * view.transpose(),
+            intensity: self.intensity,
+        }
+    }
+}
+
+pub struct SceneMesh {
+    pub path: PathBuf,
+    pub model: WavefronObject,
+    pub transform: Transform,
+    pub material: usize,
+}
+
+pub struct Scene {
+    pub size: (usize, usize),
+    pub background: Rgb,
+    pub mode: SceneRenderMode,
+    pub ambient: f64,
+    pub primitives: PrimitiveStyle,
+    pub sampler: SamplerSettings,
+    pub transparency: Transparency,
+    pub tone: ToneSettings,
+    pub dither: bool,
+    pub post: Vec<PostEffect>,
+    pub environment: Option<SceneEnvironment>,
+    pub depth: DepthSettings,
+    pub camera: Transform,
+    pub lights: Vec<SceneLight>,
+    pub materials: Vec<Material>,
+    pub meshes: Vec<SceneMesh>,
+    pub animation: Option<KeyframeAnimation>,
+}
+
+fn default_width() -> usize {
+    800
+}
+
+fn default_height() -> usize {
+    800
+}
+
+fn default_background() -> Rgb {
+    DEFAULT_BACKGROUND
+}
+
+fn default_mode() -> String {
+    String::from("shaded")
+}
+
+fn default_ambient() -> f64 {
+    DEFAULT_AMBIENT
+}
+
+fn default_line_width() -> f64 {
+    DEFAULT_LINE_WIDTH
+}
+
+fn default_point_size() -> f64 {
+    DEFAULT_POINT_SIZE
+}
+
+fn default_texture_filter() -> String {
+    String::from(TextureFilter::Trilinear.get_name())
+}
+
+fn default_max_anisotropy() -> usize {
+    DEFAULT_MAX_ANISOTROPY
+}
+
+fn default_texture_budget_mb() -> usize {
+    DEFAULT_TEXTURE_BUDGET / (1024 * 1024)
+}
+
+fn default_transparency() -> String {
+    String::from("sorted")
+}
+
+fn default_oit_layers() -> usize {
+    DEFAULT_MAX_LAYERS
+}
+
+fn default_tone_mapping() -> String {
+    String::from(ToneMapping::Clamp.get_name())
+}
+
+fn default_bloom_threshold() -> f64 {
+    DEFAULT_BLOOM_THRESHOLD
+}
+
+fn default_bloom_intensity() -> f64 {
+    DEFAULT_BLOOM_INTENSITY
+}
+
+fn default_bloom_radius() -> f64 {
+    DEFAULT_BLOOM_RADIUS
+}
+
+fn default_depth_scale() -> String {
+    String::from(DepthScale::Linear.get_name())
+}
+
+fn default_roughness() -> f64 {
+    DEFAULT_ROUGHNESS
+}
+
+fn default_environment_fov() -> f64 {
+    DEFAULT_ENVIRONMENT_FOV
+}
+
+fn default_one() -> f64 {
+    1.0
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_white() -> Rgb {
+    [255; 3]
+}
+
+fn default_scale() -> ScaleValue {
+    ScaleValue::Uniform(1.0)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CanvasDescription {
+    #[serde(default = "default_width")]
+    pub width: usize,
+    #[serde(default = "default_height")]
+    pub height: usize,
+    #[serde(default = "default_background")]
+    pub background: Rgb,
+}
+
+impl Default for CanvasDescription {
+    fn default() -> Self {
+        Self {
+            width: default_width(),
+            height: default_height(),
+            background: default_background(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RenderDescription {
+    #[serde(default = "default_mode")]
+    pub mode: String,
+    #[serde(default = "default_ambient")]
+    pub ambient: f64,
+    #[serde(default = "default_line_width")]
+    pub line_width: f64,
+    #[serde(default = "default_point_size")]
+    pub point_size: f64,
+    #[serde(default = "default_texture_filter")]
+    pub texture_filter: String,
+    #[serde(default)]
+    pub lod_bias: f64,
+    #[serde(default = "default_max_anisotropy")]
+    pub max_anisotropy: usize,
+    #[serde(default = "default_texture_budget_mb")]
+    pub texture_budget_mb: usize,
+    #[serde(default = "default_transparency")]
+    pub transparency: String,
+    #[serde(default = "default_oit_layers")]
+    pub oit_layers: usize,
+    #[serde(default = "default_tone_mapping")]
+    pub tone_mapping: String,
+    #[serde(default)]
+    pub exposure: f64,
+    pub bloom: Option<BloomDescription>,
+    #[serde(default)]
+    pub dither: bool,
+    #[serde(default = "default_depth_scale")]
+    pub depth_scale: String,
+    // [near, far], larger being nearer.
+    pub depth_range: Option<[f64; 2]>,
+    pub depth_out: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BloomDescription {
+    #[serde(default = "default_bloom_threshold")]
+    pub threshold: f64,
+    #[serde(default = "default_bloom_intensity")]
+    pub intensity: f64,
+    #[serde(default = "default_bloom_radius")]
+    pub radius: f64,
+}
+
+impl Default for RenderDescription {
+    fn default() -> Self {
+        Self {
+            mode: default_mode(),
+            ambient: default_ambient(),
+            line_width: default_line_width(),
+            point_size: default_point_size(),
+            texture_filter: default_texture_filter(),
+            lod_bias: 0.0,
+            max_anisotropy: default_max_anisotropy(),
+            texture_budget_mb: default_texture_budget_mb(),
+            transparency: default_transparency(),
+            oit_layers: default_oit_layers(),
+            tone_mapping: default_tone_mapping(),
+            exposure: 0.0,
+            bloom: None,
+            dither: false,
+            depth_scale: default_depth_scale(),
+            depth_range: None,
+            depth_out: None,
+        }
+    }
+}
+
+// Rotations are either yaw, pitch and roll in degrees or a w, x, y, z quaternion.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CameraDescription {
+    #[serde(default)]
+    pub position: [f64; 3],
+    pub rotation: Option<[f64; 3]>,
+    pub quaternion: Option<[f64; 4]>,
+    #[serde(default = "default_one")]
+    pub zoom: f64,
+}
+
+impl Default for CameraDescription {
+    fn default() -> Self {
+        Self {
+            position: [0.0; 3],
+            rotation: None,
+            quaternion: None,
+            zoom: default_one(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LightDescription {
+    pub direction: [f64; 3],
+    #[serde(default = "default_one")]
+    pub intensity: f64,
+    #[serde(default = "default_white")]
+    pub color: Rgb,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MaterialDescription {
+    #[serde(default = "default_white")]
+    pub color: Rgb,
+    pub texture: Option<PathBuf>,
+    #[serde(default = "default_one")]
+    pub opacity: f64,
+    pub blend: Option<BlendValue>,
+    pub alpha_test: Option<f64>,
+    #[serde(default = "default_true")]
+    pub depth_write: bool,
+    #[serde(default)]
+    pub reflectivity: f64,
+    #[serde(default = "default_roughness")]
+    pub roughness: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EnvironmentDescription {
+    pub path: PathBuf,
+    #[serde(default = "default_one")]
+    pub intensity: f64,
+    #[serde(default)]
+    pub rotation: f64,
+    #[serde(default)]
+    pub background: bool,
+    #[serde(default = "default_environment_fov")]
+    pub fov: f64,
+}
+
+// Either a preset name or a [src, dst] pair of factor names.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum BlendValue {
+    Preset(String),
+    Factors([String; 2]),
+}
+
+impl BlendValue {
+    fn build(&self, key: &str) -> Result<BlendState, SceneError> {
+        match self {
+            BlendValue::Preset(name) => BlendState::from_name(name).ok_or_else(|| {
+                let names = BlendState::PRESETS
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>();
+
+                invalid(
+                    key,
+                    format!(
+                        "unknown blend mode '{}', expected one of {}",
+                        name,
+                        names.join(", ")
+                    ),
+                )
+            }),
+            BlendValue::Factors([src, dst]) => {
+                let factor = |name: &str| {
+                    BlendFactor::from_name(name).ok_or_else(|| {
+                        let names = BlendFactor::ALL
+                            .iter()
+                            .map(|f| f.get_name())
+                            .collect::<Vec<_>>();
+
+                        invalid(
+                            key,
+                            format!(
+                                "unknown blend factor '{}', expected one of {}",
+                                name,
+                                names.join(", ")
+                            ),
+                        )
+                    })
+                };
+
+                Ok(BlendState {
+                    src: factor(src)?,
+                    dst: factor(dst)?,
+                })
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MeshDescription {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub position: [f64; 3],
+    pub rotation: Option<[f64; 3]>,
+    pub quaternion: Option<[f64; 4]>,
+    #[serde(default = "default_scale")]
+    pub scale: ScaleValue,
+    pub material: Option<String>,
+    pub weld_tolerance: Option<f64>,
+    pub tessellation: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SceneDescription {
+    #[serde(default)]
+    pub canvas: CanvasDescription,
+    #[serde(default)]
+    pub render: RenderDescription,
+    #[serde(default)]
+    pub camera: CameraDescription,
+    #[serde(default)]
+    pub lights: Vec<LightDescription>,
+    #[serde(default)]
+    pub materials: BTreeMap<String, MaterialDescription>,
+    pub meshes: Vec<MeshDescription>,
+    pub animation: Option<AnimationDescription>,
+    #[serde(default)]
+    pub post: Vec<PostDescription>,
+    pub environment: Option<EnvironmentDescription>,
+}
+
+fn build_rotation(
+    key: &str,
+    euler: Option<[f64; 3]>,
+    quaternion: Option<[f64; 4]>,
+) -> Result<Quaternion, SceneError> {
+    match (euler, quaternion) {
+        (None, None) => Ok(Quaternion::identity()),
+        (Some([yaw, pitch, roll]), None) => Ok(Quaternion::from_euler_degrees(yaw, pitch, roll)),
+        (None, Some([w, x, y, z])) if w != 0.0 || x != 0.0 || y != 0.0 || z != 0.0 => {
+            Ok(Quaternion::new(w, x, y, z))
+        }
+        (None, Some(_)) => Err(invalid(
+            &join_key(key, "quaternion"),
+            String::from("must not be all zero"),
+        )),
+        (Some(_), Some(_)) => Err(invalid(
+            key,
+            String::from("set either `rotation` or `quaternion`, not both"),
+        )),
+    }
+}
+
+// Relative paths in a scene are relative to the scene file, not the working directory.
+fn resolve_path(base: &Path, path: &Path) -> PathBuf {
+    match path.is_absolute() {
+        true => path.to_path_buf(),
+        false => base.join(path),
+    }
+}
+
+fn check_file(key: &str, path: &Path) -> Result<(), SceneError> {
+    match path.is_file() {
+        true => Ok(()),
+        false => Err(invalid(key, format!("no such file: {}", path.display()))),
+    }
+}
+
+impl SceneDescription {
+    pub fn build(self, base: &Path) -> Result<Scene, SceneError> {
+        let (w, h) = (self.canvas.width, self.canvas.height);
+
+        if w == 0 || h == 0 {
+            return Err(invalid(
+                "canvas",
+                format!("{}x{} is not a usable canvas size", w, h),
+            ));
+        }
+
+        let mode = SceneRenderMode::from_name(&self.render.mode).ok_or_else(|| {
+            let names = ViewerMode::ALL
+                .iter()
+                .map(|m| m.get_name())
+                .collect::<Vec<_>>();
+
+            invalid(
+                "render.mode",
+                format!(
+                    "unknown mode '{}', expected shaded or one of {}",
+                    self.render.mode,
+                    names.join(", ")
+                ),
+            )
+        })?;
+
+        if !self.render.ambient.is_finite() || self.render.ambient < 0.0 {
+            return Err(invalid(
+                "render.ambient",
+                String::from("must be a non-negative number"),
+            ));
+        }
+
+        for (key, pixels) in [
+            ("render.line_width", self.render.line_width),
+            ("render.point_size", self.render.point_size),
+        ] {
+            if !pixels.is_finite() || pixels < 1.0 {
+                return Err(invalid(key, String::from("must be at least one pixel")));
+            }
+        }
+
+        let filter = TextureFilter::from_name(&self.render.texture_filter).ok_or_else(|| {
+            let names = TextureFilter::ALL
+                .iter()
+                .map(|f| f.get_name())
+                .collect::<Vec<_>>();
+
+            invalid(
+                "render.texture_filter",
+                format!(
+                    "unknown filter '{}', expected one of {}",
+                    self.render.texture_filter,
+                    names.join(", ")
+                ),
+            )
+        })?;
+
+        if !self.render.lod_bias.is_finite() {
+            return Err(invalid(
+                "render.lod_bias",
+                String::from("must be a finite number"),
+            ));
+        }
+
+        if self.render.max_anisotropy == 0 {
+            return Err(invalid(
+                "render.max_anisotropy",
+                String::from("must be at least 1"),
+            ));
+        }
+
+        if self.camera.zoom <= 0.0 || !self.camera.zoom.is_finite() {
+            return Err(invalid(
+                "camera.zoom",
+                String::from("must be a positive number"),
+            ));
+        }
+
+        let [x, y, z] = self.camera.position;
+        let camera = Transform {
+            translation: Point3(x, y, z),
+            rotation: build_rotation("camera", self.camera.rotation, self.camera.quaternion)?,
+            scale: Point3(self.camera.zoom, self.camera.zoom, self.camera.zoom),
+        };
+
+        let mut lights = vec![];
+
+        for (i, light) in self.lights.into_iter().enumerate() {
+            let key = format!("lights[{}]", i);
+            let [x, y, z] = light.direction;
+
+            if x == 0.0 && y == 0.0 && z == 0.0 {
+                return Err(invalid(
+                    &join_key(&key, "direction"),
+                    String::from("must not be zero"),
+                ));
+            }
+
+            lights.push(SceneLight {
+                direction: Point3(x, y, z),
+                intensity: light.intensity,
+                color: light.color,
+            });
+        }
+
+        if lights.is_empty() {
+            lights.push(SceneLight {
+                direction: Point3(0.0, 0.0, -1.0),
+                intensity: 1.0,
+                color: [255; 3],
+            });
+        }
+
+        if self.render.oit_layers == 0 {
+            return Err(invalid(
+                "render.oit_layers",
+                String::from("must be at least 1"),
+            ));
+        }
+
+        let transparency = match self.render.transparency.as_str() {
+            "sorted" => Transparency::Sorted,
+            "oit" => Transparency::Oit {
+                max_layers: self.render.oit_layers,
+            },
+            other => {
+                return Err(invalid(
+                    "render.transparency",
+                    format!("unknown mode '{}', expected sorted or oit", other),
+                ))
+            }
+        };
+
+        let tone_mapping = ToneMapping::from_name(&self.render.tone_mapping).ok_or_else(|| {
+            let names = ToneMapping::ALL
+                .iter()
+                .map(|t| t.get_name())
+                .collect::<Vec<_>>();
+
+            invalid(
+                "render.tone_mapping",
+                format!(
+                    "unknown operator '{}', expected one of {}",
+                    self.render.tone_mapping,
+                    names.join(", ")
+                ),
+            )
+        })?;
+
+        if !self.render.exposure.is_finite() {
+            return Err(invalid(
+                "render.exposure",
+                String::from("must be a finite number"),
+            ));
+        }
+
+        let bloom = match &self.render.bloom {
+            Some(bloom) => {
+                for (key, value) in [
+                    ("render.bloom.threshold", bloom.threshold),
+                    ("render.bloom.intensity", bloom.intensity),
+                    ("render.bloom.radius", bloom.radius),
+                ] {
+                    if !value.is_finite() || value < 0.0 {
+                        return Err(invalid(key, String::from("must be a non-negative number")));
+                    }
+                }
+
+                Some(BloomSettings {
+                    threshold: bloom.threshold,
+                    intensity: bloom.intensity,
+                    radius: bloom.radius,
+                })
+            }
+            None => None,
+        };
+
+        let depth_scale = DepthScale::from_name(&self.render.depth_scale).ok_or_else(|| {
+            invalid(
+                "render.depth_scale",
+                format!(
+                    "unknown scale '{}', expected linear or log",
+                    self.render.depth_scale
+                ),
+            )
+        })?;
+
+        let depth_range = match self.render.depth_range {
+            Some([near, far]) if near.is_finite() && far.is_finite() && near != far => {
+                Some(DepthRange { near, far })
+            }
+            Some(_) => {
+                return Err(invalid(
+                    "render.depth_range",
+                    String::from("must be two different finite depths"),
+                ))
+            }
+            None => None,
+        };
+
+        if self.render.texture_budget_mb == 0 {
+            return Err(invalid(
+                "render.texture_budget_mb",
+                String::from("must be at least 1"),
+            ));
+        }
+
+        // Shared by every material, so a texture used twice is decoded once.
+        let mut textures = TextureCache::new(self.render.texture_budget_mb * 1024 * 1024);
+        let mut materials = vec![Material::default()];
+        let mut material_indices = BTreeMap::new();
+
+        for (name, material) in self.materials {
+            let key = format!("materials.{}", name);
+
+            let texture = match material.texture {
+                Some(path) => {
+                    let texture_key = join_key(&key, "texture");
+                    let path = resolve_path(base, &path);
+
+                    check_file(&texture_key, &path)?;
+
+                    let texture = textures
+                        .load(&path)
+                        .map_err(|err| invalid(&texture_key, err.to_string()))?;
+
+                    Some(texture)
+                }
+                None => None,
+            };
+
+            if !(0.0..=1.0).contains(&material.opacity) {
+                return Err(invalid(
+                    &join_key(&key, "opacity"),
+                    String::from("must be between 0 and 1"),
+                ));
+            }
+
+            for (name, value) in [
+                ("reflectivity", material.reflectivity),
+                ("roughness", material.roughness),
+            ] {
+                if !(0.0..=1.0).contains(&value) {
+                    return Err(invalid(
+                        &join_key(&key, name),
+                        String::from("must be between 0 and 1"),
+                    ));
+                }
+            }
+
+            if let Some(threshold) = material.alpha_test {
+                if !(0.0..=1.0).contains(&threshold) {
+                    return Err(invalid(
+                        &join_key(&key, "alpha_test"),
+                        String::from("must be between 0 and 1"),
+                    ));
+                }
+            }
+
+            let blend = match &material.blend {
+                Some(blend) => blend.build(&join_key(&key, "blend"))?,
+                None => BlendState::default(),
+            };
+
+            material_indices.insert(name.clone(), materials.len());
+            materials.push(Material {
+                name,
+                color: material.color,
+                texture,
+                opacity: material.opacity,
+                blend,
+                alpha_test: material.alpha_test,
+                depth_write: material.depth_write,
+                reflectivity: material.reflectivity,
+                roughness: material.roughness,
+            });
+        }
+
+        if self.meshes.is_empty() {
+            return Err(invalid(
+                "meshes",
+                String::from("a scene needs at least one mesh"),
+            ));
+        }
+
+        let mut meshes = vec![];
+
+        for (i, mesh) in self.meshes.into_iter().enumerate() {
+            let key = format!("meshes[{}]", i);
+            let path = resolve_path(base, &mesh.path);
+
+            check_file(&join_key(&key, "path"), &path)?;
+
+            let material = match &mesh.material {
+                Some(name) => Some(*material_indices.get(name).ok_or_else(|| {
+                    invalid(
+                        &join_key(&key, "material"),
+                        format!("unknown material '{}'", name),
+                    )
+                })?),
+                None => None,
+            };
+
+            let [x, y, z] = mesh.position;
+            let scale = match mesh.scale {
+                ScaleValue::Uniform(s) => Point3(s, s, s),
+                ScaleValue::Axes([x, y, z]) => Point3(x, y, z),
+            };
+            let transform = Transform {
+                translation: Point3(x, y, z),
+                rotation: build_rotation(&key, mesh.rotation, mesh.quaternion)?,
+                scale,
+            };
+
+            // glTF primitives become one mesh each and bring their own materials along,
+            // unless the scene names one.
+            let parts = match ModelFormat::from_path(&path) {
+                Some(ModelFormat::Gltf) => {
+                    let gltf = GltfModel::load(path.clone())
+                        .map_err(|err| invalid(&join_key(&key, "path"), err.to_string()))?;
+                    let first_material = materials.len();
+
+                    materials.extend(
+                        gltf.materials
+                            .iter()
+                            .map(|pbr| Material::from_pbr(pbr, &mut textures)),
+                    );
+
+                    gltf.primitives
+                        .into_iter()
+                        .map(|primitive| {
+                            let own = primitive.material.map(|m| first_material + m);
+
+                            (primitive.model, material.or(own).unwrap_or(0))
+                        })
+                        .collect()
+                }
+                Some(_) => {
+                    let mut options = LoadOptions::default();
+
+                    if let Some(tolerance) = mesh.weld_tolerance {
+                        if tolerance < 0.0 {
+                            return Err(invalid(
+                                &join_key(&key, "weld_tolerance"),
+                                String::from("must not be negative"),
+                            ));
+                        }
+
+                        options.weld_tolerance = tolerance;
+                    }
+
+                    if let Some(tessellation) = mesh.tessellation {
+                        if tessellation == 0 {
+                            return Err(invalid(
+                                &join_key(&key, "tessellation"),
+                                String::from("must be at least 1"),
+                            ));
+                        }
+
+                        options.tessellation = tessellation;
+                    }
+
+                    let model = load_model(path.clone(), &options)
+                        .map_err(|err| invalid(&join_key(&key, "path"), err))?;
+
+                    vec![(model, material.unwrap_or(0))]
+                }
+                None => {
+                    return Err(invalid(
+                        &join_key(&key, "path"),
+                        format!("unknown model format, expected {}", SUPPORTED_EXTENSIONS),
+                    ))
+                }
+            };
+
+            for (model, material) in parts {
+                meshes.push(SceneMesh {
+                    path: path.clone(),
+                    model,
+                    transform,
+                    material,
+                });
+            }
+        }
+
+        let animation = match self.animation {
+            Some(animation) => Some(animation.build("animation")?),
+            None => None,
+        };
+
+        let post = self
+            .post
+            .iter()
+            .enumerate()
+            .map(|(i, post)| {
+                post.build(base)
+                    .map_err(|msg| invalid(&format!("post[{}]", i), msg))
+            })
+            .collect::<Result<Vec<PostEffect>, SceneError>>()?;
+
+        let environment = match self.environment {
+            Some(environment) => {
+                let path = resolve_path(base, &environment.path);
+
+                check_file("environment.path", &path)?;
+
+                if !environment.intensity.is_finite() || environment.intensity < 0.0 {
+                    return Err(invalid(
+                        "environment.intensity",
+                        String::from("must be a non-negative number"),
+                    ));
+                }
+
+                if !environment.rotation.is_finite() {
+                    return Err(invalid(
+                        "environment.rotation",
+                        String::from("must be a finite number"),
+                    ));
+                }
+
+                if !(environment.fov > 0.0 && environment.fov < 180.0) {
+                    return Err(invalid(
+                        "environment.fov",
+                        String::from("must be between 0 and 180 degrees"),
+                    ));
+                }
+
+                let map = Environment::load(path)
+                    .map_err(|err| invalid("environment.path", err.to_string()))?;
+
+                Some(SceneEnvironment {
+                    map,
+                    intensity: environment.intensity,
+                    rotation: environment.rotation,
+                    background: environment.background,
+                    fov: environment.fov,
+                })
+            }
+            None => None,
+        };
+
+        let stats = textures.get_stats();
+
+        debug!(
+            textures = textures.len(),
+            hits = stats.hits,
+            misses = stats.misses,
+            evictions = stats.evictions,
+            bytes = stats.bytes;
+            "scene textures loaded"
+        );
+
+        Ok(Scene {
+            size: (w, h),
+            background: self.canvas.background,
+            mode,
+            ambient: self.render.ambient,
+            primitives: PrimitiveStyle {
+                line_width: self.render.line_width,
+                point_size: self.render.point_size,
+                ..PrimitiveStyle::default()
+            },
+            sampler: SamplerSettings {
+                filter,
+                lod_bias: self.render.lod_bias,
+                max_anisotropy: self.render.max_anisotropy,
+            },
+            transparency,
+            tone: ToneSettings {
+                operator: tone_mapping,
+                exposure: self.render.exposure,
+                bloom,
+            },
+            dither: self.render.dither,
+            post,
+            environment,
+            depth: DepthSettings {
+                scale: depth_scale,
+                range: depth_range,
+                out: self
+                    .render
+                    .depth_out
+                    .as_ref()
+                    .map(|path| resolve_path(base, path)),
+            },
+            camera,
+            lights,
+            materials,
+            meshes,
+            animation,
+        })
+    }
+}
+
+// Mean depth of the vertices after posing; larger is nearer.
+fn mesh_depth(model: &WavefronObject) -> f64 {
+    let vertices = model.get_vertices();
+
+    vertices.iter().map(|v| v.xyz.2).sum::<f64>() / vertices.len().max(1) as f64
+}
+
+impl Scene {
+    pub fn load(path: PathBuf) -> Result<Self, SceneError> {
+        let text =
+            std::fs::read_to_string(&path).map_err(|err| SceneError::Io(path.clone(), err))?;
+
+        let description: SceneDescription =
+            toml::from_str(&text).map_err(|err| SceneError::Parse(err.to_string()))?;
+
+        description.build(path.parent().unwrap_or(Path::new(".")))
+    }
+
+    pub fn get_frame_count(&self) -> usize {
+        self.animation
+            .as_ref()
+            .map_or(1, |animation| animation.get_frame_count())
+    }
+
+    pub fn get_frame_time(&self, frame: usize) -> f64 {
+        self.animation
+            .as_ref()
+            .map_or(0.0, |animation| animation.get_frame_time(frame))
+    }
+
+    // The animated model transform moves the whole scene, and the animated camera moves
+    // relative to the scene camera. Light keys, when present, drive the first light.
+    // Returns the frame's depth when `keep_depth` is set, building one if the mode kept none.
+    pub fn render_frame(
+        &self,
+        time: f64,
+        image: &mut Canvas,
+        keep_depth: bool,
+    ) -> Option<DepthBuffer> {
+        let (w, h) = image.get_size();
+
+        let pose = self.animation.as_ref().map(|a| a.evaluate(time));
+        let (root, animated_camera) = match &pose {
+            Some(pose) => (pose.model.get_affine(), pose.camera.get_view_affine()),
+            None => (identity_affine(), identity_affine()),
+        };
+
+        let view = compose_affine(animated_camera, self.camera.get_view_affine());
+        let light_rotation = animated_camera.0 * self.camera.get_view_affine().0;
+
+        let mut lights = self.lights.clone();
+
+        if let Some(animation) = self.animation.as_ref().filter(|a| !a.light.is_empty()) {
+            if let LightPreset::Directional {
+                direction,
+                intensity,
+            } = animation.light.sample(time)
+            {
+                lights[0].direction = direction;
+                lights[0].intensity = intensity;
+            }
+        }
+
+        // Only the direction matters for shading, so the zoom in the view is divided out.
+        for light in lights.iter_mut() {
+            light.direction = (light_rotation * light.direction).normalize();
+        }
+
+        let posed = self
+            .meshes
+            .iter()
+            .map(|mesh| {
+                let (linear, translation): Affine =
+                    compose_affine(view, compose_affine(root, mesh.transform.get_affine()));
+
+                mesh.model.transformed(linear, translation)
+            })
+            .collect::<Vec<WavefronObject>>();
+
+        let depth_buffer = match self.mode {
+            SceneRenderMode::Shaded => {
+                let mut depth_buffer = DepthBuffer::new(w, h);
+                let mut target = HdrTarget::new(w, h);
+
+                let environment = self
+                    .environment
+                    .as_ref()
+                    .map(|environment| environment.get_light(light_rotation));
+
+                match (&environment, &self.environment) {
+                    (
+                        Some(light),
+                        Some(SceneEnvironment {
+                            background: true,
+                            fov,
+                            ..
+                        }),
+                    ) => {
+                        let t = (fov.to_radians() / 2.0).tan();
+                        let aspect = w as f64 / h as f64;
+
+                        for y in 0..h {
+                            for x in 0..w {
+                                let ndc_x = (x as f64 + 0.5) / w as f64 * 2.0 - 1.0;
+                                let ndc_y = (y as f64 + 0.5) / h as f64 * 2.0 - 1.0;
+                                let ray = Point3(ndc_x * t * aspect, ndc_y * t, -1.0);
+
+                                target.set_pixel(x, y, light.background(ray));
+                            }
+                        }
+                    }
+                    _ => target.clear(decode_srgb(self.background)),
+                }
+
+                // Opaque meshes first, then the transparent ones back to front so they
+                // blend over everything behind them. With an A-buffer the order of the
+                // transparent ones no longer matters; it sorts each pixel instead.
+                let (mut transparent, opaque): (Vec<_>, Vec<_>) =
+                    self.meshes.iter().zip(&posed).partition(|(mesh, model)| {
+                        self.materials[mesh.material].is_transparent(model)
+                    });
+
+                transparent.sort_by(|(_, a), (_, b)| mesh_depth(a).total_cmp(&mesh_depth(b)));
+
+                let mut a_buffer = match self.transparency {
+                    Transparency::Oit { max_layers } => Some(ABuffer::new(w, h, max_layers)),
+                    Transparency::Sorted => None,
+                };
+
+                for (mesh, model) in opaque.into_iter().chain(transparent) {
+                    shade_material_mesh(
+                        model,
+                        &self.materials[mesh.material],
+                        &lights,
+                        self.ambient,
+                        environment.as_ref(),
+                        &self.sampler,
+                        &mut depth_buffer,
+                        a_buffer.as_mut(),
+                        &mut target,
+                    );
+                }
+
+                if let Some(a_buffer) = a_buffer.as_mut() {
+                    a_buffer.resolve(&depth_buffer, &mut target);
+
+                    let stats = a_buffer.get_stats();
+
+                    debug!(
+                        fragments = stats.fragments,
+                        max_layers_used = stats.max_layers_used,
+                        overflowed_pixels = stats.overflowed_pixels,
+                        dropped_fragments = stats.dropped_fragments;
+                        "a-buffer resolved"
+                    );
+
+                    if stats.overflowed_pixels > 0 {
+                        warn!(
+                            pixels = stats.overflowed_pixels,
+                            dropped = stats.dropped_fragments,
+                            max_layers = a_buffer.get_max_layers();
+                            "a-buffer overflowed, farthest layers were dropped"
+                        );
+                    }
+                }
+
+                target.resolve(&self.tone, self.dither, image);
+
+                // After every mesh, so any of them can hide another's lines and points.
+                for (mesh, model) in self.meshes.iter().zip(&posed) {
+                    if !has_primitives(model) {
+                        continue;
+                    }
+
+                    let style = PrimitiveStyle {
+                        color: self.materials[mesh.material].color,
+                        ..self.primitives
+                    };
+
+                    draw_primitives(
+                        model,
+                        &OrbitCamera::default(),
+                        &style,
+                        &mut depth_buffer,
+                        image,
+                    );
+                }
+
+                Some(depth_buffer)
+            }
+            SceneRenderMode::Viewer(mode) => {
+                let mut merged = posed[0].clone();
+
+                for model in &posed[1..] {
+                    merged.append(model);
+                }
+
+                let light = LightPreset::Directional {
+                    direction: lights[0].direction,
+                    intensity: lights[0].intensity,
+                };
+
+                let drawn = render_viewer_frame(
+                    &merged,
+                    &OrbitCamera::default(),
+                    mode,
+                    light,
+                    &self.primitives,
+                    &self.depth,
+                    image,
+                );
+
+                // The debug views draw without a depth of their own, so they only get a
+                // depth-only pass when depth of field or the saved frame needs it.
+                match drawn {
+                    Some(depth_buffer) => Some(depth_buffer),
+                    None if keep_depth || self.post.iter().any(PostEffect::needs_depth) => {
+                        let mut depth_buffer = DepthBuffer::new(w, h);
+
+                        shade_flat_with_depth(
+                            &merged,
+                            &OrbitCamera::default(),
+                            lights[0].direction,
+                            None,
+                            &mut depth_buffer,
+                        );
+
+                        Some(depth_buffer)
+                    }
+                    None => None,
+                }
+            }
+        };
+
+        apply_post_effects(&self.post, depth_buffer.as_ref(), self.dither, image);
+
+        depth_buffer.filter(|_| keep_depth)
+    }
+}
+
+pub fn render_scene<P: Presenter + ?Sized>(
+    presenter: &mut P,
+    scene: &Scene,
+) -> Result<usize, PresentError> {
+    let start = Instant::now();
+    let (w, h) = scene.size;
+
+    let mut image = Canvas::new(w, h, 0);
+    let mut rendered = 0;
+    let mut last_depth = None;
+    let mut last_time = None;
+    let frame_count = scene.get_frame_count();
+
+    for frame in 0..frame_count {
+        if !presenter.is_open() {
+            break;
+        }
+
+        let time = scene.get_frame_time(frame);
+        let keep_depth = scene.depth.out.is_some() && frame + 1 == frame_count;
+
+        last_depth = scene.render_frame(time, &mut image, keep_depth);
+        last_time = Some(time);
+        presenter.present(&image)?;
+        presenter.poll_events();
+
+        debug!(frame = frame, time = time; "scene frame");
+
+        rendered += 1;
+    }
+
+    // A presenter closed before the last frame leaves the saved one without its depth, so
+    // that frame is drawn again off screen.
+    if let (Some(_), None, Some(time)) = (&scene.depth.out, &last_depth, last_time) {
+        last_depth = scene.render_frame(time, &mut Canvas::new(w, h, 0), true);
+    }
+
+    save_depth(&scene.depth, last_depth)?;
+
+    info!(
+        mode = scene.mode.get_name(),
+        meshes = scene.meshes.len(),
+        lights = scene.lights.len(),
+        frames = rendered,
+        elapsed:? = start.elapsed();
+        "scene rendered"
+    );
+
+    Ok(rendered)
+}
@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use log::info;
+
+use crate::linear_algebra::Point3;
+use crate::wavefront_parser::WavefronObject;
+
+// STL stores every facet corner separately; corners closer than this are merged.
+pub const DEFAULT_WELD_TOLERANCE: f64 = 1e-6;
+
+const HEADER_BYTES: usize = 80;
+const FACET_BYTES: usize = 50;
+
+#[derive(Debug)]
+pub enum StlError {
+    Io(PathBuf, io::Error),
+    Malformed(PathBuf, String),
+}
+
+impl fmt::Display for StlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StlError::Io(path, err) => write!(f, "could not read {}: {}", path.display(), err),
+            StlError::Malformed(path, msg) => write!(f, "{}: {}", path.display(), msg),
+        }
+    }
+}
+
+fn read_f32_le(bytes: &[u8], at: usize) -> f64 {
+    f32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]) as f64
+}
+
+fn binary_facet_count(bytes: &[u8]) -> Option<usize> {
+    let count = bytes.get(HEADER_BYTES..HEADER_BYTES + 4)?;
+
+    Some(u32::from_le_bytes([count[0], count[1], count[2], count[3]]) as usize)
+}
+
+// Plenty of binary exporters also start the header with "solid", so only trust the
+// keyword when the size does not match the binary layout.
+fn is_ascii(bytes: &[u8]) -> bool {
+    let start = bytes
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+
+    if !bytes[start..].starts_with(b"solid") {
+        return false;
+    }
+
+    match binary_facet_count(bytes) {
+        Some(count) => bytes.len() != HEADER_BYTES + 4 + count * FACET_BYTES,
+        None => true,
+    }
+}
+
+fn parse_binary(bytes: &[u8]) -> Result<Vec<[Point3<f64>; 3]>, String> {
+    let count = binary_facet_count(bytes).ok_or("truncated binary header")?;
+    let expected = HEADER_BYTES + 4 + count * FACET_BYTES;
+
+    if bytes.len() < expected {
+        return Err(format!(
+            "header announces {} facets ({} bytes), file has {} bytes",
+            count,
+            expected,
+            bytes.len()
+        ));
+    }
+
+    // Each facet is a normal, three corners and a two byte attribute; the normal is
+    // recomputed from the winding anyway.
+    let facets = bytes[HEADER_BYTES + 4..expected]
+        .chunks_exact(FACET_BYTES)
+        .map(|facet| {
+            let corner = |i: usize| {
+                let at = 12 + i * 12;
+
+                Point3(
+                    read_f32_le(facet, at),
+                    read_f32_le(facet, at + 4),
+                    read_f32_le(facet, at + 8),
+                )
+            };
+
+            [corner(0), corner(1), corner(2)]
+        })
+        .collect();
+
+    Ok(facets)
+}
+
+fn parse_ascii(text: &str) -> Result<Vec<[Point3<f64>; 3]>, String> {
+    let mut facets = vec![];
+    let mut corners: Vec<Point3<f64>> = vec![];
+
+    for (number, line) in text.lines().enumerate() {
+        let mut tokens = line.split_whitespace();
+        let error = |msg: &str| format!("line {}: {}", number + 1, msg);
+
+        match tokens.next() {
+            Some("facet") => corners.clear(),
+            Some("vertex") => {
+                let mut coordinate = || {
+                    tokens
+                        .next()
+                        .and_then(|t| t.parse::<f64>().ok())
+                        .ok_or_else(|| error("vertex expects three numbers"))
+                };
+
+                corners.push(Point3(coordinate()?, coordinate()?, coordinate()?));
+            }
+            // Some exporters write polygons; fan them into triangles.
+            Some("endfacet") => {
+                if corners.len() < 3 {
+                    return Err(error("facet with fewer than three vertices"));
+                }
+
+                for i in 2..corners.len() {
+                    facets.push([corners[0], corners[i - 1], corners[i]]);
+                }
+
+                corners.clear();
+            }
+            _ => (),
+        }
+    }
+
+    Ok(facets)
+}
+
+// Buckets positions on a grid of `tolerance` sized cells so a lookup only has to check
+// the neighbouring cells. A zero tolerance merges bit-identical positions only.
+struct VertexWelder {
+    tolerance: f64,
+    cells: HashMap<[i64; 3], Vec<usize>>,
+    positions: Vec<Point3<f64>>,
+}
+
+impl VertexWelder {
+    fn new(tolerance: f64) -> Self {
+        Self {
+            tolerance,
+            cells: HashMap::new(),
+            positions: vec![],
+        }
+    }
+
+    fn get_cell(&self, p: Point3<f64>) -> [i64; 3] {
+        let Point3(x, y, z) = p;
+
+        match self.tolerance > 0.0 {
+            true => [x, y, z].map(|c| (c / self.tolerance).floor() as i64),
+            false => [x, y, z].map(|c| c.to_bits() as i64),
+        }
+    }
+
+    fn find(&self, p: Point3<f64>, cell: [i64; 3]) -> Option<usize> {
+        if self.tolerance <= 0.0 {
+            return self.cells.get(&cell).map(|bucket| bucket[0]);
+        }
+
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let neighbour = [cell[0] + dx, cell[1] + dy, cell[2] + dz];
+
+                    for &i in self.cells.get(&neighbour).into_iter().flatten() {
+                        if (self.positions[i] - p).get_length() <= self.tolerance {
+                            return Some(i);
+                        }
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    fn insert(&mut self, p: Point3<f64>) -> usize {
+        let cell = self.get_cell(p);
+
+        if let Some(i) = self.find(p, cell) {
+            return i;
+        }
+
+        self.positions.push(p);
+        self.cells
+            .entry(cell)
+            .or_default()
+            .push(self.positions.len() - 1);
+
+        self.positions.len() - 1
+    }
+}
+
+pub fn load_stl(path: PathBuf, weld_tolerance: f64) -> Result<WavefronObject, StlError> {
+    let bytes = std::fs::read(&path).map_err(|err| StlError::Io(path.clone(), err))?;
+    let malformed = |msg: String| StlError::Malformed(path.clone(), msg);
+
+    let ascii = is_ascii(&bytes);
+    let facets = match ascii {
+        true => {
+            let text = std::str::from_utf8(&bytes)
+                .map_err(|_| malformed(String::from("ascii STL is not valid UTF-8")))?;
+
+            parse_ascii(text)
+        }
+        false => parse_binary(&bytes),
+    }
+    .map_err(malformed)?;
+
+    let mut welder = VertexWelder::new(weld_tolerance);
+    let mut triangles = vec![];
+
+    for facet in &facets {
+        let t = facet.map(|corner| welder.insert(corner));
+
+        // Facets that collapse onto fewer than three corners would only add slivers.
+        if t[0] != t[1] && t[1] != t[2] && t[0] != t[2] {
+            triangles.push(t);
+        }
+    }
+
+    info!(
+        path:% = path.display(),
+        ascii = ascii,
+        facets = facets.len(),
+        vertices = welder.positions.len(),
+        dropped = facets.len() - triangles.len();
+        "stl model loaded"
+    );
+
+    Ok(WavefronObject::from_triangles(
+        welder.positions,
+        vec![],
+        vec![],
+        &triangles,
+    ))
+}
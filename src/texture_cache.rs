@@ -0,0 +1,241 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use log::{debug, info};
+
+use crate::image_canvas::Canvas;
+use crate::texture_loader::{decode_texture, TextureError};
+use crate::texture_sampler::MipChain;
+
+// In bytes of decoded mip levels.
+pub const DEFAULT_TEXTURE_BUDGET: usize = 256 * 1024 * 1024;
+
+// Textures are immutable once loaded, so threads and meshes share them freely.
+pub type TextureHandle = Arc<MipChain>;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: usize,
+    pub misses: usize,
+    pub evictions: usize,
+    pub bytes: usize,
+}
+
+// Files remember where they were read from, so a hash match can be checked against that
+// file on disk; decoded textures are checked against their base level instead.
+struct CacheEntry {
+    texture: TextureHandle,
+    hash: u64,
+    origin: Option<PathBuf>,
+    bytes: usize,
+    last_used: u64,
+}
+
+// Entries are found by a hash of the file contents (or of the pixels for textures that
+// did not come from a file) and then compared in full, so the same image under two paths
+// is only kept once and two images sharing a hash are still kept apart. Evicting an
+// entry only drops the cache's own handle; anyone still holding one keeps the texture
+// alive.
+pub struct TextureCache {
+    budget: usize,
+    entries: HashMap<u64, CacheEntry>,
+    hashes: HashMap<u64, Vec<u64>>,
+    paths: HashMap<PathBuf, u64>,
+    next_key: u64,
+    clock: u64,
+    stats: CacheStats,
+}
+
+impl Default for TextureCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_TEXTURE_BUDGET)
+    }
+}
+
+fn hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+
+    hasher.finish()
+}
+
+fn hash_canvas(canvas: &Canvas) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    canvas.get_size().hash(&mut hasher);
+    canvas.as_bytes().hash(&mut hasher);
+
+    hasher.finish()
+}
+
+impl TextureCache {
+    pub fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            entries: HashMap::new(),
+            hashes: HashMap::new(),
+            paths: HashMap::new(),
+            next_key: 0,
+            clock: 0,
+            stats: CacheStats::default(),
+        }
+    }
+
+    pub fn get_stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    pub fn get_budget(&self) -> usize {
+        self.budget
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn touch(&mut self, key: u64) -> Option<TextureHandle> {
+        self.clock += 1;
+
+        let entry = self.entries.get_mut(&key)?;
+        entry.last_used = self.clock;
+        self.stats.hits += 1;
+
+        Some(Arc::clone(&entry.texture))
+    }
+
+    fn find(&self, hash: u64, same: impl Fn(&CacheEntry) -> bool) -> Option<u64> {
+        self.hashes
+            .get(&hash)?
+            .iter()
+            .copied()
+            .find(|key| same(&self.entries[key]))
+    }
+
+    // Least recently used first, but never the entry that was just added, so a single
+    // texture over budget is still served.
+    fn evict(&mut self, keep: u64) {
+        while self.stats.bytes > self.budget {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .filter(|(key, _)| **key != keep)
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| *key)
+            else {
+                break;
+            };
+
+            let entry = self.entries.remove(&oldest).unwrap();
+            self.paths.retain(|_, key| *key != oldest);
+
+            if let Some(keys) = self.hashes.get_mut(&entry.hash) {
+                keys.retain(|key| *key != oldest);
+
+                if keys.is_empty() {
+                    self.hashes.remove(&entry.hash);
+                }
+            }
+
+            self.stats.bytes -= entry.bytes;
+            self.stats.evictions += 1;
+
+            debug!(bytes = entry.bytes, budget = self.budget; "texture evicted");
+        }
+    }
+
+    fn store(
+        &mut self,
+        hash: u64,
+        texture: MipChain,
+        origin: Option<PathBuf>,
+    ) -> (u64, TextureHandle) {
+        let bytes = texture.get_byte_count();
+        let texture = Arc::new(texture);
+        let key = self.next_key;
+
+        self.next_key += 1;
+        self.clock += 1;
+        self.stats.misses += 1;
+        self.stats.bytes += bytes;
+        self.entries.insert(
+            key,
+            CacheEntry {
+                texture: Arc::clone(&texture),
+                hash,
+                origin,
+                bytes,
+                last_used: self.clock,
+            },
+        );
+        self.hashes.entry(hash).or_default().push(key);
+
+        self.evict(key);
+
+        (key, texture)
+    }
+
+    pub fn load(&mut self, path: &Path) -> Result<TextureHandle, TextureError> {
+        if let Some(texture) = self.paths.get(path).copied().and_then(|k| self.touch(k)) {
+            return Ok(texture);
+        }
+
+        let bytes = std::fs::read(path).map_err(|err| TextureError::Io(path.to_path_buf(), err))?;
+        let hash = hash_bytes(&bytes);
+
+        // A file that changed or went away since it was cached no longer counts as a match.
+        let same = |entry: &CacheEntry| {
+            entry
+                .origin
+                .as_ref()
+                .is_some_and(|origin| std::fs::read(origin).is_ok_and(|b| b == bytes))
+        };
+
+        if let Some(key) = self.find(hash, same) {
+            self.paths.insert(path.to_path_buf(), key);
+
+            return Ok(self.touch(key).unwrap());
+        }
+
+        let (format, texture) = decode_texture(&bytes)
+            .map_err(|msg| TextureError::Malformed(path.to_path_buf(), msg))?;
+        let (w, h) = texture.get_size();
+
+        info!(
+            path:% = path.display(),
+            format:? = format,
+            width = w,
+            height = h,
+            alpha = texture.has_alpha();
+            "texture loaded"
+        );
+
+        let (key, texture) = self.store(hash, texture.into_mip_chain(), Some(path.to_path_buf()));
+
+        self.paths.insert(path.to_path_buf(), key);
+
+        Ok(texture)
+    }
+
+    // For textures that arrive already decoded, such as the ones embedded in glTF files.
+    pub fn insert(&mut self, canvas: Canvas) -> TextureHandle {
+        let same = |entry: &CacheEntry| match (&entry.origin, entry.texture.get_base()) {
+            (None, Some(base)) => {
+                base.get_size() == canvas.get_size() && base.as_bytes() == canvas.as_bytes()
+            }
+            _ => false,
+        };
+
+        let hash = hash_canvas(&canvas);
+
+        match self.find(hash, same) {
+            Some(key) => self.touch(key).unwrap(),
+            None => self.store(hash, MipChain::new(canvas), None).1,
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use image::{DynamicImage, ImageFormat};
+use log::info;
+
+use crate::color::encode_srgb;
+use crate::image_canvas::Canvas;
+use crate::read_tga::decode_tga;
+use crate::texture_sampler::MipChain;
+
+#[derive(Debug)]
+pub enum TextureError {
+    Io(PathBuf, io::Error),
+    Malformed(PathBuf, String),
+}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextureError::Io(path, err) => write!(f, "could not read {}: {}", path.display(), err),
+            TextureError::Malformed(path, msg) => write!(f, "{}: {}", path.display(), msg),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextureFormat {
+    Tga,
+    Png,
+    Jpeg,
+    Bmp,
+    Hdr,
+}
+
+impl TextureFormat {
+    // TGA has no magic number, so it is what is left when nothing else matches.
+    pub fn from_signature(bytes: &[u8]) -> Self {
+        if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+            TextureFormat::Png
+        } else if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+            TextureFormat::Jpeg
+        } else if bytes.starts_with(b"BM") {
+            TextureFormat::Bmp
+        } else if bytes.starts_with(b"#?RADIANCE") || bytes.starts_with(b"#?RGBE") {
+            TextureFormat::Hdr
+        } else {
+            TextureFormat::Tga
+        }
+    }
+}
+
+// Linear radiance as stored in the file, bottom row first like a canvas.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HdrImage {
+    pub width: usize,
+    pub height: usize,
+    pub texels: Vec<[f32; 3]>,
+}
+
+impl HdrImage {
+    pub fn get_size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    pub fn get_texel(&self, x: usize, y: usize) -> Option<[f32; 3]> {
+        match x < self.width && y < self.height {
+            true => Some(self.texels[x + y * self.width]),
+            false => None,
+        }
+    }
+
+    // Encoded to sRGB like any other 8-bit texture; values above one are clipped.
+    pub fn to_canvas(&self) -> Canvas {
+        let mut canvas = Canvas::new(self.width, self.height, 0);
+
+        for (i, texel) in self.texels.iter().enumerate() {
+            let rgb = encode_srgb(texel.map(|c| c as f64));
+
+            canvas
+                .set_pixel_rgb((i % self.width) as i32, (i / self.width) as i32, rgb)
+                .unwrap();
+        }
+
+        canvas
+    }
+}
+
+// Alpha, when the file has it, is kept as a gray canvas the same size as the color.
+#[derive(Clone)]
+pub enum Texture {
+    Ldr(Canvas, Option<Canvas>),
+    Hdr(HdrImage),
+}
+
+impl Texture {
+    pub fn get_size(&self) -> (usize, usize) {
+        match self {
+            Texture::Ldr(canvas, _) => canvas.get_size(),
+            Texture::Hdr(image) => image.get_size(),
+        }
+    }
+
+    pub fn has_alpha(&self) -> bool {
+        matches!(self, Texture::Ldr(_, Some(_)))
+    }
+
+    pub fn into_canvas(self) -> Canvas {
+        match self {
+            Texture::Ldr(canvas, _) => canvas,
+            Texture::Hdr(image) => image.to_canvas(),
+        }
+    }
+
+    pub fn into_mip_chain(self) -> MipChain {
+        match self {
+            Texture::Ldr(canvas, Some(alpha)) => MipChain::with_alpha(canvas, alpha),
+            Texture::Ldr(canvas, None) => MipChain::new(canvas),
+            Texture::Hdr(image) => MipChain::new_hdr(image),
+        }
+    }
+}
+
+// Decoded images come top row first; canvases are bottom-up.
+fn image_to_canvas(image: &DynamicImage) -> Canvas {
+    let rgb = image.to_rgb8();
+    let (w, h) = (rgb.width() as usize, rgb.height() as usize);
+    let mut canvas = Canvas::new(w, h, 0);
+
+    for (x, y, pixel) in rgb.enumerate_pixels() {
+        canvas
+            .set_pixel_rgb(x as i32, (h - 1 - y as usize) as i32, pixel.0)
+            .unwrap();
+    }
+
+    canvas
+}
+
+fn image_alpha_to_canvas(image: &DynamicImage) -> Option<Canvas> {
+    if !image.color().has_alpha() {
+        return None;
+    }
+
+    let rgba = image.to_rgba8();
+    let h = rgba.height() as usize;
+    let mut canvas = Canvas::new(rgba.width() as usize, h, 0);
+
+    for (x, y, pixel) in rgba.enumerate_pixels() {
+        canvas
+            .set_pixel(x as i32, (h - 1 - y as usize) as i32, pixel.0[3])
+            .unwrap();
+    }
+
+    Some(canvas)
+}
+
+fn image_to_hdr(image: &DynamicImage) -> HdrImage {
+    let rgb = image.to_rgb32f();
+    let (w, h) = (rgb.width() as usize, rgb.height() as usize);
+
+    let texels = rgb
+        .rows()
+        .rev()
+        .flat_map(|row| row.map(|pixel| pixel.0))
+        .collect::<Vec<[f32; 3]>>();
+
+    HdrImage {
+        width: w,
+        height: h,
+        texels,
+    }
+}
+
+pub fn decode_texture(bytes: &[u8]) -> Result<(TextureFormat, Texture), String> {
+    let format = TextureFormat::from_signature(bytes);
+
+    let texture = match format {
+        TextureFormat::Tga => {
+            let image = decode_tga(bytes)?;
+
+            Texture::Ldr(image.to_canvas(), image.alpha_to_canvas())
+        }
+        _ => {
+            let image_format = match format {
+                TextureFormat::Png => ImageFormat::Png,
+                TextureFormat::Jpeg => ImageFormat::Jpeg,
+                TextureFormat::Bmp => ImageFormat::Bmp,
+                _ => ImageFormat::Hdr,
+            };
+
+            let image = image::load_from_memory_with_format(bytes, image_format)
+                .map_err(|err| err.to_string())?;
+
+            match format {
+                TextureFormat::Hdr => Texture::Hdr(image_to_hdr(&image)),
+                _ => Texture::Ldr(image_to_canvas(&image), image_alpha_to_canvas(&image)),
+            }
+        }
+    };
+
+    Ok((format, texture))
+}
+
+pub fn load_texture(path: PathBuf) -> Result<Texture, TextureError> {
+    let bytes = std::fs::read(&path).map_err(|err| TextureError::Io(path.clone(), err))?;
+
+    let (format, texture) =
+        decode_texture(&bytes).map_err(|msg| TextureError::Malformed(path.clone(), msg))?;
+    let (w, h) = texture.get_size();
+
+    info!(
+        path:% = path.display(),
+        format:? = format,
+        width = w,
+        height = h,
+        alpha = texture.has_alpha();
+        "texture loaded"
+    );
+
+    Ok(texture)
+}
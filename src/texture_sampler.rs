@@ -0,0 +1,380 @@
+use crate::color::{decode_srgb, encode_srgb, srgb_to_linear, LinearRgb};
+use crate::image_canvas::{Canvas, Rgb};
+use crate::linear_algebra::{Point2, Point3};
+use crate::texture_loader::HdrImage;
+
+pub const DEFAULT_MAX_ANISOTROPY: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextureFilter {
+    // Base level only.
+    Nearest,
+    Bilinear,
+    // Blends the two mip levels around the footprint.
+    Trilinear,
+    // Several trilinear taps along the footprint's long axis.
+    Anisotropic,
+}
+
+impl TextureFilter {
+    pub const ALL: [TextureFilter; 4] = [
+        TextureFilter::Nearest,
+        TextureFilter::Bilinear,
+        TextureFilter::Trilinear,
+        TextureFilter::Anisotropic,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|f| f.get_name() == name)
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            TextureFilter::Nearest => "nearest",
+            TextureFilter::Bilinear => "bilinear",
+            TextureFilter::Trilinear => "trilinear",
+            TextureFilter::Anisotropic => "anisotropic",
+        }
+    }
+}
+
+// A positive bias picks smaller mips, trading sharpness for less shimmer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SamplerSettings {
+    pub filter: TextureFilter,
+    pub lod_bias: f64,
+    pub max_anisotropy: usize,
+}
+
+impl Default for SamplerSettings {
+    fn default() -> Self {
+        Self {
+            filter: TextureFilter::Trilinear,
+            lod_bias: 0.0,
+            max_anisotropy: DEFAULT_MAX_ANISOTROPY,
+        }
+    }
+}
+
+// How far the UVs move per pixel step in x and in y.
+#[derive(Clone, Copy, Debug)]
+pub struct UvDerivatives {
+    pub ddx: Point2<f64>,
+    pub ddy: Point2<f64>,
+}
+
+// Level 0 is the texture itself, every further level halves both sides down to 1x1.
+// Color is stored as sRGB and filtered in linear light, or kept as linear floats for HDR
+// images so values above one survive; alpha, if any, is a linear gray chain of its own.
+#[derive(Clone)]
+pub struct MipChain {
+    levels: MipLevels,
+    srgb: bool,
+    alpha: Option<Box<MipChain>>,
+}
+
+#[derive(Clone)]
+enum MipLevels {
+    Canvas(Vec<Canvas>),
+    Float(Vec<HdrImage>),
+}
+
+impl MipChain {
+    pub fn new(base: Canvas) -> Self {
+        Self::build(base, true)
+    }
+
+    // For data that is not a color, such as alpha.
+    pub fn new_linear(base: Canvas) -> Self {
+        Self::build(base, false)
+    }
+
+    fn build(base: Canvas, srgb: bool) -> Self {
+        let mut levels = vec![base];
+
+        loop {
+            let previous = levels.last().unwrap();
+            let (w, h) = previous.get_size();
+
+            if w <= 1 && h <= 1 {
+                break;
+            }
+
+            let (nw, nh) = ((w / 2).max(1), (h / 2).max(1));
+            let mut level = Canvas::new(nw, nh, 0);
+
+            // Box filter over each 2x2 block; odd edges reuse their last row or column.
+            for y in 0..nh {
+                for x in 0..nw {
+                    let mut sum = [0.0; 3];
+
+                    for (sx, sy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                        let texel = previous
+                            .get_pixel_rgb((2 * x + sx).min(w - 1), (2 * y + sy).min(h - 1));
+                        let texel = match srgb {
+                            true => decode_srgb(texel),
+                            false => texel.map(|c| c as f64 / 255.0),
+                        };
+
+                        for c in 0..3 {
+                            sum[c] += texel[c] / 4.0;
+                        }
+                    }
+
+                    let texel = match srgb {
+                        true => encode_srgb(sum),
+                        false => sum.map(|c| (c * 255.0).round() as u8),
+                    };
+
+                    level.set_pixel_rgb(x as i32, y as i32, texel).unwrap();
+                }
+            }
+
+            levels.push(level);
+        }
+
+        Self {
+            levels: MipLevels::Canvas(levels),
+            srgb,
+            alpha: None,
+        }
+    }
+
+    // Same box filter as `build`, straight on the linear values.
+    pub fn new_hdr(base: HdrImage) -> Self {
+        let mut levels = vec![base];
+
+        loop {
+            let previous = levels.last().unwrap();
+            let (w, h) = previous.get_size();
+
+            if w <= 1 && h <= 1 {
+                break;
+            }
+
+            let (nw, nh) = ((w / 2).max(1), (h / 2).max(1));
+            let texels = (0..nw * nh)
+                .map(|i| {
+                    let (x, y) = (i % nw, i / nw);
+                    let mut sum = [0.0f32; 3];
+
+                    for (sx, sy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                        let texel =
+                            previous.texels[(2 * x + sx).min(w - 1) + (2 * y + sy).min(h - 1) * w];
+
+                        for (s, c) in sum.iter_mut().zip(texel) {
+                            *s += c / 4.0;
+                        }
+                    }
+
+                    sum
+                })
+                .collect();
+
+            levels.push(HdrImage {
+                width: nw,
+                height: nh,
+                texels,
+            });
+        }
+
+        Self {
+            levels: MipLevels::Float(levels),
+            srgb: true,
+            alpha: None,
+        }
+    }
+
+    pub fn with_alpha(base: Canvas, alpha: Canvas) -> Self {
+        Self {
+            alpha: Some(Box::new(MipChain::new_linear(alpha))),
+            ..MipChain::new(base)
+        }
+    }
+
+    // The 8-bit level 0, as given, for chains built from a canvas.
+    pub fn get_base(&self) -> Option<&Canvas> {
+        match &self.levels {
+            MipLevels::Canvas(levels) => levels.first(),
+            MipLevels::Float(_) => None,
+        }
+    }
+
+    pub fn get_level_count(&self) -> usize {
+        match &self.levels {
+            MipLevels::Canvas(levels) => levels.len(),
+            MipLevels::Float(levels) => levels.len(),
+        }
+    }
+
+    pub fn get_level_size(&self, level: usize) -> (usize, usize) {
+        match &self.levels {
+            MipLevels::Canvas(levels) => levels[level].get_size(),
+            MipLevels::Float(levels) => levels[level].get_size(),
+        }
+    }
+
+    // Memory held by every level, alpha included.
+    pub fn get_byte_count(&self) -> usize {
+        let color = match &self.levels {
+            MipLevels::Canvas(levels) => levels.iter().map(|l| l.as_bytes().len()).sum(),
+            MipLevels::Float(levels) => levels
+                .iter()
+                .map(|l| l.texels.len() * std::mem::size_of::<[f32; 3]>())
+                .sum::<usize>(),
+        };
+
+        color + self.get_alpha().map_or(0, MipChain::get_byte_count)
+    }
+
+    pub fn get_alpha(&self) -> Option<&MipChain> {
+        self.alpha.as_deref()
+    }
+
+    pub fn has_alpha(&self) -> bool {
+        self.alpha.is_some()
+    }
+
+    pub fn get_size(&self) -> (usize, usize) {
+        self.get_level_size(0)
+    }
+
+    // Coordinates wrap like a repeating sampler. Values come out linear, from zero to one
+    // unless the chain holds floats.
+    fn texel(&self, level: usize, x: i64, y: i64) -> Point3<f64> {
+        let (w, h) = self.get_level_size(level);
+        let (x, y) = (
+            x.rem_euclid(w as i64) as usize,
+            y.rem_euclid(h as i64) as usize,
+        );
+        let [r, g, b] = match &self.levels {
+            MipLevels::Float(levels) => levels[level].texels[x + y * w].map(|c| c as f64),
+            MipLevels::Canvas(levels) => {
+                let texel = levels[level].get_pixel_rgb(x, y);
+
+                match self.srgb {
+                    true => texel.map(srgb_to_linear),
+                    false => texel.map(|c| c as f64 / 255.0),
+                }
+            }
+        };
+
+        Point3(r, g, b)
+    }
+
+    fn nearest(&self, level: usize, uv: Point2<f64>) -> Point3<f64> {
+        let (w, h) = self.get_level_size(level);
+        let Point2(u, v) = uv;
+
+        self.texel(
+            level,
+            (u * w as f64).floor() as i64,
+            (v * h as f64).floor() as i64,
+        )
+    }
+
+    fn bilinear(&self, level: usize, uv: Point2<f64>) -> Point3<f64> {
+        let (w, h) = self.get_level_size(level);
+
+        // Texel centers sit at half-integer coordinates.
+        let x = uv.0 * w as f64 - 0.5;
+        let y = uv.1 * h as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = self.texel(level, x0, y0) * (1.0 - fx) + self.texel(level, x0 + 1, y0) * fx;
+        let bottom =
+            self.texel(level, x0, y0 + 1) * (1.0 - fx) + self.texel(level, x0 + 1, y0 + 1) * fx;
+
+        top * (1.0 - fy) + bottom * fy
+    }
+
+    fn trilinear(&self, uv: Point2<f64>, lod: f64) -> Point3<f64> {
+        let last = self.get_level_count() - 1;
+        let lod = lod.clamp(0.0, last as f64);
+        let fine = lod.floor() as usize;
+        let coarse = (fine + 1).min(last);
+        let t = lod - fine as f64;
+
+        match fine == coarse || t == 0.0 {
+            true => self.bilinear(fine, uv),
+            false => self.bilinear(fine, uv) * (1.0 - t) + self.bilinear(coarse, uv) * t,
+        }
+    }
+
+    pub fn sample_linear(
+        &self,
+        uv: Point2<f64>,
+        derivatives: UvDerivatives,
+        settings: &SamplerSettings,
+    ) -> LinearRgb {
+        let (w, h) = self.get_size();
+
+        // Footprint of one pixel in base level texels.
+        let to_texels = |d: Point2<f64>| Point2(d.0 * w as f64, d.1 * h as f64);
+        let (dx, dy) = (to_texels(derivatives.ddx), to_texels(derivatives.ddy));
+        let (length_x, length_y) = (dx.0.hypot(dx.1), dy.0.hypot(dy.1));
+
+        let color = match settings.filter {
+            TextureFilter::Nearest => self.nearest(0, uv),
+            TextureFilter::Bilinear => self.bilinear(0, uv),
+            TextureFilter::Trilinear => {
+                let footprint = length_x.max(length_y).max(f64::MIN_POSITIVE);
+
+                self.trilinear(uv, footprint.log2() + settings.lod_bias)
+            }
+            TextureFilter::Anisotropic => {
+                let (major, minor, axis) = match length_x >= length_y {
+                    true => (length_x, length_y, derivatives.ddx),
+                    false => (length_y, length_x, derivatives.ddy),
+                };
+
+                let ratio = major / minor.max(f64::MIN_POSITIVE);
+                let taps = (ratio.ceil() as usize).clamp(1, settings.max_anisotropy.max(1));
+                let lod = (major / taps as f64).max(f64::MIN_POSITIVE).log2() + settings.lod_bias;
+
+                // Taps are spread evenly over the long axis, centered on the pixel.
+                let sum = (0..taps)
+                    .map(|i| {
+                        let offset = (i as f64 + 0.5) / taps as f64 - 0.5;
+
+                        self.trilinear(Point2(uv.0 + axis.0 * offset, uv.1 + axis.1 * offset), lod)
+                    })
+                    .fold(Point3(0.0, 0.0, 0.0), |sum, color| sum + color);
+
+                sum * (1.0 / taps as f64)
+            }
+        };
+
+        [color.0, color.1, color.2]
+    }
+
+    // Back in the texture's own encoding; HDR chains are encoded to sRGB and clipped.
+    pub fn sample(
+        &self,
+        uv: Point2<f64>,
+        derivatives: UvDerivatives,
+        settings: &SamplerSettings,
+    ) -> Rgb {
+        let color = self.sample_linear(uv, derivatives, settings);
+
+        match self.srgb {
+            true => encode_srgb(color),
+            false => color.map(|c| (c * 255.0).round().clamp(0.0, 255.0) as u8),
+        }
+    }
+
+    // Between zero and one, and one for textures without alpha.
+    pub fn sample_alpha(
+        &self,
+        uv: Point2<f64>,
+        derivatives: UvDerivatives,
+        settings: &SamplerSettings,
+    ) -> f64 {
+        match &self.alpha {
+            Some(alpha) => alpha.sample_linear(uv, derivatives, settings)[0],
+            None => 1.0,
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use std::fmt::Write;
+use std::path::Path;
+
+// One `newmtl` block. The common scalar and color statements are typed; texture maps
+// and anything else are kept verbatim, options included, so they write back unchanged.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MtlMaterial {
+    pub name: String,
+    pub ambient: Option<[f64; 3]>,
+    pub diffuse: Option<[f64; 3]>,
+    pub specular: Option<[f64; 3]>,
+    pub emissive: Option<[f64; 3]>,
+    pub shininess: Option<f64>,
+    pub optical_density: Option<f64>,
+    pub dissolve: Option<f64>,
+    pub illumination: Option<u32>,
+    pub statements: Vec<(String, String)>,
+}
+
+impl MtlMaterial {
+    pub fn named(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Self::default()
+        }
+    }
+
+    // The file name is the last argument, after any `-o u v w` style options.
+    pub fn get_map_file(&self, keyword: &str) -> Option<&str> {
+        self.statements
+            .iter()
+            .rev()
+            .find(|(k, _)| k == keyword)
+            .and_then(|(_, args)| args.split_whitespace().last())
+    }
+
+    // Rewrites the file of every texture statement, keeping its options.
+    pub fn with_map_files(&self, rewrite: impl Fn(&str) -> String) -> Self {
+        let mut material = self.clone();
+
+        for (_, args) in material
+            .statements
+            .iter_mut()
+            .filter(|(k, _)| is_map_statement(k))
+        {
+            *args = match args.rsplit_once(' ') {
+                Some((options, file)) => format!("{} {}", options, rewrite(file)),
+                None if args.is_empty() => continue,
+                None => rewrite(args),
+            };
+        }
+
+        material
+    }
+}
+
+// Statements whose last argument names a texture file.
+fn is_map_statement(keyword: &str) -> bool {
+    keyword.starts_with("map_") || matches!(keyword, "bump" | "disp" | "decal" | "refl")
+}
+
+// A single number stands for a gray color.
+fn parse_color(args: &[&str]) -> Option<[f64; 3]> {
+    let values = args
+        .iter()
+        .map(|a| a.parse::<f64>().ok())
+        .collect::<Option<Vec<f64>>>()?;
+
+    match values.as_slice() {
+        [g] => Some([*g; 3]),
+        [r, g, b] => Some([*r, *g, *b]),
+        _ => None,
+    }
+}
+
+// Options such as `d -halo 0.5` precede the value.
+fn parse_scalar(args: &[&str]) -> Option<f64> {
+    args.last()?.parse::<f64>().ok()
+}
+
+pub fn parse_mtl(text: &str) -> Vec<MtlMaterial> {
+    let mut materials: Vec<MtlMaterial> = vec![];
+
+    for line in text.lines() {
+        let tokens = line.split_whitespace().collect::<Vec<&str>>();
+
+        let Some((&keyword, args)) = tokens.split_first() else {
+            continue;
+        };
+
+        if keyword.starts_with('#') {
+            continue;
+        }
+
+        if keyword == "newmtl" {
+            materials.push(MtlMaterial::named(&args.join(" ")));
+            continue;
+        }
+
+        let Some(material) = materials.last_mut() else {
+            continue;
+        };
+
+        let typed = match keyword {
+            "Ka" => parse_color(args).map(|c| material.ambient = Some(c)),
+            "Kd" => parse_color(args).map(|c| material.diffuse = Some(c)),
+            "Ks" => parse_color(args).map(|c| material.specular = Some(c)),
+            "Ke" => parse_color(args).map(|c| material.emissive = Some(c)),
+            "Ns" => parse_scalar(args).map(|s| material.shininess = Some(s)),
+            "Ni" => parse_scalar(args).map(|s| material.optical_density = Some(s)),
+            "d" => parse_scalar(args).map(|s| material.dissolve = Some(s)),
+            "Tr" => parse_scalar(args).map(|s| material.dissolve = Some(1.0 - s)),
+            "illum" => args
+                .first()
+                .and_then(|a| a.parse::<u32>().ok())
+                .map(|i| material.illumination = Some(i)),
+            _ => None,
+        };
+
+        // Malformed typed statements are kept as text rather than dropped.
+        if typed.is_none() {
+            material
+                .statements
+                .push((keyword.to_string(), args.join(" ")));
+        }
+    }
+
+    materials
+}
+
+// Texture files are named relative to the library, so they are joined onto its directory
+// to stay readable from wherever the model is used.
+pub fn load_mtl(path: &Path) -> std::io::Result<Vec<MtlMaterial>> {
+    let directory = path.parent().unwrap_or(Path::new(""));
+    let text = std::fs::read_to_string(path)?;
+
+    Ok(parse_mtl(&text)
+        .iter()
+        .map(|m| m.with_map_files(|file| directory.join(file).to_string_lossy().into_owned()))
+        .collect())
+}
+
+pub fn write_mtl_string(materials: &[MtlMaterial]) -> String {
+    let mut out = String::new();
+
+    for material in materials {
+        let color = |out: &mut String, keyword: &str, c: Option<[f64; 3]>| {
+            if let Some([r, g, b]) = c {
+                writeln!(out, "{} {} {} {}", keyword, r, g, b).unwrap();
+            }
+        };
+
+        writeln!(out, "newmtl {}", material.name).unwrap();
+        color(&mut out, "Ka", material.ambient);
+        color(&mut out, "Kd", material.diffuse);
+        color(&mut out, "Ks", material.specular);
+        color(&mut out, "Ke", material.emissive);
+
+        for (keyword, value) in [
+            ("Ns", material.shininess),
+            ("Ni", material.optical_density),
+            ("d", material.dissolve),
+        ] {
+            if let Some(value) = value {
+                writeln!(out, "{} {}", keyword, value).unwrap();
+            }
+        }
+
+        if let Some(illumination) = material.illumination {
+            writeln!(out, "illum {}", illumination).unwrap();
+        }
+
+        for (keyword, args) in &material.statements {
+            writeln!(out, "{} {}", keyword, args).unwrap();
+        }
+
+        out.push('\n');
+    }
+
+    out
+}
@@ -1,11 +1,17 @@
-use crate::linear_algebra::{Point3, Vec3Unsigned};
+use crate::free_form::{
+    tessellate_curve, tessellate_surface, FreeFormCurve, FreeFormSurface, FreeFormType,
+};
+use crate::image_canvas::Rgb;
+use crate::linear_algebra::{Matrix3, Point3, Vec3Unsigned};
+use crate::wavefront_mtl::{load_mtl, MtlMaterial};
+use log::{info, warn};
 use std::borrow::BorrowMut;
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::SplitWhitespace;
 
-
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct TextureUV {
     u: f64,
     v: Option<f64>,
@@ -20,9 +26,13 @@ impl TextureUV {
     pub fn unravel_uv_impl(&self) -> (f64, f64) {
         (self.u, self.v.unwrap())
     }
+
+    pub fn unravel_uvw(&self) -> (f64, Option<f64>, Option<f64>) {
+        (self.u, self.v, self.w)
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Vertex {
     pub xyz: Point3<f64>,
     pub w: Option<f64>,
@@ -50,6 +60,7 @@ impl std::ops::Sub for Vertex {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct SpaceVertex {
     u: f64,
     v: Option<f64>,
@@ -60,15 +71,31 @@ impl SpaceVertex {
     pub fn new(u: f64, v: Option<f64>, w: Option<f64>) -> Self {
         Self { u, v, w }
     }
+
+    pub fn unravel_uvw(&self) -> (f64, Option<f64>, Option<f64>) {
+        (self.u, self.v, self.w)
+    }
+}
+
+// The `o`, `g`, `usemtl` and `s` state in effect when an element was read. Names are
+// indices into the object's tables; `None` is the unnamed default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ElementTags {
+    pub object: Option<usize>,
+    pub group: Option<usize>,
+    pub material: Option<usize>,
+    pub smoothing: Option<u32>,
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct FaceElement {
     vertex_triplet: FaceTriplet,
     texture_triplet: FaceTriplet,
     normal_triplet: FaceTriplet,
+    tags: ElementTags,
 }
 
-type FaceTriplet = (Option<usize>, Option<usize>, Option<usize>);
+pub type FaceTriplet = (Option<usize>, Option<usize>, Option<usize>);
 
 impl FaceElement {
     pub fn new(
@@ -80,28 +107,144 @@ impl FaceElement {
             vertex_triplet,
             texture_triplet,
             normal_triplet,
+            tags: ElementTags::default(),
         }
     }
+
+    pub fn get_triplets(&self) -> (FaceTriplet, FaceTriplet, FaceTriplet) {
+        (
+            self.vertex_triplet,
+            self.texture_triplet,
+            self.normal_triplet,
+        )
+    }
+
+    pub fn get_tags(&self) -> ElementTags {
+        self.tags
+    }
+
+    pub fn get_vertex_indices(&self) -> Option<Vec3Unsigned<usize>> {
+        complete_triplet(self.vertex_triplet)
+    }
+
+    // `None` unless every corner of the face has a `vt`.
+    pub fn get_texture_indices(&self) -> Option<Vec3Unsigned<usize>> {
+        complete_triplet(self.texture_triplet)
+    }
+
+    // `None` unless every corner of the face has a `vn`.
+    pub fn get_normal_indices(&self) -> Option<Vec3Unsigned<usize>> {
+        complete_triplet(self.normal_triplet)
+    }
+}
+
+fn complete_triplet(triplet: FaceTriplet) -> Option<Vec3Unsigned<usize>> {
+    match triplet {
+        (Some(a), Some(b), Some(c)) => Some(Vec3Unsigned::new(a, b, c)),
+        _ => None,
+    }
+}
+
+// A polyline for `l`, a set of points for `p`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrimitiveElement {
+    pub vertices: Vec<usize>,
+    pub tags: ElementTags,
 }
 
+enum FreeFormElement {
+    Curve(FreeFormCurve),
+    Surface(FreeFormSurface),
+}
+
+// What `g`, `o`, `usemtl`, `s`, `mtllib`, `cstype` and `deg` have set so far while reading
+// a file, plus the free-form element waiting for its `end`.
+#[derive(Default)]
+struct ParseState {
+    tags: ElementTags,
+    objects: Vec<String>,
+    groups: Vec<String>,
+    materials: Vec<MtlMaterial>,
+    // Materials a `usemtl` named before any library defined them.
+    undefined_materials: Vec<usize>,
+    free_form_type: Option<FreeFormType>,
+    degree: (usize, usize),
+    pending: Option<FreeFormElement>,
+    curves: Vec<FreeFormCurve>,
+    surfaces: Vec<FreeFormSurface>,
+    // Trimming statements by keyword, counted so each is only warned about once.
+    skipped: BTreeMap<String, usize>,
+}
+
+// Negative indices count back from the last element read so far. Either way the index
+// has to name an element that was already read.
+fn resolve_index(token: &str, count: usize) -> Option<usize> {
+    let index = token.parse::<i64>().ok()?;
+
+    let resolved = match index < 0 {
+        true => count as i64 + 1 + index,
+        false => index,
+    };
+
+    (1..=count as i64)
+        .contains(&resolved)
+        .then_some(resolved as usize)
+}
+
+fn intern(names: &mut Vec<String>, name: &str) -> usize {
+    match names.iter().position(|n| n == name) {
+        Some(i) => i,
+        None => {
+            names.push(name.to_string());
+            names.len() - 1
+        }
+    }
+}
+
+fn intern_material(materials: &mut Vec<MtlMaterial>, material: &MtlMaterial) -> usize {
+    match materials.iter().position(|m| m.name == material.name) {
+        Some(i) => i,
+        None => {
+            materials.push(material.clone());
+            materials.len() - 1
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct WavefronObject {
     v: Vec<Vertex>,
     vt: Vec<TextureUV>,
     vn: Vec<Point3<f64>>,
     vp: Vec<SpaceVertex>,
     f: Vec<FaceElement>,
-    l: Vec<Vec<usize>>,
+    l: Vec<PrimitiveElement>,
+    p: Vec<PrimitiveElement>,
+    // Per-vertex colors, parallel to `v` when present.
+    vc: Vec<Rgb>,
+    objects: Vec<String>,
+    groups: Vec<String>,
+    materials: Vec<MtlMaterial>,
+    curves: Vec<FreeFormCurve>,
+    surfaces: Vec<FreeFormSurface>,
 }
 
 impl WavefronObject {
     pub fn new(path: PathBuf) -> Self {
-        let read_str = Self::read_to_string(path.clone());
+        // Long free-form statements are split with a trailing backslash.
+        let read_str = Self::read_to_string(path.clone())
+            .replace("\\\r\n", " ")
+            .replace("\\\n", " ");
         let mut v: Vec<Vertex> = vec![];
         let mut vt: Vec<TextureUV> = vec![];
         let mut vn: Vec<Point3<f64>> = vec![];
         let mut vp: Vec<SpaceVertex> = vec![];
         let mut f: Vec<FaceElement> = vec![];
-        let mut l: Vec<Vec<usize>> = vec![];
+        let mut l: Vec<PrimitiveElement> = vec![];
+        let mut p: Vec<PrimitiveElement> = vec![];
+        let mut vc: Vec<Rgb> = vec![];
+        let mut state = ParseState::default();
+        let directory = path.parent().unwrap_or(Path::new(""));
 
         let mut lines = read_str.lines();
 
@@ -114,10 +257,41 @@ impl WavefronObject {
                 &mut vp,
                 &mut f,
                 &mut l,
+                &mut p,
+                &mut vc,
+                &mut state,
+                directory,
             )
         });
 
-        println!("Wavefront object {} loaded", path.display());
+        if !vc.is_empty() {
+            vc.resize(v.len(), [255; 3]);
+        }
+
+        // Libraries can come anywhere in the file, so names are only checked at the end.
+        for index in &state.undefined_materials {
+            warn!(material = state.materials[*index].name.as_str(); "usemtl names an undefined material");
+        }
+
+        for (statement, count) in &state.skipped {
+            warn!(statement = statement.as_str(), count = count; "ignoring unsupported trimming statements");
+        }
+
+        // Only trimming curves refer to them, so they are kept for writing back but not used.
+        if !vp.is_empty() {
+            warn!(count = vp.len(); "ignoring parameter space vertices");
+        }
+
+        info!(
+            path:% = path.display(),
+            vertices = v.len(),
+            faces = f.len(),
+            groups = state.groups.len(),
+            materials = state.materials.len(),
+            curves = state.curves.len(),
+            surfaces = state.surfaces.len();
+            "wavefront object loaded"
+        );
         Self {
             v,
             vt,
@@ -125,9 +299,93 @@ impl WavefronObject {
             vp,
             f,
             l,
+            p,
+            vc,
+            objects: state.objects,
+            groups: state.groups,
+            materials: state.materials,
+            curves: state.curves,
+            surfaces: state.surfaces,
+        }
+    }
+
+    // For formats that index positions, UVs and normals together. `uvs` and `normals` are
+    // either empty or as long as `positions`, and triangle indices are 0-based.
+    pub fn from_triangles(
+        positions: Vec<Point3<f64>>,
+        uvs: Vec<(f64, f64)>,
+        normals: Vec<Point3<f64>>,
+        triangles: &[[usize; 3]],
+    ) -> Self {
+        let has_uvs = !uvs.is_empty();
+        let has_normals = !normals.is_empty();
+
+        let triplet = |t: &[usize; 3], present: bool| match present {
+            true => (Some(t[0] + 1), Some(t[1] + 1), Some(t[2] + 1)),
+            false => (None, None, None),
+        };
+
+        let f = triangles
+            .iter()
+            .map(|t| {
+                FaceElement::new(
+                    triplet(t, true),
+                    triplet(t, has_uvs),
+                    triplet(t, has_normals),
+                )
+            })
+            .collect();
+
+        Self {
+            v: positions
+                .into_iter()
+                .map(|Point3(x, y, z)| Vertex::new((x, y, z), None))
+                .collect(),
+            vt: uvs
+                .into_iter()
+                .map(|(u, v)| TextureUV::new(u, Some(v), None))
+                .collect(),
+            vn: normals,
+            vp: vec![],
+            f,
+            l: vec![],
+            p: vec![],
+            vc: vec![],
+            objects: vec![],
+            groups: vec![],
+            materials: vec![],
+            curves: vec![],
+            surfaces: vec![],
         }
     }
 
+    pub fn with_colors(mut self, colors: Vec<Rgb>) -> Self {
+        self.vc = colors;
+        self
+    }
+
+    pub fn with_points(mut self, points: Vec<PrimitiveElement>) -> Self {
+        self.p = points;
+        self
+    }
+
+    // Gives every face, line and point the one material.
+    pub fn with_material(mut self, material: MtlMaterial) -> Self {
+        self.materials = vec![material];
+
+        for tags in self.f.iter_mut().map(|face| &mut face.tags).chain(
+            self.l
+                .iter_mut()
+                .chain(self.p.iter_mut())
+                .map(|e| &mut e.tags),
+        ) {
+            tags.material = Some(0);
+        }
+
+        self
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn do_one_round(
         split_ws: &mut SplitWhitespace,
         v: &mut Vec<Vertex>,
@@ -135,28 +393,59 @@ impl WavefronObject {
         vn: &mut Vec<Point3<f64>>,
         vp: &mut Vec<SpaceVertex>,
         f: &mut Vec<FaceElement>,
-        l: &mut Vec<Vec<usize>>,
+        l: &mut Vec<PrimitiveElement>,
+        p: &mut Vec<PrimitiveElement>,
+        vc: &mut Vec<Rgb>,
+        state: &mut ParseState,
+        directory: &Path,
     ) {
-        Self::parse_vertex(split_ws.clone().borrow_mut(), v);
+        Self::parse_vertex(split_ws.clone().borrow_mut(), v, vc);
         Self::parse_uvs(split_ws.clone().borrow_mut(), vt);
         Self::parse_normals(split_ws.clone().borrow_mut(), vn);
         Self::parse_space_vertices(split_ws.clone().borrow_mut(), vp);
-        Self::parse_lines(split_ws.clone().borrow_mut(), l);
-        Self::parse_face_elements(split_ws.clone().borrow_mut(), f)
+        Self::parse_state(split_ws.clone().borrow_mut(), state, directory);
+        Self::parse_free_form(
+            split_ws.clone().borrow_mut(),
+            state,
+            (v.len(), vt.len(), vn.len()),
+        );
+        Self::parse_primitives(split_ws.clone().borrow_mut(), l, p, state.tags, v.len());
+        Self::parse_face_elements(
+            split_ws.clone().borrow_mut(),
+            f,
+            state.tags,
+            (v.len(), vt.len(), vn.len()),
+        )
     }
 
     fn read_to_string(path: PathBuf) -> String {
         fs::read_to_string(path).unwrap()
     }
 
-    fn parse_vertex(split_ws: &mut SplitWhitespace, v: &mut Vec<Vertex>) {
+    fn parse_vertex(split_ws: &mut SplitWhitespace, v: &mut Vec<Vertex>, vc: &mut Vec<Rgb>) {
         match split_ws.next() {
             Some(first) => {
                 if first == "v" {
                     let x_str = split_ws.next().unwrap();
                     let y_str = split_ws.next().unwrap();
                     let z_str = split_ws.next().unwrap();
-                    let w_option = split_ws.next();
+                    let rest = split_ws.collect::<Vec<&str>>();
+
+                    // `v x y z r g b` is the common vertex color extension, with
+                    // channels in [0, 1]; a single extra value is the weight.
+                    let w_option = match rest.len() {
+                        3 => {
+                            let channel = |c: &str| {
+                                (c.parse::<f64>().unwrap().clamp(0.0, 1.0) * 255.0).round() as u8
+                            };
+
+                            vc.resize(v.len(), [255; 3]);
+                            vc.push([channel(rest[0]), channel(rest[1]), channel(rest[2])]);
+
+                            None
+                        }
+                        _ => rest.first().copied(),
+                    };
 
                     let triplet = (
                         x_str.parse::<f64>().unwrap(),
@@ -264,38 +553,214 @@ impl WavefronObject {
         }
     }
 
-    fn parse_face_elements(split_ws: &mut SplitWhitespace, f: &mut Vec<FaceElement>) {
-        fn parse_single_triplet(slash_seperated: &str) -> FaceTriplet {
-            let mut split_slash = slash_seperated.split("/");
+    fn parse_state(split_ws: &mut SplitWhitespace, state: &mut ParseState, directory: &Path) {
+        let Some(first) = split_ws.next() else {
+            return;
+        };
 
-            let vi_option = split_slash.next();
-            let ti_option = split_slash.next();
-            let ni_option = split_slash.next();
+        // Names may contain spaces; a bare statement goes back to the default.
+        let name = split_ws.clone().collect::<Vec<&str>>().join(" ");
+        let named = (!name.is_empty()).then_some(name.as_str());
 
-            let vertex_index = if let Some(vi_str) = vi_option {
-                Some(vi_str.parse::<usize>().unwrap())
-            } else {
-                None
-            };
+        match first {
+            "o" => state.tags.object = named.map(|n| intern(&mut state.objects, n)),
+            "g" => state.tags.group = named.map(|n| intern(&mut state.groups, n)),
+            "usemtl" => {
+                state.tags.material = named.map(|n| {
+                    let count = state.materials.len();
+                    let index = intern_material(&mut state.materials, &MtlMaterial::named(n));
 
-            let texture_index = if let Some(ti_str) = ti_option {
-                Some(ti_str.parse::<usize>().unwrap())
-            } else {
-                None
-            };
+                    if index == count {
+                        state.undefined_materials.push(index);
+                    }
+
+                    index
+                })
+            }
+            "s" => {
+                state.tags.smoothing = named
+                    .and_then(|n| n.parse::<u32>().ok())
+                    .filter(|s| *s != 0)
+            }
+            "mtllib" => {
+                for file in split_ws {
+                    match load_mtl(&directory.join(file)) {
+                        Ok(materials) => {
+                            for material in &materials {
+                                let index = intern_material(&mut state.materials, material);
+
+                                // A library read after the `usemtl` still defines it.
+                                if let Some(i) =
+                                    state.undefined_materials.iter().position(|u| *u == index)
+                                {
+                                    state.undefined_materials.swap_remove(i);
+                                    state.materials[index] = material.clone();
+                                }
+                            }
+                        }
+                        Err(err) => {
+                            warn!(file = file, error:% = err; "could not read material library")
+                        }
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    // Curves and surfaces are kept as read; `tessellated` turns them into lines and faces.
+    fn parse_free_form(
+        split_ws: &mut SplitWhitespace,
+        state: &mut ParseState,
+        counts: (usize, usize, usize),
+    ) {
+        let Some(first) = split_ws.next() else {
+            return;
+        };
+        let args = split_ws.collect::<Vec<&str>>();
+        let numbers = |args: &[&str]| {
+            args.iter()
+                .map(|a| a.parse::<f64>().ok())
+                .collect::<Option<Vec<f64>>>()
+        };
+
+        match first {
+            "cstype" => {
+                state.free_form_type = FreeFormType::from_args(&args);
+
+                if state.free_form_type.is_none() {
+                    warn!(cstype = args.join(" "); "unsupported free-form type");
+                }
+            }
+            "deg" => {
+                let degrees = args
+                    .iter()
+                    .map(|a| a.parse::<usize>().ok())
+                    .collect::<Option<Vec<usize>>>();
+
+                state.degree = match degrees.as_deref() {
+                    Some([u]) => (*u, 0),
+                    Some([u, v]) => (*u, *v),
+                    _ => state.degree,
+                };
+            }
+            "curv" | "surf" => {
+                state.pending = None;
+
+                let Some(kind) = state.free_form_type else {
+                    warn!(statement = first; "skipping free-form element without a supported cstype");
+                    return;
+                };
+                let n_range = if first == "curv" { 2 } else { 4 };
+
+                if args.len() < n_range {
+                    warn!(statement = first; "skipping free-form element without a parameter range");
+                    return;
+                }
+
+                let range = numbers(&args[..n_range]);
+                let corners = args[n_range..]
+                    .iter()
+                    .map(|corner| {
+                        let mut parts = corner.split('/');
+                        let mut index = |count: usize| {
+                            parts
+                                .next()
+                                .filter(|x| !x.is_empty())
+                                .and_then(|x| resolve_index(x, count))
+                        };
+
+                        (index(counts.0), index(counts.1), index(counts.2))
+                    })
+                    .collect::<Vec<FaceTriplet>>();
+
+                let (Some(range), true) = (range, corners.iter().all(|c| c.0.is_some())) else {
+                    warn!(statement = first; "skipping free-form element with invalid numbers");
+                    return;
+                };
+
+                state.pending = Some(match first {
+                    "curv" => FreeFormElement::Curve(FreeFormCurve {
+                        kind,
+                        degree: state.degree.0,
+                        range: (range[0], range[1]),
+                        control: corners.iter().map(|c| c.0.unwrap()).collect(),
+                        parameters: vec![],
+                        tags: state.tags,
+                    }),
+                    _ => FreeFormElement::Surface(FreeFormSurface {
+                        kind,
+                        degree: state.degree,
+                        range_u: (range[0], range[1]),
+                        range_v: (range[2], range[3]),
+                        control: corners,
+                        parameters_u: vec![],
+                        parameters_v: vec![],
+                        tags: state.tags,
+                    }),
+                });
+            }
+            "parm" => {
+                let Some((direction, values)) = args.split_first() else {
+                    return;
+                };
+                let values = numbers(values).unwrap_or_default();
+
+                match (&mut state.pending, *direction) {
+                    (Some(FreeFormElement::Curve(curve)), "u") => curve.parameters.extend(values),
+                    (Some(FreeFormElement::Surface(surface)), "u") => {
+                        surface.parameters_u.extend(values)
+                    }
+                    (Some(FreeFormElement::Surface(surface)), "v") => {
+                        surface.parameters_v.extend(values)
+                    }
+                    _ => (),
+                }
+            }
+            // 2D curves in a surface's parameter space and the statements that trim with
+            // them; surfaces are tessellated over their whole range instead.
+            "curv2" | "trim" | "hole" | "scrv" | "sp" => {
+                *state.skipped.entry(first.to_string()).or_default() += 1
+            }
+            "end" => match state.pending.take() {
+                Some(FreeFormElement::Curve(curve)) => state.curves.push(curve),
+                Some(FreeFormElement::Surface(surface)) => state.surfaces.push(surface),
+                None => (),
+            },
+            _ => (),
+        }
+    }
+
+    // Indices may count back from the end like in `l` and `p`; a face with one that does
+    // not resolve, or points past what was read, is skipped.
+    fn parse_face_elements(
+        split_ws: &mut SplitWhitespace,
+        f: &mut Vec<FaceElement>,
+        tags: ElementTags,
+        counts: (usize, usize, usize),
+    ) {
+        fn parse_single_triplet(
+            slash_seperated: &str,
+            counts: (usize, usize, usize),
+        ) -> Option<FaceTriplet> {
+            let mut split_slash = slash_seperated.split("/");
 
-            let normal_index = if let Some(ni_str) = ni_option {
-                Some(ni_str.parse::<usize>().unwrap())
-            } else {
-                None
+            // `v//vn` leaves the texture index empty.
+            let mut resolve = |count: usize| match split_slash.next().filter(|x| !x.is_empty()) {
+                Some(token) => resolve_index(token, count).map(Some),
+                None => Some(None),
             };
 
-            (vertex_index, texture_index, normal_index)
+            let vertex_index = resolve(counts.0)?;
+            let texture_index = resolve(counts.1)?;
+            let normal_index = resolve(counts.2)?;
+
+            Some((vertex_index, texture_index, normal_index))
         }
 
         if let Some(first_line) = split_ws.next() {
             if first_line != "f" {
-                return ();
+                return;
             }
         }
 
@@ -305,14 +770,19 @@ impl WavefronObject {
             let p3 = split_ws.next();
 
             if p1.is_none() || p2.is_none() || p3.is_none() {
-                return ();
+                return;
             }
 
             let (sl1, sl2, sl3) = (p1.unwrap(), p2.unwrap(), p3.unwrap());
 
-            let trip1 = parse_single_triplet(sl1);
-            let trip2 = parse_single_triplet(sl2);
-            let trip3 = parse_single_triplet(sl3);
+            let (Some(trip1), Some(trip2), Some(trip3)) = (
+                parse_single_triplet(sl1, counts),
+                parse_single_triplet(sl2, counts),
+                parse_single_triplet(sl3, counts),
+            ) else {
+                warn!(statement = "f"; "skipping element with invalid vertex indices");
+                return;
+            };
 
             let vertex_triplet = (trip1.0, trip2.0, trip3.0);
             let texture_triplet = (trip1.1, trip2.1, trip3.1);
@@ -321,23 +791,39 @@ impl WavefronObject {
             (vertex_triplet, texture_triplet, normal_triplet)
         };
 
-        let face_element = FaceElement::new(vertex_triplet, texture_triplet, normal_triplet);
+        let mut face_element = FaceElement::new(vertex_triplet, texture_triplet, normal_triplet);
+        face_element.tags = tags;
 
         f.push(face_element)
     }
 
-    fn parse_lines(split_ws: &mut SplitWhitespace, l: &mut Vec<Vec<usize>>) {
-        match split_ws.next() {
-            Some(first) => {
-                if first == "l" {
-                    let lines = split_ws
-                        .map(|x| x.parse::<usize>().unwrap())
-                        .collect::<Vec<usize>>();
+    // Lines may carry texture indices as `v/vt`; only the vertex is kept.
+    fn parse_primitives(
+        split_ws: &mut SplitWhitespace,
+        l: &mut Vec<PrimitiveElement>,
+        p: &mut Vec<PrimitiveElement>,
+        tags: ElementTags,
+        n_vertices: usize,
+    ) {
+        let (statement, target) = match split_ws.next() {
+            Some("l") => ("l", l),
+            Some("p") => ("p", p),
+            _ => return,
+        };
 
-                    l.push(lines)
-                }
+        let vertices = split_ws
+            .map(|x| {
+                x.split('/')
+                    .next()
+                    .and_then(|i| resolve_index(i, n_vertices))
+            })
+            .collect::<Option<Vec<usize>>>();
+
+        match vertices {
+            Some(vertices) if !vertices.is_empty() => {
+                target.push(PrimitiveElement { vertices, tags })
             }
-            None => (),
+            _ => warn!(statement = statement; "skipping element with invalid vertex indices"),
         }
     }
 
@@ -349,12 +835,23 @@ impl WavefronObject {
         self.vt.get(*u - 1)
     }
 
+    pub fn get_normal_at_index(&self, u: &usize) -> Option<&Point3<f64>> {
+        self.vn.get(*u - 1)
+    }
+
+    pub fn get_color_at_index(&self, u: &usize) -> Option<&Rgb> {
+        self.vc.get(*u - 1)
+    }
+
+    pub fn has_vertex_colors(&self) -> bool {
+        !self.v.is_empty() && self.vc.len() == self.v.len()
+    }
+
     pub fn get_vertex_impl(&self, u: usize) -> Vertex {
         self.v[u]
     }
 
     pub fn get_vert_triplets_from_face_elements(&self) -> Vec<Vec3Unsigned<usize>> {
-
         self.f
             .iter()
             .map(|x| x.vertex_triplet)
@@ -372,6 +869,301 @@ impl WavefronObject {
             .collect::<Vec<Vec3Unsigned<usize>>>()
     }
 
+    pub fn get_normal_triplets_from_elements(&self) -> Vec<Vec3Unsigned<usize>> {
+        self.f
+            .iter()
+            .map(|x| x.normal_triplet)
+            .filter(|x| x.0.is_some() && x.1.is_some() && x.2.is_some())
+            .map(|x| Vec3Unsigned::new(x.0.unwrap(), x.1.unwrap(), x.2.unwrap()))
+            .collect::<Vec<Vec3Unsigned<usize>>>()
+    }
+
+    // Normals go through the inverse transpose so they stay perpendicular under
+    // non-uniform scale.
+    pub fn transformed(&self, linear: Matrix3, translation: Point3<f64>) -> WavefronObject {
+        let normal_matrix = linear
+            .inverse()
+            .unwrap_or_else(Matrix3::identity)
+            .transpose();
+
+        let mut model = self.clone();
+
+        for vertex in model.v.iter_mut() {
+            vertex.xyz = linear * vertex.xyz + translation;
+        }
+
+        for normal in model.vn.iter_mut() {
+            *normal = (normal_matrix * *normal).normalize();
+        }
+
+        model
+    }
+
+    // Indices in `other` are shifted past this object's elements, so the merged object
+    // draws both meshes in one pass.
+    pub fn append(&mut self, other: &WavefronObject) {
+        let (nv, nvt, nvn) = (self.v.len(), self.vt.len(), self.vn.len());
+
+        // Colors stay parallel to the vertices; whichever side has none gets white.
+        if self.has_vertex_colors() || other.has_vertex_colors() {
+            self.vc.resize(nv, [255; 3]);
+            self.vc
+                .extend((0..other.v.len()).map(|i| other.vc.get(i).copied().unwrap_or([255; 3])));
+        }
+
+        let shift =
+            |t: FaceTriplet, n: usize| (t.0.map(|i| i + n), t.1.map(|i| i + n), t.2.map(|i| i + n));
+
+        self.v.extend_from_slice(&other.v);
+        self.vt.extend_from_slice(&other.vt);
+        self.vn.extend_from_slice(&other.vn);
+        self.vp.extend_from_slice(&other.vp);
+
+        // Names are merged into this object's tables, so equal names share an index.
+        let objects = other
+            .objects
+            .iter()
+            .map(|n| intern(&mut self.objects, n))
+            .collect::<Vec<usize>>();
+        let groups = other
+            .groups
+            .iter()
+            .map(|n| intern(&mut self.groups, n))
+            .collect::<Vec<usize>>();
+        let materials = other
+            .materials
+            .iter()
+            .map(|m| intern_material(&mut self.materials, m))
+            .collect::<Vec<usize>>();
+
+        let retag = |tags: ElementTags| ElementTags {
+            object: tags.object.map(|i| objects[i]),
+            group: tags.group.map(|i| groups[i]),
+            material: tags.material.map(|i| materials[i]),
+            smoothing: tags.smoothing,
+        };
+
+        self.f.extend(other.f.iter().map(|face| FaceElement {
+            vertex_triplet: shift(face.vertex_triplet, nv),
+            texture_triplet: shift(face.texture_triplet, nvt),
+            normal_triplet: shift(face.normal_triplet, nvn),
+            tags: retag(face.tags),
+        }));
+
+        self.l.extend(other.l.iter().map(|line| PrimitiveElement {
+            vertices: line.vertices.iter().map(|i| i + nv).collect(),
+            tags: retag(line.tags),
+        }));
+
+        self.p.extend(other.p.iter().map(|point| PrimitiveElement {
+            vertices: point.vertices.iter().map(|i| i + nv).collect(),
+            tags: retag(point.tags),
+        }));
+
+        self.curves
+            .extend(other.curves.iter().map(|curve| FreeFormCurve {
+                control: curve.control.iter().map(|i| i + nv).collect(),
+                tags: retag(curve.tags),
+                ..curve.clone()
+            }));
+
+        self.surfaces.extend(other.surfaces.iter().map(|surface| {
+            FreeFormSurface {
+                control: surface
+                    .control
+                    .iter()
+                    .map(|corner| {
+                        (
+                            corner.0.map(|i| i + nv),
+                            corner.1.map(|i| i + nvt),
+                            corner.2.map(|i| i + nvn),
+                        )
+                    })
+                    .collect(),
+                tags: retag(surface.tags),
+                ..surface.clone()
+            }
+        }));
+    }
+
+    // Control points with their weight folded in; a missing `w` is 1.
+    fn get_homogeneous_control(&self, indices: &[usize], rational: bool) -> Option<Vec<[f64; 4]>> {
+        indices
+            .iter()
+            .map(|i| {
+                let vertex = self.get_vertex_at_index(i)?;
+                let Point3(x, y, z) = vertex.xyz;
+                let w = match rational {
+                    true => vertex.w.unwrap_or(1.0),
+                    false => 1.0,
+                };
+
+                Some([x * w, y * w, z * w, w])
+            })
+            .collect()
+    }
+
+    // Curves become `l` polylines and surfaces a grid of triangles with parametric UVs and
+    // normals, each sampled `resolution` times per direction. Elements whose degree,
+    // parameters and control points do not add up are dropped with a warning.
+    pub fn tessellated(&self, resolution: usize) -> WavefronObject {
+        let mut model = self.clone();
+        let resolution = resolution.max(1);
+
+        model.curves.clear();
+        model.surfaces.clear();
+
+        for curve in &self.curves {
+            let points = self
+                .get_homogeneous_control(&curve.control, curve.kind.rational)
+                .and_then(|control| tessellate_curve(curve, &control, resolution));
+
+            let Some(points) = points else {
+                warn!(degree = curve.degree, control = curve.control.len(); "skipping invalid curve");
+                continue;
+            };
+
+            let first = model.v.len() + 1;
+
+            model
+                .v
+                .extend(points.iter().map(|p| Vertex { xyz: *p, w: None }));
+            model.l.push(PrimitiveElement {
+                vertices: (first..first + points.len()).collect(),
+                tags: curve.tags,
+            });
+        }
+
+        for surface in &self.surfaces {
+            let control = surface
+                .control
+                .iter()
+                .map(|c| c.0.unwrap())
+                .collect::<Vec<usize>>();
+            let grid = self
+                .get_homogeneous_control(&control, surface.kind.rational)
+                .and_then(|control| tessellate_surface(surface, &control, resolution));
+
+            let Some(grid) = grid else {
+                warn!(degree:? = surface.degree, control = surface.control.len(); "skipping invalid surface");
+                continue;
+            };
+
+            let side = resolution + 1;
+            let at = |i: usize, j: usize| grid[i.min(resolution) + j.min(resolution) * side];
+            let (v0, vt0, vn0) = (model.v.len() + 1, model.vt.len() + 1, model.vn.len() + 1);
+
+            for j in 0..side {
+                for i in 0..side {
+                    // Central differences, one-sided at the borders.
+                    let du = at(i + 1, j) - at(i.saturating_sub(1), j);
+                    let dv = at(i, j + 1) - at(i, j.saturating_sub(1));
+                    let normal = du ^ dv;
+
+                    model.v.push(Vertex {
+                        xyz: at(i, j),
+                        w: None,
+                    });
+                    model.vt.push(TextureUV::new(
+                        i as f64 / resolution as f64,
+                        Some(j as f64 / resolution as f64),
+                        None,
+                    ));
+                    model.vn.push(match normal.get_length() > f64::EPSILON {
+                        true => normal.normalize(),
+                        false => Point3(0.0, 0.0, 1.0),
+                    });
+                }
+            }
+
+            for j in 0..resolution {
+                for i in 0..resolution {
+                    let corner = |di: usize, dj: usize| (i + di) + (j + dj) * side;
+
+                    for t in [
+                        [corner(0, 0), corner(1, 0), corner(1, 1)],
+                        [corner(0, 0), corner(1, 1), corner(0, 1)],
+                    ] {
+                        let triplet =
+                            |base: usize| (Some(base + t[0]), Some(base + t[1]), Some(base + t[2]));
+
+                        model.f.push(FaceElement {
+                            vertex_triplet: triplet(v0),
+                            texture_triplet: triplet(vt0),
+                            normal_triplet: triplet(vn0),
+                            tags: surface.tags,
+                        });
+                    }
+                }
+            }
+        }
+
+        if !self.curves.is_empty() || !self.surfaces.is_empty() {
+            info!(
+                curves = self.curves.len(),
+                surfaces = self.surfaces.len(),
+                resolution = resolution,
+                faces = model.f.len(),
+                lines = model.l.len();
+                "free-form geometry tessellated"
+            );
+        }
+
+        model
+    }
+
+    pub fn get_curves(&self) -> &[FreeFormCurve] {
+        &self.curves
+    }
+
+    pub fn get_surfaces(&self) -> &[FreeFormSurface] {
+        &self.surfaces
+    }
+
+    pub fn get_vertices(&self) -> &[Vertex] {
+        &self.v
+    }
+
+    pub fn get_uvs(&self) -> &[TextureUV] {
+        &self.vt
+    }
+
+    pub fn get_normals(&self) -> &[Point3<f64>] {
+        &self.vn
+    }
+
+    pub fn get_space_vertices(&self) -> &[SpaceVertex] {
+        &self.vp
+    }
+
+    pub fn get_faces(&self) -> &[FaceElement] {
+        &self.f
+    }
+
+    pub fn get_lines(&self) -> &[PrimitiveElement] {
+        &self.l
+    }
+
+    pub fn get_points(&self) -> &[PrimitiveElement] {
+        &self.p
+    }
+
+    pub fn get_colors(&self) -> &[Rgb] {
+        &self.vc
+    }
+
+    pub fn get_objects(&self) -> &[String] {
+        &self.objects
+    }
+
+    pub fn get_groups(&self) -> &[String] {
+        &self.groups
+    }
+
+    pub fn get_materials(&self) -> &[MtlMaterial] {
+        &self.materials
+    }
+
     pub fn get_len_vertices(&self) -> usize {
         self.v.len()
     }
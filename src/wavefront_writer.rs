@@ -0,0 +1,280 @@
+use std::fmt::Write;
+use std::path::{Component, Path, PathBuf};
+
+use log::info;
+
+use crate::linear_algebra::Point3;
+use crate::wavefront_mtl::{write_mtl_string, MtlMaterial};
+use crate::wavefront_parser::{ElementTags, WavefronObject};
+
+// `{}` prints the shortest decimal that parses back to the same f64, which is what
+// makes a write/parse round trip lossless.
+fn write_optional(out: &mut String, value: Option<f64>) {
+    if let Some(value) = value {
+        write!(out, " {}", value).unwrap();
+    }
+}
+
+fn write_corner(out: &mut String, v: Option<usize>, vt: Option<usize>, vn: Option<usize>) {
+    let index = |i: Option<usize>| i.map(|i| i.to_string()).unwrap_or_default();
+
+    write!(out, " {}", index(v)).unwrap();
+
+    match (vt, vn) {
+        (None, None) => (),
+        (vt, None) => write!(out, "/{}", index(vt)).unwrap(),
+        (vt, vn) => write!(out, "/{}/{}", index(vt), index(vn)).unwrap(),
+    }
+}
+
+fn write_parameters(out: &mut String, direction: &str, values: &[f64]) {
+    write!(out, "parm {}", direction).unwrap();
+
+    for value in values {
+        write!(out, " {}", value).unwrap();
+    }
+
+    out.push('\n');
+}
+
+// Emits the `o`, `g`, `usemtl` and `s` statements that differ from the current state.
+fn write_tags(
+    out: &mut String,
+    model: &WavefronObject,
+    current: &mut ElementTags,
+    tags: ElementTags,
+) {
+    let name = |names: &[String], i: Option<usize>| {
+        i.map(|i| format!(" {}", names[i])).unwrap_or_default()
+    };
+
+    if tags.object != current.object {
+        writeln!(out, "o{}", name(model.get_objects(), tags.object)).unwrap();
+    }
+
+    if tags.group != current.group {
+        writeln!(out, "g{}", name(model.get_groups(), tags.group)).unwrap();
+    }
+
+    if tags.material != current.material {
+        let material = tags
+            .material
+            .map(|i| format!(" {}", model.get_materials()[i].name))
+            .unwrap_or_default();
+
+        writeln!(out, "usemtl{}", material).unwrap();
+    }
+
+    if tags.smoothing != current.smoothing {
+        match tags.smoothing {
+            Some(s) => writeln!(out, "s {}", s).unwrap(),
+            None => writeln!(out, "s off").unwrap(),
+        }
+    }
+
+    *current = tags;
+}
+
+// Vertex data first, then faces, lines, points and free-form elements, each under the state it
+// was read with.
+pub fn write_obj_string(model: &WavefronObject, mtllib: Option<&str>) -> String {
+    let mut out = String::new();
+    let colors = model.get_colors();
+
+    if let Some(mtllib) = mtllib {
+        writeln!(out, "mtllib {}", mtllib).unwrap();
+    }
+
+    for (i, vertex) in model.get_vertices().iter().enumerate() {
+        let Point3(x, y, z) = vertex.xyz;
+
+        write!(out, "v {} {} {}", x, y, z).unwrap();
+
+        match (colors.get(i), vertex.w) {
+            (Some(color), _) if model.has_vertex_colors() => {
+                for c in color {
+                    write!(out, " {}", *c as f64 / 255.0).unwrap();
+                }
+            }
+            (_, w) => write_optional(&mut out, w),
+        }
+
+        out.push('\n');
+    }
+
+    for (keyword, values) in [
+        (
+            "vt",
+            model
+                .get_uvs()
+                .iter()
+                .map(|uv| uv.unravel_uvw())
+                .collect::<Vec<_>>(),
+        ),
+        (
+            "vp",
+            model
+                .get_space_vertices()
+                .iter()
+                .map(|vp| vp.unravel_uvw())
+                .collect::<Vec<_>>(),
+        ),
+    ] {
+        for (u, v, w) in values {
+            write!(out, "{} {}", keyword, u).unwrap();
+            write_optional(&mut out, v);
+            write_optional(&mut out, w);
+            out.push('\n');
+        }
+    }
+
+    for Point3(x, y, z) in model.get_normals() {
+        writeln!(out, "vn {} {} {}", x, y, z).unwrap();
+    }
+
+    // Elements are written by kind rather than in file order, so name the objects and
+    // groups up front; a reader then numbers them the same way this object does.
+    for (keyword, names) in [("o", model.get_objects()), ("g", model.get_groups())] {
+        if !names.is_empty() {
+            for name in names {
+                writeln!(out, "{} {}", keyword, name).unwrap();
+            }
+
+            writeln!(out, "{}", keyword).unwrap();
+        }
+    }
+
+    let mut current = ElementTags::default();
+
+    for face in model.get_faces() {
+        let (v, vt, vn) = face.get_triplets();
+
+        write_tags(&mut out, model, &mut current, face.get_tags());
+        out.push('f');
+        write_corner(&mut out, v.0, vt.0, vn.0);
+        write_corner(&mut out, v.1, vt.1, vn.1);
+        write_corner(&mut out, v.2, vt.2, vn.2);
+        out.push('\n');
+    }
+
+    for (keyword, elements) in [("l", model.get_lines()), ("p", model.get_points())] {
+        for element in elements {
+            write_tags(&mut out, model, &mut current, element.tags);
+            out.push_str(keyword);
+
+            for i in &element.vertices {
+                write!(out, " {}", i).unwrap();
+            }
+
+            out.push('\n');
+        }
+    }
+
+    for curve in model.get_curves() {
+        write_tags(&mut out, model, &mut current, curve.tags);
+        writeln!(out, "cstype {}", curve.kind.get_args()).unwrap();
+        writeln!(out, "deg {}", curve.degree).unwrap();
+        write!(out, "curv {} {}", curve.range.0, curve.range.1).unwrap();
+
+        for i in &curve.control {
+            write!(out, " {}", i).unwrap();
+        }
+
+        out.push('\n');
+        write_parameters(&mut out, "u", &curve.parameters);
+        writeln!(out, "end").unwrap();
+    }
+
+    for surface in model.get_surfaces() {
+        let (degree_u, degree_v) = surface.degree;
+
+        write_tags(&mut out, model, &mut current, surface.tags);
+        writeln!(out, "cstype {}", surface.kind.get_args()).unwrap();
+        writeln!(out, "deg {} {}", degree_u, degree_v).unwrap();
+        write!(
+            out,
+            "surf {} {} {} {}",
+            surface.range_u.0, surface.range_u.1, surface.range_v.0, surface.range_v.1
+        )
+        .unwrap();
+
+        for (v, vt, vn) in &surface.control {
+            write_corner(&mut out, *v, *vt, *vn);
+        }
+
+        out.push('\n');
+        write_parameters(&mut out, "u", &surface.parameters_u);
+        write_parameters(&mut out, "v", &surface.parameters_v);
+        writeln!(out, "end").unwrap();
+    }
+
+    out
+}
+
+// The path from `base` to `target`, both taken from the working directory when relative.
+// Targets on another root (a different drive on Windows) stay absolute.
+fn relative_path(target: &Path, base: &Path) -> std::io::Result<PathBuf> {
+    let target = std::path::absolute(target)?;
+    let base = std::path::absolute(base)?;
+
+    let target = target.components().collect::<Vec<Component>>();
+    let base = base.components().collect::<Vec<Component>>();
+
+    if target.first() != base.first() {
+        return Ok(target.iter().collect());
+    }
+
+    let shared = target.iter().zip(&base).take_while(|(t, b)| t == b).count();
+
+    let mut relative = PathBuf::new();
+
+    for _ in shared..base.len() {
+        relative.push("..");
+    }
+
+    relative.extend(&target[shared..]);
+
+    Ok(relative)
+}
+
+// Materials go to a sibling .mtl with the same stem. Texture paths are rewritten relative
+// to that file, so the copy still finds the textures it was read with.
+pub fn write_obj(model: &WavefronObject, path: &Path) -> std::io::Result<()> {
+    let mtl_directory = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let materials = model
+        .get_materials()
+        .iter()
+        .map(|m| {
+            m.with_map_files(|file| match relative_path(Path::new(file), mtl_directory) {
+                Ok(relative) => relative.to_string_lossy().into_owned(),
+                Err(_) => file.to_string(),
+            })
+        })
+        .collect::<Vec<MtlMaterial>>();
+    let materials = materials.as_slice();
+
+    let mtl_path = path.with_extension("mtl");
+    let mtllib = match materials.is_empty() {
+        true => None,
+        false => mtl_path.file_name().and_then(|n| n.to_str()),
+    };
+
+    if mtllib.is_some() {
+        std::fs::write(&mtl_path, write_mtl_string(materials))?;
+    }
+
+    std::fs::write(path, write_obj_string(model, mtllib))?;
+
+    info!(
+        path:% = path.display(),
+        vertices = model.get_n_vertices(),
+        faces = model.get_n_faces(),
+        materials = materials.len();
+        "wavefront object written"
+    );
+
+    Ok(())
+}
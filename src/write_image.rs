@@ -0,0 +1,92 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::image_canvas::{Canvas, CHANNELS};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    Tga,
+    Ppm,
+}
+
+impl ImageFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+
+        match extension.as_str() {
+            "tga" => Some(ImageFormat::Tga),
+            "ppm" => Some(ImageFormat::Ppm),
+            _ => None,
+        }
+    }
+
+    pub fn get_extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Tga => "tga",
+            ImageFormat::Ppm => "ppm",
+        }
+    }
+}
+
+// Uncompressed 24-bit TGA with a bottom-left origin, so canvas rows go out in storage order.
+pub fn encode_tga<W: Write>(writer: &mut W, image: &Canvas) -> io::Result<()> {
+    let (w, h) = image.get_size();
+
+    let (w16, h16) = match (u16::try_from(w), u16::try_from(h)) {
+        (Ok(w16), Ok(h16)) => (w16, h16),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}x{} is too large for a TGA image", w, h),
+            ))
+        }
+    };
+
+    let mut header = [0u8; 18];
+    header[2] = 2;
+    header[12..14].copy_from_slice(&w16.to_le_bytes());
+    header[14..16].copy_from_slice(&h16.to_le_bytes());
+    header[16] = 24;
+
+    writer.write_all(&header)?;
+
+    let mut bgr = vec![0u8; w * CHANNELS];
+
+    for row in image.rows() {
+        for (dst, src) in bgr
+            .chunks_exact_mut(CHANNELS)
+            .zip(row.chunks_exact(CHANNELS))
+        {
+            dst.copy_from_slice(&[src[2], src[1], src[0]]);
+        }
+
+        writer.write_all(&bgr)?;
+    }
+
+    Ok(())
+}
+
+// Binary PPM is stored top to bottom, so the rows are written in reverse.
+pub fn encode_ppm<W: Write>(writer: &mut W, image: &Canvas) -> io::Result<()> {
+    let (w, h) = image.get_size();
+
+    write!(writer, "P6\n{} {}\n255\n", w, h)?;
+
+    for row in image.rows().rev() {
+        writer.write_all(row)?;
+    }
+
+    Ok(())
+}
+
+pub fn write_image(path: PathBuf, image: &Canvas, format: ImageFormat) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    match format {
+        ImageFormat::Tga => encode_tga(&mut writer, image)?,
+        ImageFormat::Ppm => encode_ppm(&mut writer, image)?,
+    }
+
+    writer.flush()
+}